@@ -1,5 +1,3 @@
-mod raw_guard;
-
 mod main {
     use std::io::{Read as _, Write as _};
     use std::os::fd::AsFd as _;
@@ -8,7 +6,7 @@ mod main {
         child: &mut std::process::Child,
         pty: &mut pty_process::blocking::Pty,
     ) {
-        let _raw = super::raw_guard::RawGuard::new();
+        let _raw = pty_process::passthrough::RawMode::new().unwrap();
         let mut buf = [0_u8; 4096];
         let stdin = std::io::stdin();
         let stdin_fd = stdin.as_fd();
@@ -1,35 +0,0 @@
-use std::os::fd::AsFd as _;
-
-pub struct RawGuard {
-    termios: nix::sys::termios::Termios,
-}
-
-impl RawGuard {
-    #[allow(dead_code)]
-    pub fn new() -> Self {
-        let stdin = std::io::stdin();
-        let stdin = stdin.as_fd();
-        let termios = nix::sys::termios::tcgetattr(stdin).unwrap();
-        let mut termios_raw = termios.clone();
-        nix::sys::termios::cfmakeraw(&mut termios_raw);
-        nix::sys::termios::tcsetattr(
-            stdin,
-            nix::sys::termios::SetArg::TCSANOW,
-            &termios_raw,
-        )
-        .unwrap();
-        Self { termios }
-    }
-}
-
-impl Drop for RawGuard {
-    fn drop(&mut self) {
-        let stdin = std::io::stdin();
-        let stdin = stdin.as_fd();
-        let _ = nix::sys::termios::tcsetattr(
-            stdin,
-            nix::sys::termios::SetArg::TCSANOW,
-            &self.termios,
-        );
-    }
-}
@@ -0,0 +1,128 @@
+// usage:
+//   session serve <socket> <command> [args...]
+//   session attach <socket>
+// press ^\ to detach
+
+#[cfg(feature = "async")]
+mod main {
+    use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+    const DETACH_KEY: u8 = 0x1c;
+
+    pub async fn serve(
+        socket: &str,
+        cmd: &str,
+        args: &[String],
+    ) -> std::result::Result<
+        std::process::ExitStatus,
+        Box<dyn std::error::Error>,
+    > {
+        let (pty, pts) = pty_process::open()?;
+        pty.resize(pty_process::Size::new(24, 80))?;
+        let child = pty_process::Command::new(cmd).args(args).spawn(pts)?;
+        let listener = tokio::net::UnixListener::bind(socket)?;
+        let status = pty_process::session::SessionServer::new(child, pty)
+            .run(listener)
+            .await;
+        let _ = std::fs::remove_file(socket);
+        Ok(status?)
+    }
+
+    pub async fn attach(
+        socket: &str,
+    ) -> std::result::Result<
+        Option<std::process::ExitStatus>,
+        Box<dyn std::error::Error>,
+    > {
+        let client =
+            pty_process::session::SessionClient::connect(socket).await?;
+        let (mut reader, mut writer) = client.into_split();
+        let _raw = pty_process::passthrough::RawMode::new()?;
+
+        let mut stdout = tokio::io::stdout();
+        let output = tokio::spawn(async move {
+            while let Some(event) = reader.recv().await? {
+                match event {
+                    pty_process::session::SessionEvent::Output(data) => {
+                        stdout.write_all(&data).await?;
+                        stdout.flush().await?;
+                    }
+                    pty_process::session::SessionEvent::Exit(status) => {
+                        return Ok(Some(status));
+                    }
+                    _ => {}
+                }
+            }
+            Ok::<_, std::io::Error>(None)
+        });
+
+        let mut winch = tokio::signal::unix::signal(
+            tokio::signal::unix::SignalKind::window_change(),
+        )?;
+        let mut stdin = tokio::io::stdin();
+        let mut buf = [0_u8; 4096];
+        writer.resize(host_size()?).await?;
+        tokio::pin!(output);
+        loop {
+            tokio::select! {
+                bytes = stdin.read(&mut buf) => {
+                    let bytes = bytes?;
+                    if bytes == 0 {
+                        break;
+                    }
+                    let data = &buf[..bytes];
+                    if let Some(i) = data.iter().position(|&c| c == DETACH_KEY) {
+                        writer.send_input(&data[..i]).await?;
+                        break;
+                    }
+                    writer.send_input(data).await?;
+                }
+                _ = winch.recv() => writer.resize(host_size()?).await?,
+                status = &mut output => return Ok(status??),
+            }
+        }
+        writer.detach().await?;
+        Ok(None)
+    }
+
+    fn host_size() -> std::io::Result<pty_process::Size> {
+        Ok(rustix::termios::tcgetwinsize(rustix::stdio::stdin())?.into())
+    }
+}
+
+#[cfg(feature = "async")]
+#[tokio::main]
+async fn main() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let status = match args.as_slice() {
+        [mode, socket, cmd, args @ ..] if mode == "serve" => {
+            Some(main::serve(socket, cmd, args).await.unwrap())
+        }
+        [mode, socket] if mode == "attach" => {
+            let status = main::attach(socket).await.unwrap();
+            if status.is_none() {
+                eprintln!("\r\n[detached]\r");
+            }
+            status
+        }
+        _ => {
+            eprintln!("usage: session serve <socket> <command> [args...]");
+            eprintln!("       session attach <socket>");
+            std::process::exit(1);
+        }
+    };
+    if let Some(status) = status {
+        std::process::exit(
+            status
+                .code()
+                .unwrap_or_else(|| status.signal().unwrap_or(0) + 128),
+        );
+    }
+}
+
+#[cfg(not(feature = "async"))]
+fn main() {
+    unimplemented!()
+}
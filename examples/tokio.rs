@@ -1,5 +1,3 @@
-mod raw_guard;
-
 #[cfg(feature = "async")]
 mod main {
     use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
@@ -8,7 +6,7 @@ mod main {
         child: &mut tokio::process::Child,
         pty: &mut pty_process::Pty,
     ) -> std::result::Result<(), Box<dyn std::error::Error>> {
-        let _raw = super::raw_guard::RawGuard::new();
+        let _raw = pty_process::passthrough::RawMode::new().unwrap();
 
         let mut in_buf = [0_u8; 4096];
         let mut out_buf = [0_u8; 4096];
@@ -0,0 +1,164 @@
+// a minimal parser for the escape sequences that can appear in terminal
+// output (and input), loosely based on the state machine described at
+// https://vt100.net/emu/dec_ansi_parser
+
+// sequences longer than these are truncated, to avoid buffering
+// indefinitely when given garbage
+const MAX_CSI: usize = 256;
+const MAX_STRING: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum State {
+    #[default]
+    Ground,
+    Escape,
+    EscapeIntermediate,
+    Csi,
+    String,
+    StringEscape,
+}
+
+impl State {
+    pub fn next(self, byte: u8) -> Self {
+        // an ESC within a string which isn't part of a string terminator
+        // ends the string and starts a new sequence
+        let state = if self == Self::StringEscape && byte != b'\\' {
+            Self::Escape
+        } else {
+            self
+        };
+        match (state, byte) {
+            // ESC always starts a new sequence, except within a string,
+            // where it may be the start of the string terminator
+            (Self::String, 0x1b) => Self::StringEscape,
+            (_, 0x1b) => Self::Escape,
+            // CAN and SUB abort any sequence in progress
+            (Self::Csi | Self::String, 0x18 | 0x1a)
+            | (Self::Csi, 0x40..=0x7e)
+            | (Self::String, 0x07) => Self::Ground,
+            (Self::Escape, b'[') | (Self::Csi, _) => Self::Csi,
+            (Self::Escape, b']' | b'P' | b'X' | b'^' | b'_')
+            | (Self::String, _) => Self::String,
+            (Self::Escape | Self::EscapeIntermediate, 0x20..=0x2f) => {
+                Self::EscapeIntermediate
+            }
+            // anything else either finishes a sequence or isn't part of one
+            _ => Self::Ground,
+        }
+    }
+
+    // returns true if the given byte (which causes a transition from this
+    // state) starts a new sequence
+    pub fn starts_sequence(self, byte: u8) -> bool {
+        match self {
+            Self::String => false,
+            Self::StringEscape => byte != b'\\',
+            _ => byte == 0x1b,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Action<'a> {
+    // a printable byte (which may be part of a multibyte character)
+    Print(u8),
+    // a C0 control character (or DEL)
+    Control(u8),
+    // the intermediate and final bytes of an escape sequence
+    Escape(&'a [u8]),
+    // the parameter, intermediate, and final bytes of a CSI sequence
+    Csi(&'a [u8]),
+    // the introducer and contents of an OSC, DCS, SOS, PM, or APC string
+    String(u8, &'a [u8]),
+}
+
+#[derive(Debug, Default)]
+pub struct Parser {
+    state: State,
+    kind: u8,
+    buf: Vec<u8>,
+}
+
+impl Parser {
+    pub fn in_sequence(&self) -> bool {
+        self.state != State::Ground
+    }
+
+    pub fn advance(&mut self, byte: u8) -> Option<Action<'_>> {
+        let prev = self.state;
+        self.state = prev.next(byte);
+        // strings which are terminated by something other than ST are
+        // discarded, along with the terminator
+        let prev = if prev == State::StringEscape && byte != b'\\' {
+            self.buf.clear();
+            State::Escape
+        } else {
+            prev
+        };
+        match (prev, self.state) {
+            (State::Ground, State::Ground) => {
+                if byte < 0x20 || byte == 0x7f {
+                    Some(Action::Control(byte))
+                } else {
+                    Some(Action::Print(byte))
+                }
+            }
+            (_, State::Escape) => {
+                self.buf.clear();
+                None
+            }
+            (State::Escape, State::Csi) => None,
+            (State::Escape, State::String) => {
+                self.kind = byte;
+                None
+            }
+            (State::Csi, State::Csi)
+            | (State::Escape | State::EscapeIntermediate, _)
+                if self.buf.len() < MAX_CSI =>
+            {
+                self.buf.push(byte);
+                match self.state {
+                    State::Ground if (0x30..=0x7e).contains(&byte) => {
+                        Some(Action::Escape(&self.buf))
+                    }
+                    _ => None,
+                }
+            }
+            (State::String, State::String) => {
+                if self.buf.len() < MAX_STRING {
+                    self.buf.push(byte);
+                }
+                None
+            }
+            (State::Csi, State::Ground) if (0x40..=0x7e).contains(&byte) => {
+                self.buf.push(byte);
+                Some(Action::Csi(&self.buf))
+            }
+            (State::String | State::StringEscape, State::Ground)
+                if byte == 0x07 || byte == b'\\' =>
+            {
+                Some(Action::String(self.kind, &self.buf))
+            }
+            _ => None,
+        }
+    }
+}
+
+// returns the length of the prefix of buf which doesn't end in the middle
+// of an escape sequence, unless the incomplete sequence at the end is
+// longer than max_pending
+pub fn complete_len(buf: &[u8], max_pending: usize) -> usize {
+    let mut state = State::Ground;
+    let mut start = 0;
+    for (i, &byte) in buf.iter().enumerate() {
+        if state.starts_sequence(byte) {
+            start = if byte == 0x1b { i } else { i - 1 };
+        }
+        state = state.next(byte);
+    }
+    if state == State::Ground || buf.len() - start > max_pending {
+        buf.len()
+    } else {
+        start
+    }
+}
@@ -0,0 +1,175 @@
+/// Wrapper around [`std::process::Child`]
+///
+/// Dereferences to the wrapped [`std::process::Child`], so all of its
+/// methods and fields are available directly.
+#[derive(Debug)]
+pub struct Child {
+    inner: Option<std::process::Child>,
+    // opened on first use, so that children which never need it don't hold
+    // an extra file descriptor
+    #[cfg(target_os = "linux")]
+    pidfd: std::sync::OnceLock<Option<crate::Pidfd>>,
+    kill_on_drop: bool,
+    kill_grace_period: std::time::Duration,
+}
+
+impl Child {
+    pub(crate) fn new(
+        inner: std::process::Child,
+        kill_on_drop: bool,
+        kill_grace_period: std::time::Duration,
+    ) -> Self {
+        Self {
+            inner: Some(inner),
+            #[cfg(target_os = "linux")]
+            pidfd: std::sync::OnceLock::new(),
+            kill_on_drop,
+            kill_grace_period,
+        }
+    }
+
+    /// Returns a [`Pidfd`](crate::Pidfd) referring to the child, or `None`
+    /// if the kernel doesn't support pidfds or the child had already been
+    /// reaped when this was first called. The pidfd is opened on the first
+    /// call, and kept until the `Child` is dropped.
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn pidfd(&self) -> Option<&crate::Pidfd> {
+        self.pidfd
+            .get_or_init(|| {
+                crate::Pidfd::open(rustix::process::Pid::from_child(self))
+            })
+            .as_ref()
+    }
+
+    /// Sends the given signal to the child. On Linux, this uses the
+    /// child's [`pidfd`](Self::pidfd) when available, so the signal can't
+    /// be delivered to an unrelated process which reused the child's pid.
+    ///
+    /// # Errors
+    /// Returns an error if sending the signal fails, including if the child
+    /// has already been reaped.
+    pub fn signal(
+        &self,
+        signal: rustix::process::Signal,
+    ) -> crate::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(pidfd) = self.pidfd() {
+            return pidfd.signal(signal);
+        }
+        let pid = rustix::process::Pid::from_child(self);
+        // once the child has been reaped, its pid may belong to an
+        // unrelated process
+        if !crate::sys::is_unreaped_child(pid) {
+            return Err(rustix::io::Errno::SRCH.into());
+        }
+        rustix::process::kill_process(pid, signal)?;
+        Ok(())
+    }
+
+    /// Sends `SIGHUP` and `SIGTERM` to every process in the child's process
+    /// group, waits up to the configured grace period (see
+    /// [`Command::kill_grace_period`](crate::blocking::Command::kill_grace_period))
+    /// for them to exit, sends `SIGKILL` to any that remain, and then waits
+    /// for the child to exit.
+    ///
+    /// Since the child is the leader of a new session, this will also reach
+    /// any processes it spawned, unless they have moved themselves into a
+    /// different process group. The process group is signalled even if the
+    /// child itself has already exited, to clean up anything it left behind.
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child fails.
+    pub fn kill_session(
+        &mut self,
+    ) -> crate::Result<std::process::ExitStatus> {
+        let grace_period = self.kill_grace_period;
+        #[cfg(target_os = "linux")]
+        let leader = self
+            .pidfd
+            .get_or_init(|| {
+                crate::Pidfd::open(rustix::process::Pid::from_child(self))
+            })
+            .as_ref()
+            .map(std::os::fd::AsFd::as_fd);
+        #[cfg(not(target_os = "linux"))]
+        let leader = None;
+        let child = self.inner.as_mut().unwrap_or_else(|| unreachable!());
+        // the rest of the session may still be running even if the child
+        // itself has already exited
+        let status = child.try_wait()?;
+        crate::sys::teardown_session(
+            rustix::process::Pid::from_child(child),
+            grace_period,
+            leader,
+            || matches!(child.try_wait(), Ok(Some(_)) | Err(_)),
+        );
+        match status {
+            Some(status) => Ok(status),
+            None => Ok(child.wait()?),
+        }
+    }
+
+    /// Gracefully shuts down the child by hanging up the given pty (see
+    /// [`Pty::hangup`](crate::blocking::Pty::hangup)), which gives
+    /// interactive programs such as shells and editors a chance to save
+    /// their state and exit. If the child has not exited within the given
+    /// timeout, its session is torn down as described in
+    /// [`kill_session`](Self::kill_session).
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child fails.
+    pub fn shutdown(
+        &mut self,
+        pty: crate::blocking::Pty,
+        timeout: std::time::Duration,
+    ) -> crate::Result<std::process::ExitStatus> {
+        pty.hangup();
+        let deadline = std::time::Instant::now() + timeout;
+        loop {
+            if let Some(status) = self.inner_mut().try_wait()? {
+                return Ok(status);
+            }
+            if std::time::Instant::now() >= deadline {
+                return self.kill_session();
+            }
+            std::thread::sleep(crate::sys::SESSION_POLL_INTERVAL);
+        }
+    }
+
+    /// Returns the wrapped [`std::process::Child`]. The child will no longer
+    /// be killed when dropped, even if
+    /// [`Command::kill_on_drop`](crate::blocking::Command::kill_on_drop) was
+    /// set.
+    #[must_use]
+    pub fn into_inner(mut self) -> std::process::Child {
+        self.inner.take().unwrap_or_else(|| unreachable!())
+    }
+
+    fn inner_mut(&mut self) -> &mut std::process::Child {
+        // inner is only ever None after into_inner, which consumes self
+        self.inner.as_mut().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl std::ops::Deref for Child {
+    type Target = std::process::Child;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.as_ref().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl std::ops::DerefMut for Child {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner_mut()
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if self.kill_on_drop && self.inner.is_some() {
+            let _ = self.kill_session();
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::os::{fd::AsRawFd as _, unix::process::ExitStatusExt as _};
+
+/// Allocate a new pty of the given size and fork a child process attached
+/// to it, running the given closure in the child instead of executing a
+/// new program.
+///
+/// The child will be made the session leader of a new session, with the
+/// pty as its controlling terminal and as its `stdin`, `stdout`, and
+/// `stderr`. The child exits with the status code returned by the closure
+/// (or 101 if the closure panics).
+///
+/// The returned [`ForkedChild`] can be used to wait for or kill the child
+/// process, and the returned [`Pty`](super::Pty) can be used to
+/// communicate with it.
+///
+/// # Safety
+/// The closure runs in the forked child, so the same restrictions apply as
+/// for [`std::os::unix::process::CommandExt::pre_exec`]: if the parent
+/// process has multiple threads, only async-signal-safe operations may be
+/// performed. In particular, allocating memory, taking locks (including
+/// the ones guarding [`std::io::stdout`] and [`std::io::stderr`]), or
+/// interacting with other threads may deadlock. The child exits via
+/// `_exit`, so atexit handlers and destructors for values owned by the
+/// parent are not run, and buffered output that was not flushed by the
+/// closure itself will be lost.
+///
+/// # Errors
+/// Returns an error if the pty failed to be allocated or resized, or if
+/// the child process failed to be forked.
+pub unsafe fn fork_with_pty<F>(
+    size: crate::Size,
+    f: F,
+) -> crate::Result<(ForkedChild, super::Pty)>
+where
+    F: FnOnce() -> i32 + Send + 'static,
+{
+    let (pty, pts) = super::open()?;
+    pty.resize(size)?;
+
+    let mut setup = pts.0.setup_forked_child();
+    let pty_fd = pty.as_raw_fd();
+    let pts_fd = pts.0.as_raw_fd();
+
+    // we can't go through std::process::Command here, since spawning
+    // blocks until the child either execs or exits
+    // Safety: the child only calls async-signal-safe functions (setsid(),
+    // ioctl(), dup2(), close(), and _exit()) before running the closure,
+    // and the caller is responsible for the safety of the closure itself.
+    match unsafe { libc::fork() } {
+        -1 => Err(std::io::Error::last_os_error().into()),
+        0 => unsafe {
+            if setup().is_err() {
+                libc::_exit(127);
+            }
+            if pts_fd > 2 {
+                libc::close(pts_fd);
+            }
+            libc::close(pty_fd);
+
+            let code =
+                std::panic::catch_unwind(std::panic::AssertUnwindSafe(f))
+                    .unwrap_or(101);
+            libc::_exit(code)
+        },
+        pid => Ok((ForkedChild { pid, status: None }, pty)),
+    }
+}
+
+/// A handle to a child process created by [`fork_with_pty`].
+///
+/// Unlike [`std::process::Child`], dropping this handle does not reap the
+/// child, so [`wait`](Self::wait) should be called to avoid leaving a
+/// zombie process behind.
+#[derive(Debug)]
+pub struct ForkedChild {
+    pid: libc::pid_t,
+    status: Option<std::process::ExitStatus>,
+}
+
+impl ForkedChild {
+    /// Returns the process id of the child. Since the child is a session
+    /// leader, this is also its process group id and session id.
+    #[must_use]
+    pub fn id(&self) -> u32 {
+        self.pid.unsigned_abs()
+    }
+
+    /// Sends `SIGKILL` to the child. See [`std::process::Child::kill`].
+    ///
+    /// # Errors
+    /// Returns an error if the signal could not be sent.
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        if self.status.is_some() {
+            return Ok(());
+        }
+        if unsafe { libc::kill(self.pid, libc::SIGKILL) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Waits for the child to exit. See [`std::process::Child::wait`].
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child fails.
+    pub fn wait(&mut self) -> std::io::Result<std::process::ExitStatus> {
+        loop {
+            if let Some(status) = self.waitpid(0)? {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Checks whether the child has exited, without blocking. See
+    /// [`std::process::Child::try_wait`].
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child fails.
+    pub fn try_wait(
+        &mut self,
+    ) -> std::io::Result<Option<std::process::ExitStatus>> {
+        self.waitpid(libc::WNOHANG)
+    }
+
+    fn waitpid(
+        &mut self,
+        options: libc::c_int,
+    ) -> std::io::Result<Option<std::process::ExitStatus>> {
+        if let Some(status) = self.status {
+            return Ok(Some(status));
+        }
+        let mut status = 0;
+        match unsafe { libc::waitpid(self.pid, &raw mut status, options) } {
+            -1 => {
+                let err = std::io::Error::last_os_error();
+                if err.kind() == std::io::ErrorKind::Interrupted {
+                    Ok(None)
+                } else {
+                    Err(err)
+                }
+            }
+            0 => Ok(None),
+            _ => {
+                let status = std::process::ExitStatus::from_raw(status);
+                self.status = Some(status);
+                Ok(Some(status))
+            }
+        }
+    }
+}
@@ -1,7 +1,13 @@
 //! Blocking equivalents for [`pty_process::Command`](crate::Command) and
 //! [`pty_process::Pty`](crate::Pty)
 
+mod child;
+pub use child::Child;
 mod command;
 pub use command::Command;
+mod fork;
+pub use fork::{ForkedChild, fork_with_pty};
+mod pool;
+pub use pool::{PoolContext, PoolEvent, PtyPool, SessionId, SessionMetrics};
 mod pty;
 pub use pty::{Pts, Pty, open};
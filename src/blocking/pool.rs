@@ -0,0 +1,474 @@
+// without a pidfd to wait on, children are checked for exit at least this
+// often
+const REAP_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(50);
+
+/// Identifies a session in a [`PtyPool`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SessionId(u64);
+
+/// Something which happened in a session in a [`PtyPool`], passed to the
+/// session's handler.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum PoolEvent<'a> {
+    /// Output was read from the pty.
+    Output(&'a [u8]),
+    /// The child process exited with the given status. This is the last
+    /// event for the session, after which it is removed from the pool.
+    Exit(std::process::ExitStatus),
+}
+
+/// Statistics about a session in a [`PtyPool`].
+#[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
+pub struct SessionMetrics {
+    /// The number of bytes read from the pty.
+    pub bytes_read: u64,
+    /// The number of bytes written to the pty.
+    pub bytes_written: u64,
+    /// The number of [`PoolEvent::Output`] events dispatched.
+    pub reads: u64,
+    /// When the session was added to the pool.
+    pub started: std::time::Instant,
+    /// When the child process was found to have exited.
+    pub exited: Option<std::time::Instant>,
+}
+
+impl SessionMetrics {
+    fn new() -> Self {
+        Self {
+            bytes_read: 0,
+            bytes_written: 0,
+            reads: 0,
+            started: std::time::Instant::now(),
+            exited: None,
+        }
+    }
+
+    /// Returns how long the session has been running, or how long it ran
+    /// for if the child process has exited.
+    #[must_use]
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.exited
+            .unwrap_or_else(std::time::Instant::now)
+            .duration_since(self.started)
+    }
+}
+
+/// Passed to a session's handler along with each [`PoolEvent`].
+pub struct PoolContext<'a> {
+    id: SessionId,
+    pty: &'a crate::blocking::Pty,
+    input: &'a mut Vec<u8>,
+    metrics: &'a SessionMetrics,
+}
+
+impl PoolContext<'_> {
+    /// Returns the id of the session.
+    #[must_use]
+    pub fn id(&self) -> SessionId {
+        self.id
+    }
+
+    /// Returns the session's pty, for example to resize it.
+    #[must_use]
+    pub fn pty(&self) -> &crate::blocking::Pty {
+        self.pty
+    }
+
+    /// Queues the given data to be written to the pty. See
+    /// [`PtyPool::write`].
+    pub fn write(&mut self, data: &[u8]) {
+        self.input.extend_from_slice(data);
+    }
+
+    /// Returns the session's metrics.
+    #[must_use]
+    pub fn metrics(&self) -> &SessionMetrics {
+        self.metrics
+    }
+}
+
+impl std::fmt::Debug for PoolContext<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PoolContext")
+            .field("id", &self.id)
+            .field("metrics", &self.metrics)
+            .finish_non_exhaustive()
+    }
+}
+
+type Handler =
+    Box<dyn FnMut(&mut PoolContext<'_>, PoolEvent<'_>) + Send + 'static>;
+
+struct Session {
+    pty: crate::blocking::Pty,
+    child: crate::blocking::Child,
+    handler: Handler,
+    input: Vec<u8>,
+    metrics: SessionMetrics,
+    // set once reading from the pty fails, which usually means that every
+    // process using the pts has exited
+    eof: bool,
+}
+
+impl Session {
+    #[cfg(target_os = "linux")]
+    fn pidfd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        use std::os::fd::AsFd as _;
+
+        self.child.pidfd().map(crate::Pidfd::as_fd)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn pidfd(&self) -> Option<std::os::fd::BorrowedFd<'_>> {
+        None
+    }
+
+    fn dispatch(&mut self, id: SessionId, event: PoolEvent<'_>) {
+        (self.handler)(
+            &mut PoolContext {
+                id,
+                pty: &self.pty,
+                input: &mut self.input,
+                metrics: &self.metrics,
+            },
+            event,
+        );
+    }
+
+    // reads a single buffer's worth of output, so that a child which
+    // writes constantly can't hold up the others, returning whether an
+    // event was dispatched
+    fn read(&mut self, id: SessionId) -> bool {
+        use std::io::Read as _;
+
+        let mut buf = [0_u8; 4096];
+        while !self.eof {
+            match (&self.pty).read(&mut buf) {
+                Ok(0) => self.eof = true,
+                Ok(bytes) => {
+                    self.metrics.bytes_read += u64::try_from(bytes)
+                        .unwrap_or_else(|_| unreachable!());
+                    self.metrics.reads += 1;
+                    self.dispatch(id, PoolEvent::Output(&buf[..bytes]));
+                    return true;
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(_) => self.eof = true,
+            }
+        }
+        false
+    }
+
+    // reads until there is nothing more to read, returning the number of
+    // events dispatched
+    fn drain(&mut self, id: SessionId) -> usize {
+        let mut events = 0;
+        while self.read(id) {
+            events += 1;
+        }
+        events
+    }
+
+    fn write(&mut self) {
+        use std::io::Write as _;
+
+        while !self.input.is_empty() {
+            match (&self.pty).write(&self.input) {
+                Ok(bytes) => {
+                    self.metrics.bytes_written += u64::try_from(bytes)
+                        .unwrap_or_else(|_| unreachable!());
+                    self.input.drain(..bytes);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => break,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                // nothing is reading the input anymore
+                Err(_) => self.input.clear(),
+            }
+        }
+    }
+}
+
+/// Runs many ptys and their child processes from a single thread.
+///
+/// Each session is added along with a handler, which is called with the
+/// output read from the pty and with the exit status of the child. All of
+/// the ptys are multiplexed with `poll`, and on Linux, children are reaped
+/// by also polling a pidfd for each of them, so no threads are needed.
+/// On other platforms, children are checked for exit periodically instead.
+///
+/// The ptys are switched to non-blocking mode while they are in the pool.
+/// Each round of polling reads at most one buffer of output from each pty,
+/// so that a child which writes constantly can't starve the others.
+///
+/// ```no_run
+/// # fn main() -> pty_process::Result<()> {
+/// let mut pool = pty_process::blocking::PtyPool::new();
+/// for job in ["make test", "make lint"] {
+///     let (pty, pts) = pty_process::blocking::open()?;
+///     let child = pty_process::blocking::Command::new("sh")
+///         .args(["-c", job])
+///         .spawn(pts)?;
+///     pool.add(pty, child, move |_, event| {
+///         if let pty_process::blocking::PoolEvent::Exit(status) = event {
+///             println!("{job}: {status}");
+///         }
+///     })?;
+/// }
+/// pool.run()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Default)]
+pub struct PtyPool {
+    sessions: std::collections::BTreeMap<SessionId, Session>,
+    next_id: u64,
+}
+
+impl PtyPool {
+    /// Creates an empty pool.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a pty and the child process spawned on it to the pool. The
+    /// given handler will be called with each [`PoolEvent`] for the
+    /// session.
+    ///
+    /// # Errors
+    /// Returns an error if the pty can't be switched to non-blocking mode.
+    pub fn add<F>(
+        &mut self,
+        pty: crate::blocking::Pty,
+        child: crate::blocking::Child,
+        handler: F,
+    ) -> crate::Result<SessionId>
+    where
+        F: FnMut(&mut PoolContext<'_>, PoolEvent<'_>) + Send + 'static,
+    {
+        pty.0.set_nonblocking()?;
+        let id = SessionId(self.next_id);
+        self.next_id += 1;
+        self.sessions.insert(
+            id,
+            Session {
+                pty,
+                child,
+                handler: Box::new(handler),
+                input: vec![],
+                metrics: SessionMetrics::new(),
+                eof: false,
+            },
+        );
+        Ok(id)
+    }
+
+    /// Removes a session from the pool without waiting for its child
+    /// process to exit, returning its pty and child. The pty is switched
+    /// back to blocking mode, and any queued input which hasn't been
+    /// written yet is discarded.
+    pub fn remove(
+        &mut self,
+        id: SessionId,
+    ) -> Option<(crate::blocking::Pty, crate::blocking::Child)> {
+        self.sessions.remove(&id).map(|session| {
+            // this only fails if the fd is invalid, which it can't be
+            let _ = session.pty.0.set_blocking();
+            (session.pty, session.child)
+        })
+    }
+
+    /// Queues the given data to be written to a session's pty, returning
+    /// false if there is no such session. The data is written as the pty
+    /// becomes writable while the pool is running.
+    pub fn write(&mut self, id: SessionId, data: &[u8]) -> bool {
+        self.sessions.get_mut(&id).is_some_and(|session| {
+            session.input.extend_from_slice(data);
+            true
+        })
+    }
+
+    /// Returns a session's pty, for example to resize it.
+    #[must_use]
+    pub fn pty(&self, id: SessionId) -> Option<&crate::blocking::Pty> {
+        self.sessions.get(&id).map(|session| &session.pty)
+    }
+
+    /// Returns a session's child process, for example to kill it.
+    pub fn child_mut(
+        &mut self,
+        id: SessionId,
+    ) -> Option<&mut crate::blocking::Child> {
+        self.sessions.get_mut(&id).map(|session| &mut session.child)
+    }
+
+    /// Returns a session's metrics.
+    #[must_use]
+    pub fn metrics(&self, id: SessionId) -> Option<SessionMetrics> {
+        self.sessions.get(&id).map(|session| session.metrics)
+    }
+
+    /// Returns the ids of the sessions in the pool.
+    pub fn ids(&self) -> impl Iterator<Item = SessionId> + '_ {
+        self.sessions.keys().copied()
+    }
+
+    /// Returns the number of sessions in the pool.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    /// Returns true if there are no sessions in the pool.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// Dispatches events until every child process has exited and been
+    /// removed from the pool.
+    ///
+    /// # Errors
+    /// Returns an error if polling fails.
+    pub fn run(&mut self) -> crate::Result<()> {
+        while !self.is_empty() {
+            self.poll(None)?;
+        }
+        Ok(())
+    }
+
+    /// Waits up to the given timeout (or indefinitely, if `None`) for any
+    /// session to become ready, and dispatches the resulting events,
+    /// returning the number of events dispatched. Returns immediately if
+    /// the pool is empty.
+    ///
+    /// # Errors
+    /// Returns an error if polling fails.
+    pub fn poll(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<usize> {
+        if self.is_empty() {
+            return Ok(0);
+        }
+
+        let ready = self.wait(timeout)?;
+
+        let mut events = 0;
+        let mut exited = vec![];
+        for (&id, session) in &mut self.sessions {
+            let (pty_ready, pid_ready) = ready
+                .get(&id)
+                .copied()
+                .unwrap_or((rustix::event::PollFlags::empty(), false));
+            if pty_ready.contains(rustix::event::PollFlags::OUT) {
+                session.write();
+            }
+            if pty_ready.intersects(
+                rustix::event::PollFlags::IN
+                    | rustix::event::PollFlags::HUP
+                    | rustix::event::PollFlags::ERR,
+            ) && session.read(id)
+            {
+                events += 1;
+            }
+            if pid_ready || session.pidfd().is_none() {
+                match session.child.try_wait() {
+                    Ok(Some(status)) => exited.push((id, status)),
+                    Ok(None) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+
+        for (id, status) in exited {
+            let Some(mut session) = self.sessions.remove(&id) else {
+                continue;
+            };
+            // output written before the child exited is still buffered in
+            // the pty
+            events += session.drain(id);
+            session.metrics.exited = Some(std::time::Instant::now());
+            session.dispatch(id, PoolEvent::Exit(status));
+            events += 1;
+        }
+
+        Ok(events)
+    }
+
+    fn wait(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<
+        std::collections::HashMap<
+            SessionId,
+            (rustix::event::PollFlags, bool),
+        >,
+    > {
+        let timeout = if self.sessions.values().any(|s| s.pidfd().is_none()) {
+            Some(timeout.map_or(REAP_INTERVAL, |t| t.min(REAP_INTERVAL)))
+        } else {
+            timeout
+        };
+        let timeout = timeout
+            .map(rustix::event::Timespec::try_from)
+            .transpose()
+            .map_err(|_| rustix::io::Errno::INVAL)?;
+
+        let mut fds = vec![];
+        let mut owners = vec![];
+        for (&id, session) in &self.sessions {
+            // once the pty is closed, it would always be reported as
+            // hung up, so only the pidfd is still useful
+            if !session.eof {
+                let mut flags = rustix::event::PollFlags::IN;
+                if !session.input.is_empty() {
+                    flags |= rustix::event::PollFlags::OUT;
+                }
+                fds.push(rustix::event::PollFd::new(&session.pty, flags));
+                owners.push((id, false));
+            }
+            if let Some(pidfd) = session.pidfd() {
+                fds.push(rustix::event::PollFd::from_borrowed_fd(
+                    pidfd,
+                    rustix::event::PollFlags::IN,
+                ));
+                owners.push((id, true));
+            }
+        }
+
+        match rustix::event::poll(&mut fds, timeout.as_ref()) {
+            Ok(_) | Err(rustix::io::Errno::INTR) => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        let mut ready = std::collections::HashMap::new();
+        for (fd, (id, is_pidfd)) in fds.iter().zip(owners) {
+            let revents = fd.revents();
+            if revents.is_empty() {
+                continue;
+            }
+            let entry = ready
+                .entry(id)
+                .or_insert((rustix::event::PollFlags::empty(), false));
+            if is_pidfd {
+                entry.1 = true;
+            } else {
+                entry.0 |= revents;
+            }
+        }
+        Ok(ready)
+    }
+}
+
+impl std::fmt::Debug for PtyPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PtyPool")
+            .field("sessions", &self.sessions.keys().collect::<Vec<_>>())
+            .finish_non_exhaustive()
+    }
+}
@@ -1,3 +1,5 @@
+use std::io::{Read as _, Write as _};
+
 /// Allocate and return a new pty.
 ///
 /// # Errors
@@ -5,11 +7,14 @@
 pub fn open() -> crate::Result<(Pty, Pts)> {
     let pty = crate::sys::Pty::open()?;
     let pts = pty.pts()?;
-    Ok((Pty(pty), Pts(pts)))
+    Ok((Pty(pty, std::sync::Mutex::default()), Pts(pts)))
 }
 
 /// An allocated pty
-pub struct Pty(crate::sys::Pty);
+pub struct Pty(
+    pub(crate) crate::sys::Pty,
+    std::sync::Mutex<Option<std::time::Duration>>,
+);
 
 impl Pty {
     /// Use the provided file descriptor as a pty.
@@ -18,7 +23,9 @@ impl Pty {
     /// The provided file descriptor must be valid, open, and belong to a pty.
     #[must_use]
     pub unsafe fn from_fd(fd: std::os::fd::OwnedFd) -> Self {
-        unsafe { Self(crate::sys::Pty::from_fd(fd)) }
+        unsafe {
+            Self(crate::sys::Pty::from_fd(fd), std::sync::Mutex::default())
+        }
     }
 
     /// Change the terminal size associated with the pty.
@@ -28,6 +35,269 @@ impl Pty {
     pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
         self.0.set_term_size(size)
     }
+
+    /// Discard data which is queued in the pty, without it being read,
+    /// like `tcflush`.
+    ///
+    /// # Errors
+    /// Returns an error if the queue couldn't be flushed.
+    pub fn discard(&self, queue: crate::Queue) -> crate::Result<()> {
+        self.0.discard(queue)
+    }
+
+    /// Wait until the child has read all of the input written to the pty,
+    /// like `tcdrain`. In canonical mode, an incomplete line is not
+    /// waited for, since the child can't read it until it is finished.
+    ///
+    /// This waits indefinitely if the child never reads its input.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the input queue couldn't be
+    /// determined.
+    pub fn drain(&self) -> crate::Result<()> {
+        let pts = self.0.start_drain()?;
+        while crate::sys::queue_len(&pts)? > 0 {
+            std::thread::sleep(crate::sys::DRAIN_POLL_INTERVAL);
+        }
+        Ok(())
+    }
+
+    /// Suspend output from the child, as if `^S` had been typed, like
+    /// `tcflow` with `TCOOFF`. Writes to the pts will block until
+    /// [`resume_output`](Self::resume_output) is called.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be suspended.
+    pub fn suspend_output(&self) -> crate::Result<()> {
+        self.0.set_output_suspended(true)
+    }
+
+    /// Resume output from the child after
+    /// [`suspend_output`](Self::suspend_output), like `tcflow` with
+    /// `TCOON`.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be resumed.
+    pub fn resume_output(&self) -> crate::Result<()> {
+        self.0.set_output_suspended(false)
+    }
+
+    /// Send a break, like `tcsendbreak`. Note that ptys on Linux ignore
+    /// breaks.
+    ///
+    /// # Errors
+    /// Returns an error if the break couldn't be sent.
+    pub fn send_break(&self) -> crate::Result<()> {
+        self.0.send_break()
+    }
+
+    /// Returns the number of bytes of input which have been written to the
+    /// pty but not yet read by the child (`FIONREAD` on the pts). In
+    /// canonical mode, an incomplete line is not counted.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn input_queue_len(&self) -> crate::Result<usize> {
+        self.0.input_queue_len()
+    }
+
+    /// Returns the number of bytes of output which have been written by
+    /// the child but not yet read from the pty (`TIOCOUTQ`, or `FIONREAD`
+    /// on Linux, where `TIOCOUTQ` is always 0 for ptys).
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn output_queue_len(&self) -> crate::Result<usize> {
+        self.0.output_queue_len()
+    }
+
+    /// Hang up the pty, as if the terminal had been disconnected. This sends
+    /// `SIGHUP` (followed by `SIGCONT`) to the foreground process group and
+    /// the session leader of the session attached to the pty, and then
+    /// closes the pty. Processes which are still reading from or writing to
+    /// the pts will see end of file or errors afterwards.
+    pub fn hangup(self) {
+        self.0.signal_hangup();
+    }
+
+    /// Waits until data can be read from the pty without blocking, or until
+    /// the given timeout (if any) elapses. Returns false if the timeout
+    /// elapsed.
+    ///
+    /// This also returns true once every process has closed the pts, since
+    /// reading will then immediately return an error.
+    ///
+    /// # Errors
+    /// Returns an error if polling the pty fails.
+    pub fn wait_readable(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> std::io::Result<bool> {
+        Ok(crate::sys::wait_readable(&self.0, timeout)?)
+    }
+
+    /// Reads from the pty, waiting at most the given timeout for data to
+    /// become available.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`std::io::ErrorKind::TimedOut`] if no data
+    /// was available before the timeout elapsed, or any error returned by
+    /// reading from the pty.
+    pub fn read_timeout(
+        &self,
+        buf: &mut [u8],
+        timeout: std::time::Duration,
+    ) -> std::io::Result<usize> {
+        if !self.wait_readable(Some(timeout))? {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                "timed out waiting for the pty to be readable",
+            ));
+        }
+        (&self.0).read(buf)
+    }
+
+    /// Reads from the pty if data is available, without blocking.
+    ///
+    /// Note that if another thread is also reading from the pty, it may
+    /// read the available data first, in which case this will block until
+    /// more data is available.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`std::io::ErrorKind::WouldBlock`] if no
+    /// data is available, or any error returned by reading from the pty.
+    pub fn try_read(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if !self.wait_readable(Some(std::time::Duration::ZERO))? {
+            return Err(std::io::ErrorKind::WouldBlock.into());
+        }
+        (&self.0).read(buf)
+    }
+
+    /// Sets a timeout for reads through the [`std::io::Read`]
+    /// implementations, after which they fail with an error of kind
+    /// [`std::io::ErrorKind::TimedOut`]. `None`, the default, means that
+    /// reads block indefinitely.
+    ///
+    /// # Errors
+    /// Returns an error of kind [`std::io::ErrorKind::InvalidInput`] if the
+    /// timeout is zero, the same as
+    /// [`TcpStream::set_read_timeout`](std::net::TcpStream::set_read_timeout).
+    pub fn set_read_timeout(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> std::io::Result<()> {
+        if timeout == Some(std::time::Duration::ZERO) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot set a zero read timeout",
+            ));
+        }
+        *self
+            .1
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = timeout;
+        Ok(())
+    }
+
+    /// Returns true if the pts currently has echo disabled, as is usually
+    /// the case while a program is reading a password.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal settings couldn't be read.
+    pub fn is_echo_disabled(&self) -> crate::Result<bool> {
+        self.0.echo_disabled()
+    }
+
+    /// Waits until the pts is in the state typically used for reading a
+    /// password (echo disabled, but still reading a line at a time), or
+    /// until the given timeout (if any) elapses. Returns false if the
+    /// timeout elapsed.
+    ///
+    /// The terminal settings are polled, so this doesn't read any output
+    /// from the pty.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal settings couldn't be read.
+    pub fn wait_for_password_prompt(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<bool> {
+        let deadline =
+            timeout.map(|timeout| std::time::Instant::now() + timeout);
+        loop {
+            if self.0.password_prompt()? {
+                return Ok(true);
+            }
+            if deadline
+                .is_some_and(|deadline| std::time::Instant::now() >= deadline)
+            {
+                return Ok(false);
+            }
+            std::thread::sleep(crate::sys::PROMPT_POLL_INTERVAL);
+        }
+    }
+
+    /// Send a secret, such as a password, to the pty. Include the trailing
+    /// newline in the secret if the program expects one.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub fn send_secret(
+        &self,
+        secret: &crate::SecretBytes,
+    ) -> std::io::Result<()> {
+        (&self.0).write_all(secret.expose())
+    }
+
+    /// Starts tracking the terminal modes which affect how input is
+    /// encoded (see [`modes`](Self::modes)) from the output read from this
+    /// pty. Tracking is off by default, since it requires scanning all of
+    /// the output, so this should be called before reading anything that
+    /// the child writes.
+    pub fn track_modes(&self) {
+        self.0.track_modes();
+    }
+
+    /// Returns the terminal modes which affect how input is encoded, as
+    /// set by the output which has been read from this pty since
+    /// [`track_modes`](Self::track_modes) was called. Without tracking,
+    /// this always returns the default modes.
+    #[must_use]
+    pub fn modes(&self) -> crate::keys::Modes {
+        self.0.modes()
+    }
+
+    /// Send a key press to the pty, encoded according to the current
+    /// [`modes`](Self::modes).
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub fn send_key(
+        &self,
+        key: impl Into<crate::keys::KeyPress>,
+    ) -> std::io::Result<()> {
+        (&self.0).write_all(&key.into().encode(self.0.modes()))
+    }
+
+    /// Send text to the pty as if it was pasted into the terminal. See
+    /// [`keys::encode_paste`](crate::keys::encode_paste).
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub fn send_paste(&self, text: &[u8]) -> std::io::Result<()> {
+        (&self.0).write_all(&crate::keys::encode_paste(text, self.0.modes()))
+    }
+
+    /// Send a mouse event to the pty, using the SGR mouse protocol.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub fn send_mouse(
+        &self,
+        event: crate::keys::MouseEvent,
+    ) -> std::io::Result<()> {
+        (&self.0).write_all(&event.encode())
+    }
 }
 
 impl From<Pty> for std::os::fd::OwnedFd {
@@ -50,7 +320,7 @@ impl std::os::fd::AsRawFd for Pty {
 
 impl std::io::Read for Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        self.0.read(buf)
+        (&*self).read(buf)
     }
 }
 
@@ -66,7 +336,14 @@ impl std::io::Write for Pty {
 
 impl std::io::Read for &Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        (&self.0).read(buf)
+        let timeout = *self
+            .1
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        match timeout {
+            Some(timeout) => self.read_timeout(buf, timeout),
+            None => (&self.0).read(buf),
+        }
     }
 }
 
@@ -0,0 +1,473 @@
+//! Fan-out of the output of a pty to multiple concurrent readers.
+//!
+//! ```no_run
+//! # #[cfg(feature = "async")]
+//! # #[tokio::main]
+//! # async fn main() -> pty_process::Result<()> {
+//! use futures::stream::StreamExt as _;
+//!
+//! let (pty, pts) = pty_process::open()?;
+//! let mut child = pty_process::Command::new("top").spawn(pts)?;
+//! let (pty_r, pty_w) = pty.into_split();
+//! let broadcast = pty_process::broadcast::PtyBroadcast::spawn(
+//!     pty_r,
+//!     pty_process::broadcast::Config::new().replay(4096),
+//! );
+//! let mut ui = broadcast.subscribe();
+//! let mut log = broadcast.subscribe();
+//! tokio::spawn(async move {
+//!     while let Some(Ok(data)) = log.next().await {
+//!         eprintln!("{data:?}");
+//!     }
+//! });
+//! while let Some(data) = ui.next().await {
+//!     // ...
+//! }
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "async"))]
+//! # fn main() {}
+//! ```
+
+/// What to do when a subscriber falls behind by more than the capacity of
+/// the buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Lag {
+    /// Discard the oldest buffered output. Subscribers which hadn't read
+    /// it yet will receive a [`Lagged`] error with the number of bytes
+    /// they missed, and then continue from the oldest output still
+    /// available.
+    #[default]
+    DropOldest,
+    /// Stop reading from the pty until every subscriber has caught up.
+    /// This means that the program running in the pty will eventually
+    /// block writing its output if any subscriber stops reading.
+    Backpressure,
+}
+
+/// Configuration for a [`PtyBroadcast`].
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    capacity: usize,
+    lag: Lag,
+    replay: usize,
+}
+
+impl Config {
+    /// Creates a new configuration with the defaults: a capacity of 64KiB,
+    /// dropping the oldest output when subscribers lag behind, and no
+    /// replay.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            capacity: 64 * 1024,
+            lag: Lag::DropOldest,
+            replay: 0,
+        }
+    }
+
+    /// Sets the number of bytes of output to buffer.
+    #[must_use]
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+
+    /// Sets what to do when a subscriber falls behind.
+    #[must_use]
+    pub fn lag(mut self, lag: Lag) -> Self {
+        self.lag = lag;
+        self
+    }
+
+    /// Sets the number of bytes of previous output which new subscribers
+    /// will receive first, to catch up on the current state of the
+    /// terminal. This is limited by the capacity of the buffer.
+    #[must_use]
+    pub fn replay(mut self, replay: usize) -> Self {
+        self.replay = replay;
+        self
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The error returned by a [`Subscriber`] which fell behind by more than
+/// the capacity of the buffer, containing the number of bytes of output it
+/// missed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged(pub u64);
+
+impl std::fmt::Display for Lagged {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "subscriber lagged behind by {} bytes", self.0)
+    }
+}
+
+impl std::error::Error for Lagged {}
+
+/// Reads the output of a pty in the background, and makes it available to
+/// any number of [`Subscriber`]s.
+///
+/// Output is kept in a ring buffer of a fixed size (see [`Config`]), and
+/// each subscriber receives all of the output read after it subscribed (as
+/// well as the configured amount of previous output). Reading stops when
+/// the pty returns end of file or an error (such as when the child
+/// process exits), at which point subscribers will receive the rest of the
+/// buffered output and then end.
+///
+/// This handle can be cloned to subscribe from multiple places.
+#[derive(Debug, Clone)]
+pub struct PtyBroadcast {
+    shared: std::sync::Arc<Shared>,
+}
+
+impl PtyBroadcast {
+    /// Starts a [`tokio`] task reading from the given pty read half (such
+    /// as an [`OwnedReadPty`](crate::OwnedReadPty)).
+    ///
+    /// # Panics
+    /// Panics if called outside of a tokio runtime.
+    #[cfg(feature = "async")]
+    pub fn spawn<R>(mut reader: R, config: Config) -> Self
+    where
+        R: tokio::io::AsyncRead + Unpin + Send + 'static,
+    {
+        use tokio::io::AsyncReadExt as _;
+
+        let shared = Shared::new(config);
+        tokio::spawn({
+            let shared = std::sync::Arc::clone(&shared);
+            async move {
+                let mut buf = [0_u8; 4096];
+                while let Ok(bytes @ 1..) = reader.read(&mut buf).await {
+                    let mut data = &buf[..bytes];
+                    while !data.is_empty() {
+                        let written = std::future::poll_fn(|cx| {
+                            shared.poll_push(
+                                &mut shared.lock(),
+                                data,
+                                Some(cx),
+                            )
+                        })
+                        .await;
+                        data = &data[written..];
+                    }
+                }
+                shared.close();
+            }
+        });
+        Self { shared }
+    }
+
+    /// Starts a thread reading from the given reader.
+    ///
+    /// Since a [`blocking::Pty`](crate::blocking::Pty) can't be split, a
+    /// duplicate of its file descriptor can be used here instead:
+    ///
+    /// ```no_run
+    /// # fn main() -> pty_process::Result<()> {
+    /// use std::os::fd::AsFd as _;
+    ///
+    /// let (pty, pts) = pty_process::blocking::open()?;
+    /// let mut child = pty_process::blocking::Command::new("top").spawn(pts)?;
+    /// let reader = std::fs::File::from(pty.as_fd().try_clone_to_owned()?);
+    /// let broadcast = pty_process::broadcast::PtyBroadcast::spawn_blocking(
+    ///     reader,
+    ///     pty_process::broadcast::Config::new(),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn spawn_blocking<R>(mut reader: R, config: Config) -> Self
+    where
+        R: std::io::Read + Send + 'static,
+    {
+        let shared = Shared::new(config);
+        std::thread::spawn({
+            let shared = std::sync::Arc::clone(&shared);
+            move || {
+                let mut buf = [0_u8; 4096];
+                loop {
+                    match reader.read(&mut buf) {
+                        Ok(0) => break,
+                        Ok(bytes) => shared.push_blocking(&buf[..bytes]),
+                        Err(e)
+                            if e.kind()
+                                == std::io::ErrorKind::Interrupted => {}
+                        Err(_) => break,
+                    }
+                }
+                shared.close();
+            }
+        });
+        Self { shared }
+    }
+
+    /// Returns a new [`Subscriber`], which will receive the configured
+    /// amount of previous output followed by all new output.
+    #[must_use]
+    pub fn subscribe(&self) -> Subscriber {
+        let mut state = self.shared.lock();
+        let id = state.next_id;
+        state.next_id += 1;
+        let pos = state
+            .start
+            .max(state.end().saturating_sub(self.shared.replay()));
+        state
+            .subscribers
+            .insert(id, Subscription { pos, waker: None });
+        drop(state);
+        Subscriber {
+            shared: std::sync::Arc::clone(&self.shared),
+            id,
+        }
+    }
+
+    /// Returns true once reading from the pty has stopped.
+    #[must_use]
+    pub fn is_closed(&self) -> bool {
+        self.shared.lock().closed
+    }
+}
+
+/// A handle which receives the output broadcast by a [`PtyBroadcast`].
+///
+/// Output can be received with [`blocking_recv`](Self::blocking_recv) or
+/// [`blocking_iter`](Self::blocking_iter), which block until more output
+/// is available, or via the [`Stream`](futures_core::Stream) implementation
+/// when the `async` feature is enabled. Each item is either a chunk of
+/// output or a [`Lagged`] error if output was dropped before this
+/// subscriber could read it.
+#[derive(Debug)]
+pub struct Subscriber {
+    shared: std::sync::Arc<Shared>,
+    id: usize,
+}
+
+impl Subscriber {
+    /// Waits for more output, returning `None` once reading from the pty
+    /// has stopped and all of the buffered output has been received.
+    ///
+    /// # Errors
+    /// Returns [`Lagged`] if output was dropped before it could be
+    /// received.
+    pub fn blocking_recv(&mut self) -> Option<Result<Vec<u8>, Lagged>> {
+        let mut state = self.shared.lock();
+        loop {
+            if let std::task::Poll::Ready(item) =
+                self.poll_recv(&mut state, None)
+            {
+                return item;
+            }
+            state = self.shared.wait(state);
+        }
+    }
+
+    /// Returns an [`Iterator`] which calls
+    /// [`blocking_recv`](Self::blocking_recv) for each item.
+    pub fn blocking_iter(&mut self) -> BlockingIter<'_> {
+        BlockingIter(self)
+    }
+
+    fn poll_recv(
+        &self,
+        state: &mut State,
+        cx: Option<&mut std::task::Context<'_>>,
+    ) -> std::task::Poll<Option<Result<Vec<u8>, Lagged>>> {
+        let (start, end) = (state.start, state.end());
+        let Some(sub) = state.subscribers.get_mut(&self.id) else {
+            return std::task::Poll::Ready(None);
+        };
+        if sub.pos < start {
+            let skipped = start - sub.pos;
+            sub.pos = start;
+            return std::task::Poll::Ready(Some(Err(Lagged(skipped))));
+        }
+        if sub.pos < end {
+            let offset = usize::try_from(sub.pos - start).unwrap();
+            let data = state.buf.range(offset..).copied().collect();
+            sub.pos = end;
+            self.shared.notify_pump(state);
+            return std::task::Poll::Ready(Some(Ok(data)));
+        }
+        if state.closed {
+            return std::task::Poll::Ready(None);
+        }
+        if let Some(cx) = cx {
+            sub.waker = Some(cx.waker().clone());
+        }
+        std::task::Poll::Pending
+    }
+}
+
+/// A blocking [`Iterator`] over the output received by a [`Subscriber`],
+/// returned by [`Subscriber::blocking_iter`].
+#[derive(Debug)]
+pub struct BlockingIter<'a>(&'a mut Subscriber);
+
+impl Iterator for BlockingIter<'_> {
+    type Item = Result<Vec<u8>, Lagged>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.blocking_recv()
+    }
+}
+
+#[cfg(feature = "async")]
+impl futures_core::Stream for Subscriber {
+    type Item = Result<Vec<u8>, Lagged>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        self.poll_recv(&mut self.shared.lock(), Some(cx))
+    }
+}
+
+impl Drop for Subscriber {
+    fn drop(&mut self) {
+        let mut state = self.shared.lock();
+        state.subscribers.remove(&self.id);
+        self.shared.notify_pump(&mut state);
+        drop(state);
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: Config,
+    state: std::sync::Mutex<State>,
+    cond: std::sync::Condvar,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    buf: std::collections::VecDeque<u8>,
+    // the offset in the overall output of the first byte in buf
+    start: u64,
+    subscribers: std::collections::HashMap<usize, Subscription>,
+    next_id: usize,
+    pump_waker: Option<std::task::Waker>,
+    closed: bool,
+}
+
+#[derive(Debug)]
+struct Subscription {
+    // the offset in the overall output of the next byte to read
+    pos: u64,
+    waker: Option<std::task::Waker>,
+}
+
+impl State {
+    fn end(&self) -> u64 {
+        self.start + u64::try_from(self.buf.len()).unwrap()
+    }
+}
+
+impl Shared {
+    fn new(config: Config) -> std::sync::Arc<Self> {
+        std::sync::Arc::new(Self {
+            config,
+            state: std::sync::Mutex::new(State::default()),
+            cond: std::sync::Condvar::new(),
+        })
+    }
+
+    fn replay(&self) -> u64 {
+        u64::try_from(self.config.replay.min(self.config.capacity)).unwrap()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    fn wait<'a>(
+        &self,
+        state: std::sync::MutexGuard<'a, State>,
+    ) -> std::sync::MutexGuard<'a, State> {
+        self.cond
+            .wait(state)
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    // appends as much of data to the buffer as the lag policy allows,
+    // returning the number of bytes appended
+    fn poll_push(
+        &self,
+        state: &mut State,
+        data: &[u8],
+        cx: Option<&mut std::task::Context<'_>>,
+    ) -> std::task::Poll<usize> {
+        let capacity = self.config.capacity;
+        let len = match self.config.lag {
+            Lag::DropOldest => data.len(),
+            Lag::Backpressure => {
+                let end = state.end();
+                let unread = state
+                    .subscribers
+                    .values()
+                    .map(|sub| end - sub.pos.max(state.start))
+                    .max()
+                    .unwrap_or(0);
+                let room =
+                    capacity.saturating_sub(usize::try_from(unread).unwrap());
+                if room == 0 {
+                    if let Some(cx) = cx {
+                        state.pump_waker = Some(cx.waker().clone());
+                    }
+                    return std::task::Poll::Pending;
+                }
+                room.min(data.len())
+            }
+        };
+        state.buf.extend(&data[..len]);
+        if state.buf.len() > capacity {
+            let excess = state.buf.len() - capacity;
+            state.buf.drain(..excess);
+            state.start += u64::try_from(excess).unwrap();
+        }
+        self.notify_subscribers(state);
+        std::task::Poll::Ready(len)
+    }
+
+    fn push_blocking(&self, mut data: &[u8]) {
+        let mut state = self.lock();
+        while !data.is_empty() {
+            match self.poll_push(&mut state, data, None) {
+                std::task::Poll::Ready(written) => data = &data[written..],
+                std::task::Poll::Pending => state = self.wait(state),
+            }
+        }
+    }
+
+    fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        self.notify_subscribers(&mut state);
+        drop(state);
+    }
+
+    fn notify_subscribers(&self, state: &mut State) {
+        for sub in state.subscribers.values_mut() {
+            if let Some(waker) = sub.waker.take() {
+                waker.wake();
+            }
+        }
+        self.cond.notify_all();
+    }
+
+    fn notify_pump(&self, state: &mut State) {
+        if let Some(waker) = state.pump_waker.take() {
+            waker.wake();
+        }
+        self.cond.notify_all();
+    }
+}
@@ -0,0 +1,203 @@
+/// Wrapper around [`tokio::process::Child`]
+///
+/// Dereferences to the wrapped [`tokio::process::Child`], so all of its
+/// methods and fields are available directly.
+#[derive(Debug)]
+pub struct Child {
+    inner: Option<tokio::process::Child>,
+    pid: Option<rustix::process::Pid>,
+    // opened on first use, so that children which never need it don't hold
+    // an extra file descriptor
+    #[cfg(target_os = "linux")]
+    pidfd: std::sync::OnceLock<Option<crate::Pidfd>>,
+    kill_on_drop: bool,
+    kill_grace_period: std::time::Duration,
+}
+
+impl Child {
+    pub(crate) fn new(
+        inner: tokio::process::Child,
+        kill_on_drop: bool,
+        kill_grace_period: std::time::Duration,
+    ) -> Self {
+        let pid = inner
+            .id()
+            .and_then(|id| i32::try_from(id).ok())
+            .and_then(rustix::process::Pid::from_raw);
+        Self {
+            inner: Some(inner),
+            pid,
+            #[cfg(target_os = "linux")]
+            pidfd: std::sync::OnceLock::new(),
+            kill_on_drop,
+            kill_grace_period,
+        }
+    }
+
+    /// Returns a [`Pidfd`](crate::Pidfd) referring to the child, or `None`
+    /// if the kernel doesn't support pidfds or the child had already been
+    /// reaped when this was first called. The pidfd is opened on the first
+    /// call, and kept until the `Child` is dropped.
+    #[cfg(target_os = "linux")]
+    #[must_use]
+    pub fn pidfd(&self) -> Option<&crate::Pidfd> {
+        self.pidfd
+            .get_or_init(|| self.pid.and_then(crate::Pidfd::open))
+            .as_ref()
+    }
+
+    /// Sends the given signal to the child. On Linux, this uses the
+    /// child's [`pidfd`](Self::pidfd) when available, so the signal can't
+    /// be delivered to an unrelated process which reused the child's pid.
+    ///
+    /// # Errors
+    /// Returns an error if sending the signal fails, including if the child
+    /// has already been reaped.
+    pub fn signal(
+        &self,
+        signal: rustix::process::Signal,
+    ) -> crate::Result<()> {
+        #[cfg(target_os = "linux")]
+        if let Some(pidfd) = self.pidfd() {
+            return pidfd.signal(signal);
+        }
+        // tokio forgets the pid once the child has been reaped, since it may
+        // then belong to an unrelated process
+        let pid = self.id().and(self.pid).ok_or(rustix::io::Errno::SRCH)?;
+        rustix::process::kill_process(pid, signal)?;
+        Ok(())
+    }
+
+    /// Sends `SIGHUP` and `SIGTERM` to every process in the child's process
+    /// group, waits up to the configured grace period (see
+    /// [`Command::kill_grace_period`](crate::Command::kill_grace_period))
+    /// for them to exit, sends `SIGKILL` to any that remain, and then waits
+    /// for the child to exit.
+    ///
+    /// Since the child is the leader of a new session, this will also reach
+    /// any processes it spawned, unless they have moved themselves into a
+    /// different process group. The process group is signalled even if the
+    /// child itself has already exited, to clean up anything it left behind.
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child fails.
+    pub async fn kill_session(
+        &mut self,
+    ) -> crate::Result<std::process::ExitStatus> {
+        let grace_period = self.kill_grace_period;
+        let pid = self.pid;
+        let child = self.inner_mut();
+        let Some(pid) = pid else {
+            return Ok(child.wait().await?);
+        };
+
+        // the rest of the session may still be running even if the child
+        // itself has already exited
+        crate::sys::terminate_session(pid);
+        let deadline = tokio::time::Instant::now() + grace_period;
+        loop {
+            let reaped = matches!(child.try_wait(), Ok(Some(_)) | Err(_));
+            if reaped && crate::sys::session_exited(pid) {
+                break;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                crate::sys::kill_session(pid);
+                break;
+            }
+            // the child itself can be waited for directly, but other
+            // processes in the session can only be polled for
+            if reaped {
+                tokio::time::sleep(crate::sys::SESSION_POLL_INTERVAL).await;
+            } else {
+                let _ = tokio::time::timeout_at(deadline, child.wait()).await;
+            }
+        }
+        Ok(child.wait().await?)
+    }
+
+    /// Gracefully shuts down the child by hanging up the given pty (see
+    /// [`Pty::hangup`](crate::Pty::hangup)), which gives interactive
+    /// programs such as shells and editors a chance to save their state
+    /// and exit. If the child has not exited within the given timeout, its
+    /// session is torn down as described in
+    /// [`kill_session`](Self::kill_session).
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child fails.
+    pub async fn shutdown(
+        &mut self,
+        pty: crate::Pty,
+        timeout: std::time::Duration,
+    ) -> crate::Result<std::process::ExitStatus> {
+        pty.hangup();
+        if let Ok(status) =
+            tokio::time::timeout(timeout, self.inner_mut().wait()).await
+        {
+            return Ok(status?);
+        }
+        self.kill_session().await
+    }
+
+    /// Returns the wrapped [`tokio::process::Child`]. The child will no
+    /// longer be killed when dropped, even if
+    /// [`Command::kill_on_drop`](crate::Command::kill_on_drop) was set.
+    #[must_use]
+    pub fn into_inner(mut self) -> tokio::process::Child {
+        self.inner.take().unwrap_or_else(|| unreachable!())
+    }
+
+    fn inner_mut(&mut self) -> &mut tokio::process::Child {
+        // inner is only ever None after into_inner or drop
+        self.inner.as_mut().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl std::ops::Deref for Child {
+    type Target = tokio::process::Child;
+
+    fn deref(&self) -> &Self::Target {
+        self.inner.as_ref().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl std::ops::DerefMut for Child {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.inner_mut()
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if !self.kill_on_drop {
+            return;
+        }
+        let (Some(mut child), Some(pid)) = (self.inner.take(), self.pid)
+        else {
+            return;
+        };
+        #[cfg(target_os = "linux")]
+        let pidfd =
+            self.pidfd.take().unwrap_or_else(|| crate::Pidfd::open(pid));
+
+        let grace_period = self.kill_grace_period;
+        // dropping can't block, so the grace period is waited for on a
+        // separate thread (from the runtime's blocking pool if possible,
+        // since drop may also happen outside of a runtime). the rest of the
+        // session may still be running even if the child itself has
+        // already exited. tokio will reap the child once it is dropped.
+        let teardown = move || {
+            #[cfg(target_os = "linux")]
+            let leader = pidfd.as_ref().map(std::os::fd::AsFd::as_fd);
+            #[cfg(not(target_os = "linux"))]
+            let leader = None;
+            crate::sys::teardown_session(pid, grace_period, leader, || {
+                matches!(child.try_wait(), Ok(Some(_)) | Err(_))
+            });
+        };
+        if let Ok(handle) = tokio::runtime::Handle::try_current() {
+            handle.spawn_blocking(teardown);
+        } else {
+            std::thread::spawn(teardown);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Integration with the [`tokio_util::codec`] framing APIs.
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> pty_process::Result<()> {
+//! use futures::{SinkExt as _, StreamExt as _};
+//!
+//! let (pty, pts) = pty_process::open()?;
+//! let mut child = pty_process::Command::new("vim").spawn(pts)?;
+//! let mut framed = tokio_util::codec::Framed::new(
+//!     pty,
+//!     pty_process::codec::PtyCodec::new(),
+//! );
+//! framed
+//!     .send(pty_process::keys::KeyPress::from(pty_process::keys::Key::Up))
+//!     .await?;
+//! while let Some(chunk) = framed.next().await {
+//!     println!("{:?}", chunk?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+// incomplete escape sequences longer than this are returned anyway, to
+// avoid buffering indefinitely when given garbage
+const MAX_PENDING: usize = 64 * 1024;
+
+/// A [`Decoder`](tokio_util::codec::Decoder) and
+/// [`Encoder`](tokio_util::codec::Encoder) for the data read from and
+/// written to a pty.
+///
+/// Decoding returns chunks of output which never end in the middle of an
+/// escape sequence or a multibyte UTF-8 character, so each chunk can be
+/// processed on its own.
+///
+/// Raw bytes can be encoded as they are, and [`KeyPress`](crate::keys::KeyPress)es
+/// are encoded according to the terminal modes most recently set by the
+/// decoded output (see [`keys::Modes`](crate::keys::Modes)).
+#[derive(Debug, Default)]
+pub struct PtyCodec {
+    modes: crate::keys::ModeTracker,
+}
+
+impl PtyCodec {
+    /// Creates a new codec.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the terminal modes set by the output decoded so far.
+    #[must_use]
+    pub fn modes(&self) -> crate::keys::Modes {
+        self.modes.modes()
+    }
+
+    fn frame(
+        &mut self,
+        src: &mut bytes::BytesMut,
+        len: usize,
+    ) -> bytes::Bytes {
+        let frame = src.split_to(len).freeze();
+        self.modes.process(&frame);
+        frame
+    }
+}
+
+impl tokio_util::codec::Decoder for PtyCodec {
+    type Item = bytes::Bytes;
+    type Error = std::io::Error;
+
+    fn decode(
+        &mut self,
+        src: &mut bytes::BytesMut,
+    ) -> std::io::Result<Option<Self::Item>> {
+        let len = crate::ansi::complete_len(src, MAX_PENDING);
+        let len = len - incomplete_utf8_len(&src[..len]);
+        if len == 0 {
+            return Ok(None);
+        }
+        Ok(Some(self.frame(src, len)))
+    }
+
+    fn decode_eof(
+        &mut self,
+        src: &mut bytes::BytesMut,
+    ) -> std::io::Result<Option<Self::Item>> {
+        if let Some(frame) = self.decode(src)? {
+            return Ok(Some(frame));
+        }
+        if src.is_empty() {
+            return Ok(None);
+        }
+        let len = src.len();
+        Ok(Some(self.frame(src, len)))
+    }
+}
+
+impl tokio_util::codec::Encoder<bytes::Bytes> for PtyCodec {
+    type Error = std::io::Error;
+
+    fn encode(
+        &mut self,
+        item: bytes::Bytes,
+        dst: &mut bytes::BytesMut,
+    ) -> std::io::Result<()> {
+        dst.extend_from_slice(&item);
+        Ok(())
+    }
+}
+
+impl tokio_util::codec::Encoder<&[u8]> for PtyCodec {
+    type Error = std::io::Error;
+
+    fn encode(
+        &mut self,
+        item: &[u8],
+        dst: &mut bytes::BytesMut,
+    ) -> std::io::Result<()> {
+        dst.extend_from_slice(item);
+        Ok(())
+    }
+}
+
+impl tokio_util::codec::Encoder<crate::keys::KeyPress> for PtyCodec {
+    type Error = std::io::Error;
+
+    fn encode(
+        &mut self,
+        item: crate::keys::KeyPress,
+        dst: &mut bytes::BytesMut,
+    ) -> std::io::Result<()> {
+        dst.extend_from_slice(&item.encode(self.modes.modes()));
+        Ok(())
+    }
+}
+
+// returns the number of bytes at the end of buf which are the start of a
+// multibyte utf8 character whose remaining bytes haven't been read yet
+fn incomplete_utf8_len(buf: &[u8]) -> usize {
+    for (i, &byte) in buf.iter().rev().take(3).enumerate() {
+        let len = match byte {
+            0x80..=0xbf => continue,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return 0,
+        };
+        return if i + 1 < len { i + 1 } else { 0 };
+    }
+    0
+}
@@ -4,6 +4,9 @@ pub struct Command {
     stdin: bool,
     stdout: bool,
     stderr: bool,
+    kill_on_drop: bool,
+    kill_grace_period: std::time::Duration,
+    options: crate::sys::ChildOptions,
     pre_exec_set: bool,
     pre_exec: Option<
         Box<dyn FnMut() -> std::io::Result<()> + Send + Sync + 'static>,
@@ -13,14 +16,7 @@ pub struct Command {
 impl Command {
     /// See [`tokio::process::Command::new`]
     pub fn new<S: AsRef<std::ffi::OsStr>>(program: S) -> Self {
-        Self {
-            inner: tokio::process::Command::new(program),
-            stdin: false,
-            stdout: false,
-            stderr: false,
-            pre_exec_set: false,
-            pre_exec: None,
-        }
+        tokio::process::Command::new(program).into()
     }
 
     /// See [`tokio::process::Command::arg`]
@@ -85,13 +81,61 @@ impl Command {
         self
     }
 
-    /// See [`tokio::process::Command::kill_on_drop`]
+    /// Similar to [`tokio::process::Command::kill_on_drop`], except that
+    /// rather than only killing the child itself, the entire process group
+    /// of the child's session is torn down as described in
+    /// [`Child::kill_session`](crate::Child::kill_session). Also unlike
+    /// tokio, this happens even if the child itself has already exited and
+    /// been waited on, so that any processes it left running in its
+    /// session are cleaned up too. Since dropping cannot block, this
+    /// happens on a background thread.
     #[must_use]
     pub fn kill_on_drop(mut self, kill_on_drop: bool) -> Self {
-        self.inner.kill_on_drop(kill_on_drop);
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    /// Sets how long to wait after sending `SIGHUP` and `SIGTERM` before
+    /// escalating to `SIGKILL` when the child's session is torn down (see
+    /// [`Child::kill_session`](crate::Child::kill_session)). Defaults to
+    /// zero.
+    #[must_use]
+    pub fn kill_grace_period(
+        mut self,
+        grace_period: std::time::Duration,
+    ) -> Self {
+        self.kill_grace_period = grace_period;
         self
     }
 
+    /// See [`std::process::Command::get_program`]
+    #[must_use]
+    pub fn get_program(&self) -> &std::ffi::OsStr {
+        self.inner.as_std().get_program()
+    }
+
+    /// See [`std::process::Command::get_args`]
+    pub fn get_args(&self) -> std::process::CommandArgs<'_> {
+        self.inner.as_std().get_args()
+    }
+
+    /// See [`std::process::Command::get_envs`]
+    pub fn get_envs(&self) -> std::process::CommandEnvs<'_> {
+        self.inner.as_std().get_envs()
+    }
+
+    /// See [`std::process::Command::get_current_dir`]
+    #[must_use]
+    pub fn get_current_dir(&self) -> Option<&std::path::Path> {
+        self.inner.as_std().get_current_dir()
+    }
+
+    /// See [`tokio::process::Command::as_std`]
+    #[must_use]
+    pub fn as_std(&self) -> &std::process::Command {
+        self.inner.as_std()
+    }
+
     /// See [`tokio::process::Command::stdin`]
     #[must_use]
     pub fn stdin<T: Into<std::process::Stdio>>(mut self, cfg: T) -> Self {
@@ -132,10 +176,7 @@ impl Command {
     /// [`tokio::process::Command::spawn`]), or if we fail to make the child a
     /// session leader or set its controlling terminal.
     #[allow(clippy::needless_pass_by_value)]
-    pub fn spawn(
-        mut self,
-        pts: crate::Pts,
-    ) -> crate::Result<tokio::process::Child> {
+    pub fn spawn(mut self, pts: crate::Pts) -> crate::Result<crate::Child> {
         self.spawn_impl(&pts)
     }
 
@@ -162,14 +203,14 @@ impl Command {
     pub fn spawn_borrowed(
         &mut self,
         pts: &crate::Pts,
-    ) -> crate::Result<tokio::process::Child> {
+    ) -> crate::Result<crate::Child> {
         self.spawn_impl(pts)
     }
 
     fn spawn_impl(
         &mut self,
         pts: &crate::Pts,
-    ) -> crate::Result<tokio::process::Child> {
+    ) -> crate::Result<crate::Child> {
         let (stdin, stdout, stderr) = pts.0.setup_subprocess()?;
 
         if !self.stdin {
@@ -183,35 +224,148 @@ impl Command {
         }
 
         let mut session_leader = pts.0.session_leader();
+        let options = self.options.prepare(&pts.0, self.get_current_dir())?;
         // Safety: setsid() is an async-signal-safe function and ioctl() is a
-        // raw syscall (which is inherently async-signal-safe).
+        // raw syscall (which is inherently async-signal-safe). The child
+        // options are only applied via async-signal-safe functions.
         if let Some(mut custom) = self.pre_exec.take() {
             unsafe {
                 self.inner.pre_exec(move || {
                     session_leader()?;
+                    options.apply()?;
                     custom()?;
                     Ok(())
                 })
             };
         } else if !self.pre_exec_set {
-            unsafe { self.inner.pre_exec(session_leader) };
+            unsafe {
+                self.inner.pre_exec(move || {
+                    session_leader()?;
+                    options.apply()?;
+                    Ok(())
+                })
+            };
         }
         self.pre_exec_set = true;
 
-        Ok(self.inner.spawn()?)
+        Ok(crate::Child::new(
+            self.inner.spawn()?,
+            self.kill_on_drop,
+            self.kill_grace_period,
+        ))
     }
 
-    /// See [`tokio::process::Command::uid`]
+    /// Sets the user id of the child. Unlike
+    /// [`tokio::process::Command::uid`], this is applied by this crate once the
+    /// child is a session leader, after the limits and nice value given to
+    /// [`rlimit`](Self::rlimit) and [`nice`](Self::nice) and the groups
+    /// given to [`groups`](Self::groups) and [`gid`](Self::gid) have been
+    /// set. If no supplementary groups were given and the parent is root,
+    /// they are cleared first. The child then changes into its
+    /// [`current_dir`](Self::current_dir) again, so that access to it is
+    /// checked as the new user.
     #[must_use]
     pub fn uid(mut self, id: u32) -> Self {
-        self.inner.uid(id);
+        self.options.uid = Some(id);
         self
     }
 
-    /// See [`tokio::process::Command::gid`]
+    /// Sets the group id of the child. Unlike
+    /// [`tokio::process::Command::gid`], this is applied by this crate once the
+    /// child is a session leader, after the groups given to
+    /// [`groups`](Self::groups) and before the id given to
+    /// [`uid`](Self::uid).
     #[must_use]
     pub fn gid(mut self, id: u32) -> Self {
-        self.inner.gid(id);
+        self.options.gid = Some(id);
+        self
+    }
+
+    /// Sets the supplementary group ids of the child. These are set before
+    /// the ids given to [`uid`](Self::uid) and [`gid`](Self::gid) are
+    /// applied, so this requires the parent to be privileged.
+    #[must_use]
+    pub fn groups(mut self, groups: &[u32]) -> Self {
+        self.options.groups = Some(groups.to_vec());
+        self
+    }
+
+    /// Limits the child's usage of the given resource via `setrlimit`,
+    /// with the given soft and hard limits.
+    #[must_use]
+    pub fn rlimit(
+        mut self,
+        resource: crate::Resource,
+        soft: u64,
+        hard: u64,
+    ) -> Self {
+        self.options.rlimits.push((resource, soft, hard));
+        self
+    }
+
+    /// Sets the nice value of the child. Values lower than the nice value
+    /// of the parent require the parent to be privileged.
+    #[must_use]
+    pub fn nice(mut self, nice: i32) -> Self {
+        self.options.nice = Some(nice);
+        self
+    }
+
+    /// Sets the file mode creation mask of the child.
+    #[must_use]
+    pub fn umask(mut self, mask: u32) -> Self {
+        self.options.umask = Some(mask);
+        self
+    }
+
+    /// Moves the child into new Linux namespaces via `unshare`, after it
+    /// has been made a session leader with the pty as its controlling
+    /// terminal (so the pty remains its controlling terminal inside the new
+    /// namespaces). Creating namespaces other than [`Namespaces::USER`]
+    /// requires the parent to be privileged, unless a user namespace is
+    /// also being created.
+    ///
+    /// [`Namespaces::USER`]: crate::Namespaces::USER
+    #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+    #[must_use]
+    pub fn unshare(mut self, namespaces: crate::Namespaces) -> Self {
+        self.options.namespaces.namespaces |= namespaces;
+        self
+    }
+
+    /// Maps `count` user ids starting at `outside` in the parent's user
+    /// namespace to user ids starting at `inside` in the child's new user
+    /// namespace (see [`unshare`](Self::unshare)). Unprivileged parents can
+    /// only map their own effective user id, with a count of 1.
+    #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+    #[must_use]
+    pub fn uid_map(mut self, inside: u32, outside: u32, count: u32) -> Self {
+        self.options.namespaces.set_uid_map(inside, outside, count);
+        self
+    }
+
+    /// Maps `count` group ids starting at `outside` in the parent's user
+    /// namespace to group ids starting at `inside` in the child's new user
+    /// namespace (see [`unshare`](Self::unshare)). Unprivileged parents can
+    /// only map their own effective group id, with a count of 1. When
+    /// creating a new user namespace, this also disables `setgroups` in the
+    /// child, so it can't be combined with [`groups`](Self::groups).
+    #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+    #[must_use]
+    pub fn gid_map(mut self, inside: u32, outside: u32, count: u32) -> Self {
+        self.options.namespaces.set_gid_map(inside, outside, count);
+        self
+    }
+
+    /// If set, the child gets a private instance of `/dev/pts` (and
+    /// `/dev/ptmx`) in a new mount namespace, so it can't access other ptys
+    /// on the system. Since the pty the child is attached to doesn't exist
+    /// in the new instance, it is bind mounted over `/dev/console` instead.
+    /// This implies [`Namespaces::MOUNT`](crate::Namespaces::MOUNT).
+    #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+    #[must_use]
+    pub fn private_devpts(mut self, private_devpts: bool) -> Self {
+        self.options.namespaces.private_devpts = private_devpts;
         self
     }
 
@@ -236,3 +390,35 @@ impl Command {
         self
     }
 }
+
+impl From<tokio::process::Command> for Command {
+    /// Wraps an existing [`tokio::process::Command`]. Any `stdin`, `stdout`, or
+    /// `stderr` configuration on the given command will be overridden by the
+    /// pty when spawning, unless it is set again via
+    /// [`stdin`](Self::stdin), [`stdout`](Self::stdout), or
+    /// [`stderr`](Self::stderr).
+    ///
+    /// A user or group id already set on the given command via
+    /// [`tokio::process::Command::uid`] or
+    /// [`gid`](tokio::process::Command::gid) is applied by the underlying
+    /// [`std::process::Command`], before anything configured through this
+    /// wrapper. This means that [`groups`](Self::groups),
+    /// [`rlimit`](Self::rlimit), and [`nice`](Self::nice) are applied after
+    /// privileges have been dropped, so setting groups, raising hard
+    /// limits, or lowering the nice value will fail with `EPERM`.
+    /// Set the ids through [`uid`](Self::uid) and [`gid`](Self::gid)
+    /// on this wrapper instead.
+    fn from(inner: tokio::process::Command) -> Self {
+        Self {
+            inner,
+            stdin: false,
+            stdout: false,
+            stderr: false,
+            kill_on_drop: false,
+            kill_grace_period: std::time::Duration::ZERO,
+            options: crate::sys::ChildOptions::default(),
+            pre_exec_set: false,
+            pre_exec: None,
+        }
+    }
+}
@@ -0,0 +1,499 @@
+//! Middleware for transforming the data sent to and received from a pty.
+//!
+//! A [`Filtered`] wraps a pty (or anything else that can be both read from
+//! and written to) along with a chain of [`Filter`]s, each of which can
+//! rewrite, drop, or inject data in either direction. Data is only passed
+//! to the filters in chunks which end on an escape sequence boundary, so a
+//! filter will never see half of a CSI or OSC sequence.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! use std::io::Write as _;
+//!
+//! let (pty, pts) = pty_process::blocking::open()?;
+//! let mut child = pty_process::blocking::Command::new("cat").spawn(pts)?;
+//! let mut pty = pty_process::filter::Filtered::new(pty).filter(
+//!     pty_process::filter::map_output(|data| {
+//!         data.make_ascii_uppercase();
+//!     }),
+//! );
+//! pty.write_all(b"foo\n")?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod line_editor;
+pub use line_editor::LineEditor;
+mod responder;
+pub use responder::Responder;
+
+// incomplete escape sequences longer than this are passed along anyway,
+// to avoid buffering indefinitely when given garbage
+const MAX_PENDING: usize = 64 * 1024;
+
+/// A single step in a chain of filters applied to a [`Filtered`] stream.
+///
+/// All of the methods default to passing data through unchanged.
+pub trait Filter: Send {
+    /// Called with each chunk of data being written to the pty. The chunk
+    /// can be modified in place (including being cleared, to drop it
+    /// entirely), and replies can be sent back to the reader of the stream
+    /// via the given [`Context`].
+    fn input(&mut self, data: &mut Vec<u8>, cx: &mut Context<'_>) {
+        let _ = (data, cx);
+    }
+
+    /// Called instead of [`input`](Self::input) when a secret is written
+    /// with [`Filtered::send_secret`], with the length of the secret. The
+    /// secret itself is written directly to the pty without being passed
+    /// through any filters, but filters which record or log the input can
+    /// use this to note where something was redacted.
+    fn redacted_input(&mut self, len: usize) {
+        let _ = len;
+    }
+
+    /// Called when the pty is resized with [`Filtered::resize`], after the
+    /// new size has been applied.
+    fn resize(&mut self, size: crate::Size) {
+        let _ = size;
+    }
+
+    /// Called with each chunk of data read from the pty. The chunk can be
+    /// modified in place (including being cleared, to drop it entirely),
+    /// and replies can be written back to the pty via the given
+    /// [`Context`].
+    fn output(&mut self, data: &mut Vec<u8>, cx: &mut Context<'_>) {
+        let _ = (data, cx);
+    }
+}
+
+/// Allows a [`Filter`] to send data in the opposite direction from the
+/// chunk it is currently processing.
+#[derive(Debug)]
+pub struct Context<'a> {
+    reply: &'a mut Vec<u8>,
+}
+
+impl Context<'_> {
+    /// Sends data in the opposite direction from the current chunk: replies
+    /// to input are returned to the reader of the stream, and replies to
+    /// output are written to the pty. Replies are not passed through any
+    /// filters.
+    pub fn reply(&mut self, data: &[u8]) {
+        self.reply.extend_from_slice(data);
+    }
+}
+
+/// Returns a [`Filter`] which calls the given function on each chunk of
+/// input.
+pub fn map_input<F>(f: F) -> impl Filter
+where
+    F: FnMut(&mut Vec<u8>) + Send,
+{
+    MapInput(f)
+}
+
+/// Returns a [`Filter`] which calls the given function on each chunk of
+/// output.
+pub fn map_output<F>(f: F) -> impl Filter
+where
+    F: FnMut(&mut Vec<u8>) + Send,
+{
+    MapOutput(f)
+}
+
+struct MapInput<F>(F);
+
+impl<F: FnMut(&mut Vec<u8>) + Send> Filter for MapInput<F> {
+    fn input(&mut self, data: &mut Vec<u8>, _: &mut Context<'_>) {
+        (self.0)(data);
+    }
+}
+
+struct MapOutput<F>(F);
+
+impl<F: FnMut(&mut Vec<u8>) + Send> Filter for MapOutput<F> {
+    fn output(&mut self, data: &mut Vec<u8>, _: &mut Context<'_>) {
+        (self.0)(data);
+    }
+}
+
+/// A stream with a chain of [`Filter`]s applied to the data written to and
+/// read from it.
+///
+/// Filters are ordered from the outside in: input passes through them in
+/// the order they were added before being written to the wrapped stream,
+/// and output passes through them in the reverse order after being read.
+///
+/// Incomplete escape sequences at the end of a chunk are held back until
+/// the rest of the sequence arrives. For input, [`flush`](std::io::Write::flush)
+/// can be used to force them through (for instance, to send a lone press
+/// of the escape key). For output, they are passed through once the
+/// wrapped stream reaches end of file or returns an error.
+///
+/// This implements [`std::io::Read`] and [`std::io::Write`] when the
+/// wrapped stream does (such as [`blocking::Pty`](crate::blocking::Pty)),
+/// and [`tokio::io::AsyncRead`] and [`tokio::io::AsyncWrite`] when the
+/// wrapped stream does (such as [`Pty`](crate::Pty)). Since filters can
+/// reply to output by writing to the pty, reading requires the wrapped
+/// stream to be writable as well.
+pub struct Filtered<T> {
+    inner: T,
+    filters: Vec<Box<dyn Filter>>,
+    input: Vec<u8>,
+    output: Vec<u8>,
+    readable: std::collections::VecDeque<u8>,
+    writable: std::collections::VecDeque<u8>,
+    eof: bool,
+}
+
+impl<T> Filtered<T> {
+    /// Wraps the given stream, with no filters.
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            filters: vec![],
+            input: vec![],
+            output: vec![],
+            readable: std::collections::VecDeque::new(),
+            writable: std::collections::VecDeque::new(),
+            eof: false,
+        }
+    }
+
+    /// Adds a filter to the end of the chain (the side closest to the
+    /// wrapped stream).
+    #[must_use]
+    pub fn filter(mut self, filter: impl Filter + 'static) -> Self {
+        self.push(filter);
+        self
+    }
+
+    /// Adds a filter to the end of the chain (the side closest to the
+    /// wrapped stream).
+    pub fn push(&mut self, filter: impl Filter + 'static) {
+        self.filters.push(Box::new(filter));
+    }
+
+    /// Returns a reference to the wrapped stream.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped stream. Reading from or
+    /// writing to it directly will bypass the filters.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped stream. Any data which has been processed by
+    /// the filters but not yet read or written is discarded.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn process_input(&mut self, data: &[u8], force: bool) {
+        self.input.extend_from_slice(data);
+        let len = if force {
+            self.input.len()
+        } else {
+            crate::ansi::complete_len(&self.input, MAX_PENDING)
+        };
+        if len == 0 {
+            return;
+        }
+        let mut chunk: Vec<u8> = self.input.drain(..len).collect();
+        let mut reply = vec![];
+        for filter in &mut self.filters {
+            filter.input(&mut chunk, &mut Context { reply: &mut reply });
+        }
+        self.writable.extend(chunk);
+        self.readable.extend(reply);
+    }
+
+    fn process_output(&mut self, data: &[u8], force: bool) {
+        self.output.extend_from_slice(data);
+        let len = if force {
+            self.output.len()
+        } else {
+            crate::ansi::complete_len(&self.output, MAX_PENDING)
+        };
+        if len == 0 {
+            return;
+        }
+        let mut chunk: Vec<u8> = self.output.drain(..len).collect();
+        let mut reply = vec![];
+        for filter in self.filters.iter_mut().rev() {
+            filter.output(&mut chunk, &mut Context { reply: &mut reply });
+        }
+        self.readable.extend(chunk);
+        self.writable.extend(reply);
+    }
+
+    // pending input has to be written before the secret, to keep it in
+    // order
+    fn process_secret(&mut self, secret: &crate::SecretBytes) {
+        self.process_input(&[], true);
+        for filter in &mut self.filters {
+            filter.redacted_input(secret.len());
+        }
+    }
+
+    fn notify_resize(&mut self, size: crate::Size) {
+        for filter in &mut self.filters {
+            filter.resize(size);
+        }
+    }
+
+    fn take_readable(&mut self, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(self.readable.len());
+        for (dst, src) in buf.iter_mut().zip(self.readable.drain(..len)) {
+            *dst = src;
+        }
+        len
+    }
+}
+
+impl Filtered<crate::blocking::Pty> {
+    /// Resizes the wrapped pty (see
+    /// [`blocking::Pty::resize`](crate::blocking::Pty::resize)), and then
+    /// calls each filter's [`resize`](Filter::resize) method.
+    ///
+    /// # Errors
+    /// Returns an error if the size couldn't be set.
+    pub fn resize(&mut self, size: crate::Size) -> crate::Result<()> {
+        self.inner.resize(size)?;
+        self.notify_resize(size);
+        Ok(())
+    }
+}
+
+#[cfg(feature = "async")]
+impl Filtered<crate::Pty> {
+    /// Resizes the wrapped pty (see [`Pty::resize`](crate::Pty::resize)),
+    /// and then calls each filter's [`resize`](Filter::resize) method.
+    ///
+    /// # Errors
+    /// Returns an error if the size couldn't be set.
+    pub fn resize(&mut self, size: crate::Size) -> crate::Result<()> {
+        self.inner.resize(size)?;
+        self.notify_resize(size);
+        Ok(())
+    }
+}
+
+impl<T: std::io::Write> Filtered<T> {
+    /// Writes a secret, such as a password, directly to the wrapped
+    /// stream. The secret isn't passed through the filters: instead, each
+    /// filter's [`redacted_input`](Filter::redacted_input) method is
+    /// called.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the wrapped stream fails.
+    pub fn send_secret(
+        &mut self,
+        secret: &crate::SecretBytes,
+    ) -> std::io::Result<()> {
+        self.process_secret(secret);
+        self.write_pending()?;
+        self.inner.write_all(secret.expose())
+    }
+
+    fn write_pending(&mut self) -> std::io::Result<()> {
+        while !self.writable.is_empty() {
+            let (data, _) = self.writable.as_slices();
+            match self.inner.write(data) {
+                Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+                Ok(bytes) => {
+                    self.writable.drain(..bytes);
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<T: std::io::Read + std::io::Write> std::io::Read for Filtered<T> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut data = [0_u8; 4096];
+        loop {
+            self.write_pending()?;
+            if !self.readable.is_empty() {
+                return Ok(self.take_readable(buf));
+            }
+            if self.eof {
+                return Ok(0);
+            }
+            match self.inner.read(&mut data) {
+                Ok(0) => {
+                    self.eof = true;
+                    self.process_output(&[], true);
+                }
+                Ok(bytes) => self.process_output(&data[..bytes], false),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.process_output(&[], true);
+                    if self.readable.is_empty() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<T: std::io::Write> std::io::Write for Filtered<T> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.process_input(buf, false);
+        self.write_pending()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.process_input(&[], true);
+        self.write_pending()?;
+        self.inner.flush()
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: tokio::io::AsyncWrite + Unpin> Filtered<T> {
+    /// Writes a secret, such as a password, directly to the wrapped
+    /// stream. The secret isn't passed through the filters: instead, each
+    /// filter's [`redacted_input`](Filter::redacted_input) method is
+    /// called.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the wrapped stream fails.
+    pub async fn send_secret_async(
+        &mut self,
+        secret: &crate::SecretBytes,
+    ) -> std::io::Result<()> {
+        self.process_secret(secret);
+        std::future::poll_fn(|cx| self.poll_write_pending(cx)).await?;
+        tokio::io::AsyncWriteExt::write_all(&mut self.inner, secret.expose())
+            .await
+    }
+
+    fn poll_write_pending(
+        &mut self,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        while !self.writable.is_empty() {
+            let (data, _) = self.writable.as_slices();
+            match std::task::ready!(
+                std::pin::Pin::new(&mut self.inner).poll_write(cx, data)
+            ) {
+                Ok(0) => {
+                    return std::task::Poll::Ready(Err(
+                        std::io::ErrorKind::WriteZero.into(),
+                    ));
+                }
+                Ok(bytes) => {
+                    self.writable.drain(..bytes);
+                }
+                Err(e) => return std::task::Poll::Ready(Err(e)),
+            }
+        }
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin>
+    tokio::io::AsyncRead for Filtered<T>
+{
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let mut data = [0_u8; 4096];
+        loop {
+            // replies are sent on a best effort basis here, since we don't
+            // want to block reading on the pty accepting more input
+            if let std::task::Poll::Ready(Err(e)) =
+                this.poll_write_pending(cx)
+            {
+                return std::task::Poll::Ready(Err(e));
+            }
+            if !this.readable.is_empty() {
+                let len = this.take_readable(buf.initialize_unfilled());
+                buf.advance(len);
+                return std::task::Poll::Ready(Ok(()));
+            }
+            if this.eof {
+                return std::task::Poll::Ready(Ok(()));
+            }
+            let mut read_buf = tokio::io::ReadBuf::new(&mut data);
+            match std::task::ready!(
+                std::pin::Pin::new(&mut this.inner)
+                    .poll_read(cx, &mut read_buf)
+            ) {
+                Ok(()) if read_buf.filled().is_empty() => {
+                    this.eof = true;
+                    this.process_output(&[], true);
+                }
+                Ok(()) => this.process_output(read_buf.filled(), false),
+                Err(e) => {
+                    this.process_output(&[], true);
+                    if this.readable.is_empty() {
+                        return std::task::Poll::Ready(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: tokio::io::AsyncWrite + Unpin> tokio::io::AsyncWrite for Filtered<T> {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        let this = self.get_mut();
+        std::task::ready!(this.poll_write_pending(cx))?;
+        this.process_input(buf, false);
+        // the data has been accepted at this point, and any of it which
+        // can't be written yet will be written by the next call to
+        // poll_write or poll_flush
+        if let std::task::Poll::Ready(Err(e)) = this.poll_write_pending(cx) {
+            return std::task::Poll::Ready(Err(e));
+        }
+        std::task::Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        this.process_input(&[], true);
+        std::task::ready!(this.poll_write_pending(cx))?;
+        std::pin::Pin::new(&mut this.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        this.process_input(&[], true);
+        std::task::ready!(this.poll_write_pending(cx))?;
+        std::pin::Pin::new(&mut this.inner).poll_shutdown(cx)
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Filtered<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Filtered")
+            .field("inner", &self.inner)
+            .field("filters", &self.filters.len())
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,370 @@
+/// A [`Filter`](super::Filter) which edits lines of input locally before
+/// sending them to the pty.
+///
+/// This is useful when the connection to the user has too much latency for
+/// the pty's own line editing to be pleasant (such as in a web UI).
+///
+/// While the child has the pty in canonical mode with echo enabled (as
+/// most programs which don't draw their own interface do), input is
+/// buffered and echoed back to the reader immediately, and only sent to
+/// the pty once a whole line has been entered. The echo of the line which
+/// the pty sends back when it arrives is then removed from the output, so
+/// it isn't displayed twice. The following keys are handled locally:
+/// * the pty's erase character (usually `^?`) and `^H`, to delete the
+///   previous character
+/// * the pty's word erase character (usually `^W`), to delete the previous
+///   word
+/// * the pty's kill character (usually `^U`), to delete the whole line
+/// * the up and down arrow keys, to move through previously entered lines
+///
+/// The pty's interrupt, quit, and suspend characters (usually `^C`, `^\`,
+/// and `^Z`) discard the buffered line and are sent on immediately, and
+/// any other control character sends the buffered line as it is, followed
+/// by that character. Other escape sequences (such as the left and right
+/// arrow keys) are handled the same way, since only appending to the end
+/// of the line is supported.
+///
+/// Whenever the child switches the pty out of canonical mode or disables
+/// echo (for instance, when starting a full screen program or prompting
+/// for a password), input is passed through unchanged, along with any
+/// partial line which was buffered at the time. The terminal settings are
+/// checked again before each chunk of input is edited, so that nothing is
+/// echoed locally once the child has disabled echo. While input is being
+/// passed through, they are only checked again once the child has written
+/// some output, since programs which enable line editing again almost
+/// always write something (such as a prompt) at the same time.
+///
+/// ```no_run
+/// # fn main() -> pty_process::Result<()> {
+/// let (pty, pts) = pty_process::blocking::open()?;
+/// let mut child = pty_process::blocking::Command::new("sh").spawn(pts)?;
+/// let editor = pty_process::filter::LineEditor::new(&pty)?;
+/// let pty = pty_process::filter::Filtered::new(pty).filter(editor);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct LineEditor {
+    fd: std::os::fd::OwnedFd,
+    parser: crate::ansi::Parser,
+    line: Vec<u8>,
+    history: std::collections::VecDeque<Vec<u8>>,
+    history_size: usize,
+    // index into history of the line currently being displayed, if the
+    // user has moved back through the history
+    history_pos: Option<usize>,
+    // the echo of each submitted line which the pty hasn't sent back yet
+    echo: std::collections::VecDeque<Vec<u8>>,
+    // the terminal settings as of the last time they were checked, or None
+    // if they may have changed since then
+    termios: Option<rustix::termios::Termios>,
+    // output from the end of the last read which matches the start of an
+    // echo, held back until the next read shows whether it continues
+    partial_echo: Vec<u8>,
+    // the bytes of the escape sequence currently being parsed
+    sequence: Vec<u8>,
+}
+
+impl LineEditor {
+    /// Creates a new `LineEditor` for the given pty, which is used to
+    /// check the terminal settings that the child has set. By default, the
+    /// last 100 lines are kept in the history.
+    ///
+    /// # Errors
+    /// Returns an error if the file descriptor can't be duplicated.
+    pub fn new(pty: impl std::os::fd::AsFd) -> crate::Result<Self> {
+        Ok(Self {
+            fd: pty.as_fd().try_clone_to_owned()?,
+            parser: crate::ansi::Parser::default(),
+            line: vec![],
+            history: std::collections::VecDeque::new(),
+            history_size: 100,
+            history_pos: None,
+            echo: std::collections::VecDeque::new(),
+            termios: None,
+            partial_echo: vec![],
+            sequence: vec![],
+        })
+    }
+
+    /// Sets the number of previously entered lines to keep. A size of 0
+    /// disables the history.
+    #[must_use]
+    pub fn history_size(mut self, size: usize) -> Self {
+        self.history_size = size;
+        self.history.truncate(size);
+        self
+    }
+
+    /// Returns the partial line which has been entered but not yet sent.
+    #[must_use]
+    pub fn line(&self) -> &[u8] {
+        &self.line
+    }
+
+    /// Returns the previously entered lines, oldest first.
+    pub fn history(&self) -> impl Iterator<Item = &[u8]> {
+        self.history.iter().map(Vec::as_slice)
+    }
+
+    fn process(
+        &mut self,
+        input: &[u8],
+        termios: &rustix::termios::Termios,
+        data: &mut Vec<u8>,
+        echo: &mut Vec<u8>,
+    ) {
+        // a special character of 0 means that it is disabled
+        let special = |index| {
+            Some(termios.special_codes[index]).filter(|&byte| byte != 0)
+        };
+        for &byte in input {
+            self.sequence.push(byte);
+            let key = match self.parser.advance(byte) {
+                Some(crate::ansi::Action::Print(byte)) => Key::Char(byte),
+                Some(crate::ansi::Action::Control(byte)) => {
+                    Key::Control(byte)
+                }
+                Some(
+                    crate::ansi::Action::Csi(b"A")
+                    | crate::ansi::Action::Escape(b"OA"),
+                ) => Key::Up,
+                Some(
+                    crate::ansi::Action::Csi(b"B")
+                    | crate::ansi::Action::Escape(b"OB"),
+                ) => Key::Down,
+                // including sequences which were aborted partway through
+                Some(_) | None => Key::Sequence,
+            };
+            if matches!(key, Key::Sequence) && self.parser.in_sequence() {
+                continue;
+            }
+            let sequence = std::mem::take(&mut self.sequence);
+            match key {
+                Key::Char(byte) | Key::Control(byte @ b'\t') => {
+                    self.line.push(byte);
+                    echo.push(byte);
+                }
+                Key::Control(byte @ (b'\r' | b'\n')) => {
+                    self.submit(byte, termios, data);
+                    echo.extend_from_slice(b"\r\n");
+                }
+                Key::Control(byte)
+                    if byte == b'\x08'
+                        || Some(byte)
+                            == special(
+                                rustix::termios::SpecialCodeIndex::VERASE,
+                            ) =>
+                {
+                    erase_char(&mut self.line, echo);
+                }
+                Key::Control(byte)
+                    if Some(byte)
+                        == special(
+                            rustix::termios::SpecialCodeIndex::VWERASE,
+                        ) =>
+                {
+                    while self
+                        .line
+                        .last()
+                        .is_some_and(u8::is_ascii_whitespace)
+                    {
+                        erase_char(&mut self.line, echo);
+                    }
+                    while self
+                        .line
+                        .last()
+                        .is_some_and(|byte| !byte.is_ascii_whitespace())
+                    {
+                        erase_char(&mut self.line, echo);
+                    }
+                }
+                Key::Control(byte)
+                    if Some(byte)
+                        == special(
+                            rustix::termios::SpecialCodeIndex::VKILL,
+                        ) =>
+                {
+                    while !self.line.is_empty() {
+                        erase_char(&mut self.line, echo);
+                    }
+                }
+                Key::Control(byte)
+                    if [
+                        rustix::termios::SpecialCodeIndex::VINTR,
+                        rustix::termios::SpecialCodeIndex::VQUIT,
+                        rustix::termios::SpecialCodeIndex::VSUSP,
+                    ]
+                    .into_iter()
+                    .any(|index| Some(byte) == special(index)) =>
+                {
+                    // the pty discards its own partial line in this case
+                    // too
+                    self.line.clear();
+                    self.history_pos = None;
+                    data.push(byte);
+                }
+                Key::Control(_) | Key::Sequence => {
+                    if !self.line.is_empty() {
+                        self.echo.push_back(self.line.clone());
+                    }
+                    data.append(&mut self.line);
+                    self.history_pos = None;
+                    data.extend_from_slice(&sequence);
+                }
+                Key::Up => {
+                    let pos = match self.history_pos {
+                        Some(pos) => pos.saturating_sub(1),
+                        None if self.history.is_empty() => continue,
+                        None => self.history.len() - 1,
+                    };
+                    self.recall(Some(pos), echo);
+                }
+                Key::Down => match self.history_pos {
+                    Some(pos) if pos + 1 < self.history.len() => {
+                        self.recall(Some(pos + 1), echo);
+                    }
+                    Some(_) => self.recall(None, echo),
+                    None => {}
+                },
+            }
+        }
+    }
+
+    fn submit(
+        &mut self,
+        byte: u8,
+        termios: &rustix::termios::Termios,
+        data: &mut Vec<u8>,
+    ) {
+        let mut echo = self.line.clone();
+        // this is how the pty will echo the newline back
+        if termios
+            .output_modes
+            .contains(rustix::termios::OutputModes::OPOST)
+            && termios
+                .output_modes
+                .contains(rustix::termios::OutputModes::ONLCR)
+        {
+            echo.push(b'\r');
+        }
+        echo.push(b'\n');
+        self.echo.push_back(echo);
+
+        if self.history_size > 0
+            && !self.line.is_empty()
+            && self.history.back() != Some(&self.line)
+        {
+            if self.history.len() >= self.history_size {
+                self.history.pop_front();
+            }
+            self.history.push_back(self.line.clone());
+        }
+        self.history_pos = None;
+        data.append(&mut self.line);
+        data.push(byte);
+    }
+
+    fn recall(&mut self, pos: Option<usize>, echo: &mut Vec<u8>) {
+        while !self.line.is_empty() {
+            erase_char(&mut self.line, echo);
+        }
+        self.history_pos = pos;
+        if let Some(pos) = pos {
+            self.line.clone_from(&self.history[pos]);
+            echo.extend_from_slice(&self.line);
+        }
+    }
+}
+
+impl super::Filter for LineEditor {
+    fn input(&mut self, data: &mut Vec<u8>, cx: &mut super::Context<'_>) {
+        // passing input through is always safe, but editing has to stop as
+        // soon as the child disables echo, even if it hasn't written
+        // anything since
+        if self.termios.as_ref().is_none_or(is_editing) {
+            self.termios = rustix::termios::tcgetattr(&self.fd).ok();
+        }
+        let Some(termios) = self.termios.clone().filter(is_editing) else {
+            if !self.line.is_empty() {
+                data.splice(0..0, std::mem::take(&mut self.line));
+                self.history_pos = None;
+            }
+            return;
+        };
+
+        let input = std::mem::take(data);
+        let mut echo = vec![];
+        self.process(&input, &termios, data, &mut echo);
+        cx.reply(&echo);
+    }
+
+    fn output(&mut self, data: &mut Vec<u8>, _: &mut super::Context<'_>) {
+        if !data.is_empty() {
+            self.termios = None;
+        }
+
+        // the child may write something else before (or between) the
+        // echoes of the submitted lines, so an echo is only removed where
+        // it appears in full. if a later echo is found first, the earlier
+        // ones are assumed to have been lost (for instance, because the
+        // child flushed its input) and are given up on.
+        let mut input = std::mem::take(&mut self.partial_echo);
+        input.extend_from_slice(data);
+        let mut output = Vec::with_capacity(input.len());
+        let mut rest = &input[..];
+        while let Some(&byte) = rest.first() {
+            let found = self.echo.iter().enumerate().find_map(|(i, echo)| {
+                let len = rest
+                    .iter()
+                    .zip(echo)
+                    .take_while(|(byte, expected)| byte == expected)
+                    .count();
+                (len == echo.len() || len == rest.len()).then_some((i, len))
+            });
+            match found {
+                Some((i, len)) if len < self.echo[i].len() => {
+                    self.partial_echo = rest.to_vec();
+                    break;
+                }
+                Some((i, len)) => {
+                    self.echo.drain(..=i);
+                    rest = &rest[len..];
+                }
+                None => {
+                    output.push(byte);
+                    rest = &rest[1..];
+                }
+            }
+        }
+        *data = output;
+    }
+}
+
+enum Key {
+    Char(u8),
+    Control(u8),
+    Up,
+    Down,
+    Sequence,
+}
+
+fn is_editing(termios: &rustix::termios::Termios) -> bool {
+    termios.local_modes.contains(
+        rustix::termios::LocalModes::ICANON
+            | rustix::termios::LocalModes::ECHO,
+    )
+}
+
+fn erase_char(line: &mut Vec<u8>, echo: &mut Vec<u8>) {
+    if line.is_empty() {
+        return;
+    }
+    // remove a whole utf8 character at a time
+    while let Some(byte) = line.pop() {
+        if byte & 0xc0 != 0x80 {
+            break;
+        }
+    }
+    echo.extend_from_slice(b"\x08 \x08");
+}
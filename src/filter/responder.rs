@@ -0,0 +1,183 @@
+/// A [`Filter`](super::Filter) which answers queries that programs send to
+/// the terminal, for programs which would otherwise hang waiting for a
+/// reply.
+///
+/// The following queries are answered:
+/// * Device Status Report (`CSI 5 n`), with `CSI 0 n`
+/// * Cursor Position Report (`CSI 6 n` and `CSI ? 6 n`), with the
+///   configured [`cursor_position`](Self::cursor_position)
+/// * Primary Device Attributes (`CSI c`), with the configured
+///   [`primary_device_attributes`](Self::primary_device_attributes)
+/// * Secondary Device Attributes (`CSI > c`), with the configured
+///   [`secondary_device_attributes`](Self::secondary_device_attributes)
+/// * `XTVERSION` (`CSI > q`), with the configured [`version`](Self::version)
+/// * Foreground, background, and cursor color queries (`OSC 10 ; ?`,
+///   `OSC 11 ; ?`, and `OSC 12 ; ?`), with the configured
+///   [`foreground`](Self::foreground), [`background`](Self::background),
+///   and [`cursor_color`](Self::cursor_color)
+///
+/// The queries themselves are passed through unchanged.
+///
+/// ```no_run
+/// # fn main() -> pty_process::Result<()> {
+/// let (pty, pts) = pty_process::blocking::open()?;
+/// let mut child = pty_process::blocking::Command::new("fish").spawn(pts)?;
+/// let pty = pty_process::filter::Filtered::new(pty)
+///     .filter(pty_process::filter::Responder::new().cursor_position(0, 0));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Responder {
+    parser: crate::ansi::Parser,
+    replies: Replies,
+}
+
+impl Responder {
+    /// Creates a new `Responder` with the default replies: the cursor is at
+    /// the top left corner, the device attributes are those of a VT220,
+    /// the version is the name and version of this crate, and the colors
+    /// are light gray on black.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            parser: crate::ansi::Parser::default(),
+            replies: Replies {
+                cursor_position: (0, 0),
+                primary_device_attributes: b"\x1b[?62;22c".to_vec(),
+                secondary_device_attributes: b"\x1b[>1;10;0c".to_vec(),
+                version: format!(
+                    "{}({})",
+                    env!("CARGO_PKG_NAME"),
+                    env!("CARGO_PKG_VERSION")
+                ),
+                foreground: (0xc0, 0xc0, 0xc0),
+                background: (0, 0, 0),
+                cursor_color: (0xc0, 0xc0, 0xc0),
+            },
+        }
+    }
+
+    /// Sets the cursor position to report. Rows and columns start at 0 for
+    /// the top left corner of the terminal.
+    #[must_use]
+    pub fn cursor_position(mut self, row: u16, col: u16) -> Self {
+        self.replies.cursor_position = (row, col);
+        self
+    }
+
+    /// Sets the full reply to send for Primary Device Attributes queries,
+    /// including the leading `CSI ?` and trailing `c`.
+    #[must_use]
+    pub fn primary_device_attributes(mut self, reply: &[u8]) -> Self {
+        self.replies.primary_device_attributes = reply.to_vec();
+        self
+    }
+
+    /// Sets the full reply to send for Secondary Device Attributes
+    /// queries, including the leading `CSI >` and trailing `c`.
+    #[must_use]
+    pub fn secondary_device_attributes(mut self, reply: &[u8]) -> Self {
+        self.replies.secondary_device_attributes = reply.to_vec();
+        self
+    }
+
+    /// Sets the terminal name and version to report for `XTVERSION`
+    /// queries.
+    #[must_use]
+    pub fn version(mut self, version: &str) -> Self {
+        self.replies.version = version.to_string();
+        self
+    }
+
+    /// Sets the foreground color to report.
+    #[must_use]
+    pub fn foreground(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.replies.foreground = (r, g, b);
+        self
+    }
+
+    /// Sets the background color to report.
+    #[must_use]
+    pub fn background(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.replies.background = (r, g, b);
+        self
+    }
+
+    /// Sets the cursor color to report.
+    #[must_use]
+    pub fn cursor_color(mut self, r: u8, g: u8, b: u8) -> Self {
+        self.replies.cursor_color = (r, g, b);
+        self
+    }
+}
+
+impl Default for Responder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl super::Filter for Responder {
+    fn output(&mut self, data: &mut Vec<u8>, cx: &mut super::Context<'_>) {
+        for &byte in data.iter() {
+            if let Some(action) = self.parser.advance(byte)
+                && let Some(reply) = self.replies.reply(&action)
+            {
+                cx.reply(&reply);
+            }
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Replies {
+    cursor_position: (u16, u16),
+    primary_device_attributes: Vec<u8>,
+    secondary_device_attributes: Vec<u8>,
+    version: String,
+    foreground: (u8, u8, u8),
+    background: (u8, u8, u8),
+    cursor_color: (u8, u8, u8),
+}
+
+impl Replies {
+    fn reply(&self, action: &crate::ansi::Action<'_>) -> Option<Vec<u8>> {
+        let (row, col) = self.cursor_position;
+        let (row, col) = (u32::from(row) + 1, u32::from(col) + 1);
+        match *action {
+            crate::ansi::Action::Csi(b"5n") => Some(b"\x1b[0n".to_vec()),
+            crate::ansi::Action::Csi(b"6n") => {
+                Some(format!("\x1b[{row};{col}R").into_bytes())
+            }
+            crate::ansi::Action::Csi(b"?6n") => {
+                Some(format!("\x1b[?{row};{col}R").into_bytes())
+            }
+            crate::ansi::Action::Csi(b"c" | b"0c") => {
+                Some(self.primary_device_attributes.clone())
+            }
+            crate::ansi::Action::Csi(b">c" | b">0c") => {
+                Some(self.secondary_device_attributes.clone())
+            }
+            crate::ansi::Action::Csi(b">q" | b">0q") => {
+                Some(format!("\x1bP>|{}\x1b\\", self.version).into_bytes())
+            }
+            crate::ansi::Action::String(b']', query) => {
+                let (n, (r, g, b)) = match query {
+                    b"10;?" => (10, self.foreground),
+                    b"11;?" => (11, self.background),
+                    b"12;?" => (12, self.cursor_color),
+                    _ => return None,
+                };
+                Some(
+                    format!(
+                        "\x1b]{n};rgb:{r:02x}{r:02x}/{g:02x}{g:02x}/\
+                        {b:02x}{b:02x}\x1b\\"
+                    )
+                    .into_bytes(),
+                )
+            }
+            _ => None,
+        }
+    }
+}
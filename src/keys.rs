@@ -0,0 +1,544 @@
+//! Encoding of keystrokes, pastes, and mouse events as the byte sequences
+//! that a terminal would send for them.
+//!
+//! The encoding of some keys depends on modes that the program running on
+//! the pty can set (such as application cursor keys). Once
+//! [`Pty::track_modes`](crate::blocking::Pty::track_modes) has been
+//! called, these modes are tracked from the output read from the pty, so
+//! [`Pty::send_key`](crate::blocking::Pty::send_key) will use the correct
+//! encoding as long as all output is read through the same `Pty`.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! use pty_process::keys::{Key, Modifiers};
+//!
+//! let (pty, pts) = pty_process::blocking::open()?;
+//! pty.track_modes();
+//! let mut child = pty_process::blocking::Command::new("vim").spawn(pts)?;
+//! pty.send_key(Key::Char('i'))?;
+//! pty.send_paste(b"hello world")?;
+//! pty.send_key(Key::Escape)?;
+//! pty.send_key(Key::Char('w').with(Modifiers::CTRL))?;
+//! # Ok(())
+//! # }
+//! ```
+
+/// A key on a keyboard.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    /// A key which produces a character. Combined with
+    /// [`Modifiers::CTRL`], letters and the characters `@[\]^_ ?` produce
+    /// the corresponding control character.
+    Char(char),
+    /// Enter (or Return)
+    Enter,
+    /// Tab. Combined with [`Modifiers::SHIFT`], this produces a back tab.
+    Tab,
+    /// Backspace
+    Backspace,
+    /// Escape
+    Escape,
+    /// Up arrow
+    Up,
+    /// Down arrow
+    Down,
+    /// Right arrow
+    Right,
+    /// Left arrow
+    Left,
+    /// Home
+    Home,
+    /// End
+    End,
+    /// Page Up
+    PageUp,
+    /// Page Down
+    PageDown,
+    /// Insert
+    Insert,
+    /// Delete
+    Delete,
+    /// A function key, from `F(1)` to `F(12)`. Other numbers produce no
+    /// output.
+    F(u8),
+    /// A key on the numeric keypad: one of the digits or `*+,-./=`. These
+    /// send different sequences when the application keypad mode is set.
+    Keypad(char),
+    /// The Enter key on the numeric keypad
+    KeypadEnter,
+}
+
+impl Key {
+    /// Returns this key combined with the given modifiers.
+    #[must_use]
+    pub const fn with(self, modifiers: Modifiers) -> KeyPress {
+        KeyPress {
+            key: self,
+            modifiers,
+        }
+    }
+}
+
+/// A set of modifier keys held down while pressing a [`Key`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers(u8);
+
+impl Modifiers {
+    /// The Shift key
+    pub const SHIFT: Self = Self(1);
+    /// The Alt (or Meta) key
+    pub const ALT: Self = Self(2);
+    /// The Control key
+    pub const CTRL: Self = Self(4);
+
+    /// Returns an empty set of modifiers.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Returns true if no modifiers are contained in this set.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns true if all of the modifiers in `other` are contained in
+    /// this set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    // the modifier parameter used by xterm in CSI sequences
+    const fn param(self) -> u8 {
+        self.0 + 1
+    }
+}
+
+impl std::ops::BitOr for Modifiers {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Modifiers {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+/// A [`Key`] along with the [`Modifiers`] held down while pressing it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyPress {
+    key: Key,
+    modifiers: Modifiers,
+}
+
+impl KeyPress {
+    /// Returns the key which was pressed.
+    #[must_use]
+    pub const fn key(&self) -> Key {
+        self.key
+    }
+
+    /// Returns the modifiers which were held down.
+    #[must_use]
+    pub const fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    /// Returns the bytes that a terminal in the given modes would send for
+    /// this key press.
+    #[must_use]
+    pub fn encode(&self, modes: Modes) -> Vec<u8> {
+        let mods = self.modifiers;
+        let alt = if mods.contains(Modifiers::ALT) {
+            &b"\x1b"[..]
+        } else {
+            &b""[..]
+        };
+        match self.key {
+            Key::Char(c) => {
+                let mut buf = alt.to_vec();
+                if mods.contains(Modifiers::CTRL)
+                    && let Some(byte) = ctrl(c)
+                {
+                    buf.push(byte);
+                } else {
+                    buf.extend_from_slice(
+                        c.encode_utf8(&mut [0; 4]).as_bytes(),
+                    );
+                }
+                buf
+            }
+            Key::Enter => [alt, b"\r"].concat(),
+            Key::Tab if mods.contains(Modifiers::SHIFT) => {
+                [alt, b"\x1b[Z"].concat()
+            }
+            Key::Tab => [alt, b"\t"].concat(),
+            Key::Backspace if mods.contains(Modifiers::CTRL) => {
+                [alt, b"\x08"].concat()
+            }
+            Key::Backspace => [alt, b"\x7f"].concat(),
+            Key::Escape => [alt, b"\x1b"].concat(),
+            Key::Up => cursor(b'A', mods, modes),
+            Key::Down => cursor(b'B', mods, modes),
+            Key::Right => cursor(b'C', mods, modes),
+            Key::Left => cursor(b'D', mods, modes),
+            Key::Home => cursor(b'H', mods, modes),
+            Key::End => cursor(b'F', mods, modes),
+            Key::Insert => tilde(2, mods),
+            Key::Delete => tilde(3, mods),
+            Key::PageUp => tilde(5, mods),
+            Key::PageDown => tilde(6, mods),
+            Key::F(n @ 1..=4) => {
+                let c = b'P' + (n - 1);
+                if mods.is_empty() {
+                    vec![0x1b, b'O', c]
+                } else {
+                    format!("\x1b[1;{}", mods.param())
+                        .into_bytes()
+                        .into_iter()
+                        .chain([c])
+                        .collect()
+                }
+            }
+            Key::F(n @ 5..=12) => tilde(
+                [15, 17, 18, 19, 20, 21, 23, 24][usize::from(n - 5)],
+                mods,
+            ),
+            Key::F(_) => vec![],
+            Key::Keypad(c) => {
+                if modes.application_keypad()
+                    && let Some(c) = keypad(c)
+                {
+                    vec![0x1b, b'O', c]
+                } else {
+                    [alt, c.encode_utf8(&mut [0; 4]).as_bytes()].concat()
+                }
+            }
+            Key::KeypadEnter => {
+                if modes.application_keypad() {
+                    b"\x1bOM".to_vec()
+                } else {
+                    [alt, b"\r"].concat()
+                }
+            }
+        }
+    }
+}
+
+impl From<Key> for KeyPress {
+    fn from(key: Key) -> Self {
+        key.with(Modifiers::empty())
+    }
+}
+
+fn ctrl(c: char) -> Option<u8> {
+    match c {
+        '@' | ' ' => Some(0),
+        'a'..='z' => u8::try_from(c).ok().map(|c| c - b'a' + 1),
+        'A'..='_' => u8::try_from(c).ok().map(|c| c - b'A' + 1),
+        '?' => Some(0x7f),
+        _ => None,
+    }
+}
+
+fn keypad(c: char) -> Option<u8> {
+    match c {
+        '0'..='9' | '*' | '+' | ',' | '-' | '.' | '/' => {
+            u8::try_from(c).ok().map(|c| c - b'*' + b'j')
+        }
+        '=' => Some(b'X'),
+        _ => None,
+    }
+}
+
+fn cursor(c: u8, mods: Modifiers, modes: Modes) -> Vec<u8> {
+    if !mods.is_empty() {
+        let mut buf = format!("\x1b[1;{}", mods.param()).into_bytes();
+        buf.push(c);
+        buf
+    } else if modes.application_cursor() {
+        vec![0x1b, b'O', c]
+    } else {
+        vec![0x1b, b'[', c]
+    }
+}
+
+fn tilde(n: u8, mods: Modifiers) -> Vec<u8> {
+    if mods.is_empty() {
+        format!("\x1b[{n}~").into_bytes()
+    } else {
+        format!("\x1b[{n};{}~", mods.param()).into_bytes()
+    }
+}
+
+/// Returns the bytes that a terminal in the given modes would send when
+/// the given text is pasted into it.
+///
+/// If bracketed paste mode is set, the text is wrapped in bracketed paste markers (and any bracketed paste
+/// markers within the text itself are removed, so that the text can't end
+/// the paste early).
+#[must_use]
+pub fn encode_paste(text: &[u8], modes: Modes) -> Vec<u8> {
+    const START: &[u8] = b"\x1b[200~";
+    const END: &[u8] = b"\x1b[201~";
+
+    if !modes.bracketed_paste() {
+        return text.to_vec();
+    }
+    let mut buf = START.to_vec();
+    let mut rest = text;
+    while !rest.is_empty() {
+        if rest.starts_with(START) || rest.starts_with(END) {
+            rest = &rest[START.len()..];
+        } else {
+            buf.push(rest[0]);
+            rest = &rest[1..];
+        }
+    }
+    buf.extend_from_slice(END);
+    buf
+}
+
+/// A mouse button.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseButton {
+    /// The left (primary) button
+    Left,
+    /// The middle button
+    Middle,
+    /// The right (secondary) button
+    Right,
+}
+
+/// The kind of a [`MouseEvent`].
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MouseEventKind {
+    /// A button was pressed
+    Press(MouseButton),
+    /// A button was released
+    Release(MouseButton),
+    /// The mouse was moved while a button was held down
+    Drag(MouseButton),
+    /// The mouse was moved with no buttons held down
+    Move,
+    /// The scroll wheel was scrolled up
+    ScrollUp,
+    /// The scroll wheel was scrolled down
+    ScrollDown,
+}
+
+/// A mouse event, which can be encoded using the SGR (mode 1006) mouse
+/// protocol.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MouseEvent {
+    kind: MouseEventKind,
+    row: u16,
+    col: u16,
+    modifiers: Modifiers,
+}
+
+impl MouseEvent {
+    /// Creates a new mouse event at the given position. Rows and columns
+    /// start at 0 for the top left corner of the terminal.
+    #[must_use]
+    pub const fn new(kind: MouseEventKind, row: u16, col: u16) -> Self {
+        Self {
+            kind,
+            row,
+            col,
+            modifiers: Modifiers::empty(),
+        }
+    }
+
+    /// Sets the modifier keys held down during this event.
+    #[must_use]
+    pub const fn modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+
+    /// Returns the bytes that a terminal using the SGR mouse protocol
+    /// would send for this event.
+    #[must_use]
+    pub fn encode(&self) -> Vec<u8> {
+        let button = |button| match button {
+            MouseButton::Left => 0,
+            MouseButton::Middle => 1,
+            MouseButton::Right => 2,
+        };
+        let (mut code, release) = match self.kind {
+            MouseEventKind::Press(b) => (button(b), false),
+            MouseEventKind::Release(b) => (button(b), true),
+            MouseEventKind::Drag(b) => (button(b) + 32, false),
+            MouseEventKind::Move => (35, false),
+            MouseEventKind::ScrollUp => (64, false),
+            MouseEventKind::ScrollDown => (65, false),
+        };
+        if self.modifiers.contains(Modifiers::SHIFT) {
+            code += 4;
+        }
+        if self.modifiers.contains(Modifiers::ALT) {
+            code += 8;
+        }
+        if self.modifiers.contains(Modifiers::CTRL) {
+            code += 16;
+        }
+        format!(
+            "\x1b[<{code};{};{}{}",
+            u32::from(self.col) + 1,
+            u32::from(self.row) + 1,
+            if release { 'm' } else { 'M' }
+        )
+        .into_bytes()
+    }
+}
+
+/// The terminal modes which affect how input is encoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modes {
+    application_cursor: bool,
+    application_keypad: bool,
+    bracketed_paste: bool,
+}
+
+impl Modes {
+    /// Returns true if application cursor keys mode (DECCKM) is set.
+    #[must_use]
+    pub const fn application_cursor(&self) -> bool {
+        self.application_cursor
+    }
+
+    /// Returns true if application keypad mode (DECKPAM) is set.
+    #[must_use]
+    pub const fn application_keypad(&self) -> bool {
+        self.application_keypad
+    }
+
+    /// Returns true if bracketed paste mode is set.
+    #[must_use]
+    pub const fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    /// Sets whether application cursor keys mode (DECCKM) is set.
+    #[must_use]
+    pub const fn set_application_cursor(mut self, set: bool) -> Self {
+        self.application_cursor = set;
+        self
+    }
+
+    /// Sets whether application keypad mode (DECKPAM) is set.
+    #[must_use]
+    pub const fn set_application_keypad(mut self, set: bool) -> Self {
+        self.application_keypad = set;
+        self
+    }
+
+    /// Sets whether bracketed paste mode is set.
+    #[must_use]
+    pub const fn set_bracketed_paste(mut self, set: bool) -> Self {
+        self.bracketed_paste = set;
+        self
+    }
+}
+
+// the longest CSI sequence we care about is something like
+// "?1;1000;1006;2004h", so anything much longer than that can be ignored
+const MAX_PARAMS: usize = 32;
+
+#[derive(Debug, Default)]
+enum State {
+    #[default]
+    Ground,
+    Escape,
+    Csi,
+}
+
+/// Tracks the terminal modes set by a stream of output.
+#[derive(Debug, Default)]
+pub(crate) struct ModeTracker {
+    modes: Modes,
+    state: State,
+    params: Vec<u8>,
+}
+
+impl ModeTracker {
+    pub fn modes(&self) -> Modes {
+        self.modes
+    }
+
+    pub fn process(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            if matches!(self.state, State::Ground) {
+                let Some(i) = data.iter().position(|&byte| byte == 0x1b)
+                else {
+                    return;
+                };
+                self.state = State::Escape;
+                data = &data[i + 1..];
+                continue;
+            }
+
+            let byte = data[0];
+            data = &data[1..];
+            self.state = match (&self.state, byte) {
+                (_, 0x1b) => State::Escape,
+                (State::Escape, b'[') => {
+                    self.params.clear();
+                    State::Csi
+                }
+                (State::Escape, b'=') => {
+                    self.modes.application_keypad = true;
+                    State::Ground
+                }
+                (State::Escape, b'>') => {
+                    self.modes.application_keypad = false;
+                    State::Ground
+                }
+                (State::Escape, b'c') => {
+                    self.modes = Modes::default();
+                    State::Ground
+                }
+                (State::Csi, 0x40..=0x7e) => {
+                    self.csi(byte);
+                    State::Ground
+                }
+                (State::Csi, 0x18 | 0x1a) => State::Ground,
+                (State::Csi, _) => {
+                    if self.params.len() < MAX_PARAMS {
+                        self.params.push(byte);
+                    }
+                    State::Csi
+                }
+                _ => State::Ground,
+            };
+        }
+    }
+
+    fn csi(&mut self, c: u8) {
+        let set = match c {
+            b'h' => true,
+            b'l' => false,
+            _ => return,
+        };
+        let Some(params) = self.params.strip_prefix(b"?") else {
+            return;
+        };
+        for param in params.split(|&byte| byte == b';') {
+            match param {
+                b"1" => self.modes.application_cursor = set,
+                b"66" => self.modes.application_keypad = set,
+                b"2004" => self.modes.bracketed_paste = set,
+                _ => {}
+            }
+        }
+    }
+}
@@ -25,9 +25,10 @@
 //! # }
 //! ```
 //!
-//! The returned `child` is a normal instance of [`tokio::process::Child`] (or
-//! [`std::process::Child`] for the [`blocking`] variant), with its
-//! `stdin`/`stdout`/`stderr` file descriptors pointing at the given pty. The
+//! The returned `child` is a thin wrapper around [`tokio::process::Child`]
+//! (or [`std::process::Child`] for the [`blocking`] variant) which
+//! dereferences to it, with its `stdin`/`stdout`/`stderr` file descriptors
+//! pointing at the given pty. The
 //! `pty` instance implements [`tokio::io::AsyncRead`] and
 //! [`tokio::io::AsyncWrite`] (or [`std::io::Read`] and [`std::io::Write`] for
 //! the [`blocking`] variant), and can be used to communicate with the child
@@ -39,6 +40,12 @@
 //!
 //! By default, only the [`blocking`] APIs are available. To include the
 //! asynchronous APIs, you must enable the `async` feature.
+//!
+//! The `codec` feature adds [`codec::PtyCodec`], for use with the
+//! [`tokio_util::codec`] framing APIs.
+//!
+//! On Linux, the `linux-namespaces` feature enables moving spawned
+//! children into new namespaces via `Command::unshare`.
 
 #![warn(clippy::cargo)]
 #![warn(clippy::pedantic)]
@@ -57,12 +64,38 @@
 mod error;
 pub use error::{Error, Result};
 mod types;
-pub use types::Size;
+pub use types::{Queue, Resource, Size};
 
+mod ansi;
+mod secret;
+pub use secret::SecretBytes;
 mod sys;
 
+#[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+mod namespaces;
+#[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+pub use namespaces::Namespaces;
+
+#[cfg(target_os = "linux")]
+mod pidfd;
+#[cfg(target_os = "linux")]
+pub use pidfd::Pidfd;
+
 pub mod blocking;
+pub mod broadcast;
+pub mod filter;
+pub mod keys;
+pub mod passthrough;
+pub mod recording;
+pub mod shell;
+pub mod strip;
+pub mod text;
+pub mod transcript;
 
+#[cfg(feature = "async")]
+mod child;
+#[cfg(feature = "async")]
+pub use child::Child;
 #[cfg(feature = "async")]
 mod command;
 #[cfg(feature = "async")]
@@ -73,3 +106,12 @@ mod pty;
 pub use pty::{
     OwnedReadPty, OwnedWritePty, Pts, Pty, ReadPty, WritePty, open,
 };
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+pub use stream::{Chunks, Lines};
+#[cfg(feature = "async")]
+pub mod session;
+
+#[cfg(feature = "codec")]
+pub mod codec;
@@ -0,0 +1,162 @@
+use std::os::{fd::AsRawFd as _, unix::ffi::OsStringExt as _};
+
+/// A set of Linux namespaces for a child to be moved into. See
+/// [`Command::unshare`](crate::blocking::Command::unshare).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Namespaces(rustix::thread::UnshareFlags);
+
+impl Namespaces {
+    /// A new user namespace (`CLONE_NEWUSER`)
+    pub const USER: Self = Self(rustix::thread::UnshareFlags::NEWUSER);
+    /// A new mount namespace (`CLONE_NEWNS`)
+    pub const MOUNT: Self = Self(rustix::thread::UnshareFlags::NEWNS);
+    /// A new pid namespace (`CLONE_NEWPID`). Note that the child itself
+    /// stays in its original pid namespace, and only processes that it
+    /// spawns are placed into the new one (the first of which becomes pid
+    /// 1 inside the namespace).
+    pub const PID: Self = Self(rustix::thread::UnshareFlags::NEWPID);
+    /// A new UTS namespace (`CLONE_NEWUTS`)
+    pub const UTS: Self = Self(rustix::thread::UnshareFlags::NEWUTS);
+    /// A new IPC namespace (`CLONE_NEWIPC`)
+    pub const IPC: Self = Self(rustix::thread::UnshareFlags::NEWIPC);
+    /// A new network namespace (`CLONE_NEWNET`)
+    pub const NET: Self = Self(rustix::thread::UnshareFlags::NEWNET);
+
+    /// Returns an empty set of namespaces.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self(rustix::thread::UnshareFlags::empty())
+    }
+
+    /// Returns true if no namespaces are contained in this set.
+    #[must_use]
+    pub const fn is_empty(self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns true if all of the namespaces in `other` are contained in
+    /// this set.
+    #[must_use]
+    pub const fn contains(self, other: Self) -> bool {
+        self.0.contains(other.0)
+    }
+}
+
+impl Default for Namespaces {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl std::ops::BitOr for Namespaces {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl std::ops::BitOrAssign for Namespaces {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct NamespaceOptions {
+    pub namespaces: Namespaces,
+    pub uid_map: Option<Vec<u8>>,
+    pub gid_map: Option<Vec<u8>>,
+    pub private_devpts: bool,
+    pts_path: Option<std::ffi::CString>,
+}
+
+impl NamespaceOptions {
+    pub fn set_uid_map(&mut self, inside: u32, outside: u32, count: u32) {
+        self.uid_map = Some(format!("{inside} {outside} {count}\n").into());
+    }
+
+    pub fn set_gid_map(&mut self, inside: u32, outside: u32, count: u32) {
+        self.gid_map = Some(format!("{inside} {outside} {count}\n").into());
+    }
+
+    pub fn prepare(&self, pts: &crate::sys::Pts) -> crate::Result<Self> {
+        let mut options = self.clone();
+        if options.private_devpts {
+            options.namespaces |= Namespaces::MOUNT;
+            // the bind mount has to be made from a path in the new mount
+            // namespace, so we can't use /proc/self/fd directly
+            let path = std::fs::read_link(format!(
+                "/proc/self/fd/{}",
+                pts.as_raw_fd()
+            ))?;
+            options.pts_path = Some(
+                std::ffi::CString::new(path.into_os_string().into_vec())
+                    .map_err(|_| {
+                        std::io::Error::from(std::io::ErrorKind::InvalidInput)
+                    })?,
+            );
+        }
+        Ok(options)
+    }
+
+    // this runs in the child between fork and exec, so it must only call
+    // async-signal-safe functions (and in particular must not allocate)
+    pub fn apply(&self) -> std::io::Result<()> {
+        if self.namespaces.is_empty() {
+            return Ok(());
+        }
+
+        rustix::thread::unshare(self.namespaces.0)?;
+
+        if let Some(uid_map) = &self.uid_map {
+            write_file(c"/proc/self/uid_map", uid_map)?;
+        }
+        if let Some(gid_map) = &self.gid_map {
+            // unprivileged processes must give up the ability to call
+            // setgroups before they are allowed to write a gid map
+            if self.namespaces.contains(Namespaces::USER) {
+                write_file(c"/proc/self/setgroups", b"deny")?;
+            }
+            write_file(c"/proc/self/gid_map", gid_map)?;
+        }
+
+        if self.private_devpts {
+            rustix::mount::mount_change(
+                c"/",
+                rustix::mount::MountPropagationFlags::PRIVATE
+                    | rustix::mount::MountPropagationFlags::REC,
+            )?;
+            // the new devpts instance can't contain the existing pts, so
+            // make it available as the console instead, the same way that
+            // container runtimes do
+            if let Some(pts_path) = &self.pts_path {
+                rustix::mount::mount_bind(
+                    pts_path.as_c_str(),
+                    c"/dev/console",
+                )?;
+            }
+            rustix::mount::mount(
+                c"devpts",
+                c"/dev/pts",
+                c"devpts",
+                rustix::mount::MountFlags::NOSUID
+                    | rustix::mount::MountFlags::NOEXEC,
+                c"newinstance,ptmxmode=0666,mode=0620",
+            )?;
+            rustix::mount::mount_bind(c"/dev/pts/ptmx", c"/dev/ptmx")?;
+        }
+
+        Ok(())
+    }
+}
+
+fn write_file(path: &std::ffi::CStr, contents: &[u8]) -> std::io::Result<()> {
+    let fd = rustix::fs::open(
+        path,
+        rustix::fs::OFlags::WRONLY | rustix::fs::OFlags::CLOEXEC,
+        rustix::fs::Mode::empty(),
+    )?;
+    rustix::io::write(&fd, contents)?;
+    Ok(())
+}
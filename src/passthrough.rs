@@ -0,0 +1,571 @@
+//! Run a child on a pty while letting the user interact with it through the
+//! terminal of the current process.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! let (mut pty, pts) = pty_process::blocking::open()?;
+//! let mut child = pty_process::blocking::Command::new("nethack").spawn(pts)?;
+//! let status =
+//!     pty_process::passthrough::Passthrough::new().run(&mut child, &mut pty)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::os::fd::AsRawFd as _;
+
+static SAVED_TERMIOS: std::sync::Mutex<
+    Option<(std::os::fd::RawFd, rustix::termios::Termios)>,
+> = std::sync::Mutex::new(None);
+static PANIC_HOOK: std::sync::Once = std::sync::Once::new();
+
+/// Puts the terminal attached to `stdin` into raw mode, and restores its
+/// previous settings when dropped.
+///
+/// The previous settings are also restored if the current thread panics
+/// while this guard is alive (even if panics are configured to abort). If
+/// `stdin` is not a terminal, this does nothing.
+pub struct RawMode {
+    termios: Option<rustix::termios::Termios>,
+}
+
+impl RawMode {
+    /// Puts the terminal attached to `stdin` into raw mode.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal settings could not be changed.
+    pub fn new() -> crate::Result<Self> {
+        let stdin = rustix::stdio::stdin();
+        let Ok(termios) = rustix::termios::tcgetattr(stdin) else {
+            return Ok(Self { termios: None });
+        };
+
+        PANIC_HOOK.call_once(|| {
+            let prev = std::panic::take_hook();
+            std::panic::set_hook(Box::new(move |info| {
+                restore_saved_termios();
+                prev(info);
+            }));
+        });
+        *SAVED_TERMIOS
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) =
+            Some((stdin.as_raw_fd(), termios.clone()));
+
+        let mut raw = termios.clone();
+        raw.make_raw();
+        rustix::termios::tcsetattr(
+            stdin,
+            rustix::termios::OptionalActions::Now,
+            &raw,
+        )?;
+
+        Ok(Self {
+            termios: Some(termios),
+        })
+    }
+
+    /// Restores the previous terminal settings immediately, rather than
+    /// waiting for the guard to be dropped.
+    pub fn restore(&mut self) {
+        if let Some(termios) = self.termios.take() {
+            let _ = rustix::termios::tcsetattr(
+                rustix::stdio::stdin(),
+                rustix::termios::OptionalActions::Now,
+                &termios,
+            );
+            *SAVED_TERMIOS
+                .lock()
+                .unwrap_or_else(std::sync::PoisonError::into_inner) = None;
+        }
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+fn restore_saved_termios() {
+    let saved = SAVED_TERMIOS
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .take();
+    if let Some((fd, termios)) = saved {
+        let _ = rustix::termios::tcsetattr(
+            // Safety: the fd is stdin, which is never closed
+            unsafe { std::os::fd::BorrowedFd::borrow_raw(fd) },
+            rustix::termios::OptionalActions::Now,
+            &termios,
+        );
+    }
+}
+
+type Filter = Box<dyn FnMut(&mut Vec<u8>) + Send>;
+
+/// Connects the terminal of the current process to a pty.
+///
+/// While running, the terminal attached to `stdin` is put into raw mode,
+/// everything read from `stdin` is written to the pty, and everything read
+/// from the pty is written to `stdout`. The terminal settings are restored
+/// when the child exits or if the current thread panics.
+///
+/// Signal handlers are installed through [`tokio::signal`] when running
+/// asynchronously and through `signal-hook` otherwise, so they coexist with
+/// any other handlers installed through either of those. Neither of those
+/// crates can restore a signal's default action once a handler has been
+/// installed for it, so by default handlers are only installed for
+/// `SIGWINCH` and `SIGCHLD`, whose default action is to be ignored anyway.
+/// See [`forward_signals`](Self::forward_signals) for handling `SIGTERM`
+/// and `SIGHUP`.
+pub struct Passthrough {
+    input_filter: Option<Filter>,
+    output_filter: Option<Filter>,
+    follow_resize: bool,
+    forward_signals: bool,
+}
+
+impl Passthrough {
+    /// Creates a new `Passthrough` with no filters, which follows window
+    /// size changes and doesn't forward signals.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            input_filter: None,
+            output_filter: None,
+            follow_resize: true,
+            forward_signals: false,
+        }
+    }
+
+    /// Sets a function to be called on each chunk of data read from
+    /// `stdin` before it is written to the pty. The chunk can be modified
+    /// in place (including being cleared, to drop it entirely).
+    #[must_use]
+    pub fn input_filter<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&mut Vec<u8>) + Send + 'static,
+    {
+        self.input_filter = Some(Box::new(f));
+        self
+    }
+
+    /// Sets a function to be called on each chunk of data read from the
+    /// pty before it is written to `stdout`. The chunk can be modified in
+    /// place (including being cleared, to drop it entirely).
+    #[must_use]
+    pub fn output_filter<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&mut Vec<u8>) + Send + 'static,
+    {
+        self.output_filter = Some(Box::new(f));
+        self
+    }
+
+    /// If set, the pty will be resized to match the size of the terminal
+    /// attached to `stdin`, both initially and whenever the current process
+    /// receives `SIGWINCH`. Defaults to true.
+    #[must_use]
+    pub fn follow_resize(mut self, follow_resize: bool) -> Self {
+        self.follow_resize = follow_resize;
+        self
+    }
+
+    /// If set, when the current process receives `SIGTERM` or `SIGHUP`, the
+    /// terminal settings are restored and the signal is forwarded to the
+    /// child's process group. Defaults to false, in which case those
+    /// signals keep their existing disposition (and so by default
+    /// terminate the current process, leaving the terminal in raw mode).
+    ///
+    /// Note that this installs handlers for `SIGTERM` and `SIGHUP` which
+    /// stay installed after this has run, so that those signals no longer
+    /// terminate the current process on their own. This is only
+    /// appropriate when the current process handles them in some other
+    /// way, or exits once the child does.
+    #[must_use]
+    pub fn forward_signals(mut self, forward_signals: bool) -> Self {
+        self.forward_signals = forward_signals;
+        self
+    }
+
+    /// Runs until the child exits, and returns its exit status.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal could not be put into raw mode, if
+    /// signal handlers could not be installed, or if reading from or
+    /// writing to the terminal or the pty fails.
+    pub fn run(
+        mut self,
+        child: &mut crate::blocking::Child,
+        pty: &mut crate::blocking::Pty,
+    ) -> crate::Result<std::process::ExitStatus> {
+        let mut raw = RawMode::new()?;
+        let mut signals = SignalPipe::new(self.forward_signals)?;
+        let pty = &*pty;
+        if self.follow_resize {
+            resize_from_host(|size| pty.resize(size));
+        }
+
+        // write to the file descriptor directly rather than going through
+        // the lock around std::io::stdout, so that this is also usable in
+        // a child created by fork_with_pty
+        let stdin = rustix::stdio::stdin();
+        let stdout = rustix::stdio::stdout();
+        let mut buf = [0_u8; 4096];
+        let mut fds = vec![
+            rustix::event::PollFd::new(pty, rustix::event::PollFlags::IN),
+            rustix::event::PollFd::new(
+                &signals.read,
+                rustix::event::PollFlags::IN,
+            ),
+            rustix::event::PollFd::from_borrowed_fd(
+                stdin,
+                rustix::event::PollFlags::IN,
+            ),
+        ];
+
+        loop {
+            if child.try_wait()?.is_some() {
+                break;
+            }
+
+            match rustix::event::poll(&mut fds, None) {
+                Ok(_) => {}
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(e) => return Err(e.into()),
+            }
+            let pty_ready = !fds[0].revents().is_empty();
+            let signal_ready = !fds[1].revents().is_empty();
+            let stdin_ready =
+                fds.get(2).is_some_and(|fd| !fd.revents().is_empty());
+
+            if signal_ready {
+                for signal in signals.delivery.pending() {
+                    handle_signal(
+                        signal,
+                        &mut raw,
+                        rustix::process::Pid::from_child(child),
+                        self.follow_resize,
+                        |size| pty.resize(size),
+                    );
+                }
+            }
+
+            if pty_ready {
+                match rustix::io::read(pty, &mut buf) {
+                    Ok(0) | Err(rustix::io::Errno::IO) => break,
+                    Ok(bytes) => {
+                        write_filtered(
+                            stdout,
+                            &buf[..bytes],
+                            self.output_filter.as_mut(),
+                        )?;
+                    }
+                    Err(
+                        rustix::io::Errno::INTR | rustix::io::Errno::AGAIN,
+                    ) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+
+            if stdin_ready {
+                match rustix::io::read(stdin, &mut buf) {
+                    // stop polling stdin once it is closed
+                    Ok(0) => fds.truncate(2),
+                    Ok(bytes) => {
+                        write_filtered(
+                            pty,
+                            &buf[..bytes],
+                            self.input_filter.as_mut(),
+                        )?;
+                    }
+                    Err(
+                        rustix::io::Errno::INTR | rustix::io::Errno::AGAIN,
+                    ) => {}
+                    Err(e) => return Err(e.into()),
+                }
+            }
+        }
+        drop(fds);
+
+        // the child may have written output that we haven't read yet
+        loop {
+            let mut fds = [rustix::event::PollFd::new(
+                pty,
+                rustix::event::PollFlags::IN,
+            )];
+            let timeout = rustix::event::Timespec {
+                tv_sec: 0,
+                tv_nsec: 100_000_000,
+            };
+            match rustix::event::poll(&mut fds, Some(&timeout)) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(rustix::io::Errno::INTR) => continue,
+                Err(e) => return Err(e.into()),
+            }
+            match rustix::io::read(pty, &mut buf) {
+                Ok(0) | Err(_) => break,
+                Ok(bytes) => write_filtered(
+                    stdout,
+                    &buf[..bytes],
+                    self.output_filter.as_mut(),
+                )?,
+            }
+        }
+
+        Ok(child.wait()?)
+    }
+
+    /// Runs until the child exits, and returns its exit status.
+    ///
+    /// Note that this uses [`tokio::io::stdin`], so a read from `stdin` may
+    /// still be pending on a blocking thread after this returns.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal could not be put into raw mode, if
+    /// signal handlers could not be installed, or if reading from or
+    /// writing to the terminal or the pty fails.
+    #[cfg(feature = "async")]
+    pub async fn run_async(
+        mut self,
+        child: &mut crate::Child,
+        pty: &mut crate::Pty,
+    ) -> crate::Result<std::process::ExitStatus> {
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let mut raw = RawMode::new()?;
+        let mut winch = tokio::signal::unix::signal(
+            tokio::signal::unix::SignalKind::window_change(),
+        )?;
+        let (mut term, mut hup) = if self.forward_signals {
+            (
+                Some(tokio::signal::unix::signal(
+                    tokio::signal::unix::SignalKind::terminate(),
+                )?),
+                Some(tokio::signal::unix::signal(
+                    tokio::signal::unix::SignalKind::hangup(),
+                )?),
+            )
+        } else {
+            (None, None)
+        };
+        if self.follow_resize {
+            resize_from_host(|size| pty.resize(size));
+        }
+        let pid = child
+            .id()
+            .and_then(|id| i32::try_from(id).ok())
+            .and_then(rustix::process::Pid::from_raw);
+
+        let mut stdin = tokio::io::stdin();
+        let mut stdout = tokio::io::stdout();
+        let mut in_buf = [0_u8; 4096];
+        let mut out_buf = [0_u8; 4096];
+        let mut stdin_open = true;
+
+        loop {
+            tokio::select! {
+                bytes = stdin.read(&mut in_buf), if stdin_open => {
+                    match bytes {
+                        Ok(0) => stdin_open = false,
+                        Ok(bytes) => {
+                            let mut chunk = in_buf[..bytes].to_vec();
+                            if let Some(filter) = self.input_filter.as_mut() {
+                                filter(&mut chunk);
+                            }
+                            pty.write_all(&chunk).await?;
+                        }
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+                bytes = pty.read(&mut out_buf) => match bytes {
+                    Ok(0) => break,
+                    Err(e) if e.raw_os_error() == Some(rustix::io::Errno::IO.raw_os_error()) => break,
+                    Ok(bytes) => {
+                        let mut chunk = out_buf[..bytes].to_vec();
+                        if let Some(filter) = self.output_filter.as_mut() {
+                            filter(&mut chunk);
+                        }
+                        stdout.write_all(&chunk).await?;
+                        stdout.flush().await?;
+                    }
+                    Err(e) => return Err(e.into()),
+                },
+                Some(()) = winch.recv() => {
+                    if self.follow_resize {
+                        resize_from_host(|size| pty.resize(size));
+                    }
+                }
+                Some(()) = recv_signal(term.as_mut()) => {
+                    forward_signal(&mut raw, pid, rustix::process::Signal::TERM);
+                }
+                Some(()) = recv_signal(hup.as_mut()) => {
+                    forward_signal(&mut raw, pid, rustix::process::Signal::HUP);
+                }
+                _ = child.wait() => break,
+            }
+        }
+
+        // the child may have written output that we haven't read yet
+        while let Ok(Ok(bytes)) = tokio::time::timeout(
+            std::time::Duration::from_millis(100),
+            pty.read(&mut out_buf),
+        )
+        .await
+        {
+            if bytes == 0 {
+                break;
+            }
+            let mut chunk = out_buf[..bytes].to_vec();
+            if let Some(filter) = self.output_filter.as_mut() {
+                filter(&mut chunk);
+            }
+            stdout.write_all(&chunk).await?;
+        }
+        stdout.flush().await?;
+
+        Ok(child.wait().await?)
+    }
+}
+
+impl Default for Passthrough {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_filtered(
+    fd: impl std::os::fd::AsFd,
+    buf: &[u8],
+    filter: Option<&mut Filter>,
+) -> std::io::Result<()> {
+    if let Some(filter) = filter {
+        let mut chunk = buf.to_vec();
+        filter(&mut chunk);
+        write_all(fd, &chunk)
+    } else {
+        write_all(fd, buf)
+    }
+}
+
+fn write_all(
+    fd: impl std::os::fd::AsFd,
+    mut buf: &[u8],
+) -> std::io::Result<()> {
+    while !buf.is_empty() {
+        match rustix::io::write(&fd, buf) {
+            Ok(0) => return Err(std::io::ErrorKind::WriteZero.into()),
+            Ok(bytes) => buf = &buf[bytes..],
+            Err(rustix::io::Errno::INTR) => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(())
+}
+
+fn resize_from_host(resize: impl FnOnce(crate::Size) -> crate::Result<()>) {
+    if let Ok(winsize) = rustix::termios::tcgetwinsize(rustix::stdio::stdin())
+    {
+        let _ = resize(winsize.into());
+    }
+}
+
+fn handle_signal(
+    signal: libc::c_int,
+    raw: &mut RawMode,
+    pid: rustix::process::Pid,
+    follow_resize: bool,
+    resize: impl FnOnce(crate::Size) -> crate::Result<()>,
+) {
+    match signal {
+        libc::SIGWINCH if follow_resize => resize_from_host(resize),
+        libc::SIGTERM => {
+            forward_signal(raw, Some(pid), rustix::process::Signal::TERM);
+        }
+        libc::SIGHUP => {
+            forward_signal(raw, Some(pid), rustix::process::Signal::HUP);
+        }
+        _ => {}
+    }
+}
+
+#[cfg(feature = "async")]
+async fn recv_signal(
+    signal: Option<&mut tokio::signal::unix::Signal>,
+) -> Option<()> {
+    match signal {
+        Some(signal) => signal.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+fn forward_signal(
+    raw: &mut RawMode,
+    pid: Option<rustix::process::Pid>,
+    signal: rustix::process::Signal,
+) {
+    raw.restore();
+    if let Some(pid) = pid {
+        let _ = rustix::process::kill_process_group(pid, signal);
+    }
+}
+
+// the default action of these is to be ignored, so it doesn't matter that
+// their handlers can't be removed again
+const SIGNALS: [libc::c_int; 2] = [libc::SIGWINCH, libc::SIGCHLD];
+const FORWARDED_SIGNALS: [libc::c_int; 2] = [libc::SIGTERM, libc::SIGHUP];
+
+// forwards signals to a socket, so that they can be handled in an event loop.
+// the handlers are registered through signal-hook, which shares them with
+// any other users of signal-hook-registry (including tokio). they are
+// unregistered again when this is dropped, although signal-hook leaves its
+// own handler installed for each signal.
+struct SignalPipe {
+    // a second handle to the read end of the socket, so that it can be
+    // polled while signals are being drained
+    read: std::os::unix::net::UnixStream,
+    delivery: signal_hook::iterator::backend::SignalDelivery<
+        std::os::unix::net::UnixStream,
+        signal_hook::iterator::exfiltrator::SignalOnly,
+    >,
+}
+
+impl SignalPipe {
+    fn new(forward_signals: bool) -> crate::Result<Self> {
+        let (read, write) = std::os::unix::net::UnixStream::pair()?;
+        read.set_nonblocking(true)?;
+        write.set_nonblocking(true)?;
+        Ok(Self {
+            read: read.try_clone()?,
+            delivery:
+                signal_hook::iterator::backend::SignalDelivery::with_pipe(
+                    read,
+                    write,
+                    signal_hook::iterator::exfiltrator::SignalOnly,
+                    SIGNALS.into_iter().chain(
+                        FORWARDED_SIGNALS
+                            .into_iter()
+                            .filter(|_| forward_signals),
+                    ),
+                )?,
+        })
+    }
+}
+
+impl std::fmt::Debug for Passthrough {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Passthrough")
+            .field("follow_resize", &self.follow_resize)
+            .field("forward_signals", &self.forward_signals)
+            .finish_non_exhaustive()
+    }
+}
+
+impl std::fmt::Debug for RawMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RawMode")
+            .field("active", &self.termios.is_some())
+            .finish()
+    }
+}
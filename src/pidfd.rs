@@ -0,0 +1,109 @@
+/// A file descriptor referring to a child process, returned by
+/// [`Child::pidfd`](crate::Child::pidfd) and
+/// [`blocking::Child::pidfd`](crate::blocking::Child::pidfd).
+///
+/// Unlike a pid, a pidfd always refers to the same process, even after
+/// that process has exited and its pid has been reused, so signals sent
+/// with [`signal`](Self::signal) can never reach an unrelated process.
+///
+/// The file descriptor becomes readable once the process exits, so it can
+/// be polled alongside the pty in an event loop (it implements
+/// [`AsFd`](std::os::fd::AsFd)) rather than relying on `SIGCHLD`. Note that
+/// this only reports that the process has exited: it still needs to be
+/// reaped with [`wait`](std::process::Child::wait) or
+/// [`try_wait`](std::process::Child::try_wait) to retrieve its exit status.
+///
+/// Only available on Linux.
+#[derive(Debug)]
+pub struct Pidfd(std::os::fd::OwnedFd);
+
+impl Pidfd {
+    pub(crate) fn open(pid: rustix::process::Pid) -> Option<Self> {
+        // pidfds require linux 5.3
+        let fd = rustix::process::pidfd_open(
+            pid,
+            rustix::process::PidfdFlags::empty(),
+        )
+        .ok()?;
+        // if the child has already been reaped, its pid may have been
+        // reused by an unrelated process, which won't be our child
+        rustix::process::waitid(
+            rustix::process::WaitId::PidFd(std::os::fd::AsFd::as_fd(&fd)),
+            rustix::process::WaitIdOptions::EXITED
+                | rustix::process::WaitIdOptions::NOHANG
+                | rustix::process::WaitIdOptions::NOWAIT,
+        )
+        .ok()?;
+        Some(Self(fd))
+    }
+
+    /// Sends the given signal to the process.
+    ///
+    /// # Errors
+    /// Returns an error if sending the signal fails, including if the
+    /// process has already exited.
+    pub fn signal(
+        &self,
+        signal: rustix::process::Signal,
+    ) -> crate::Result<()> {
+        rustix::process::pidfd_send_signal(&self.0, signal)?;
+        Ok(())
+    }
+
+    /// Returns true if the process has exited.
+    ///
+    /// # Errors
+    /// Returns an error if polling the file descriptor fails.
+    pub fn has_exited(&self) -> crate::Result<bool> {
+        self.wait_exited(Some(std::time::Duration::ZERO))
+    }
+
+    /// Blocks until the process exits, or until the given timeout (if any)
+    /// elapses. Returns true if the process has exited.
+    ///
+    /// # Errors
+    /// Returns an error if polling the file descriptor fails.
+    pub fn wait_exited(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<bool> {
+        Ok(crate::sys::wait_readable(&self.0, timeout)?)
+    }
+
+    /// Waits for the process to exit.
+    ///
+    /// # Errors
+    /// Returns an error if the file descriptor can't be registered with the
+    /// tokio runtime.
+    #[cfg(feature = "async")]
+    pub async fn exited(&self) -> crate::Result<()> {
+        // each call registers its own duplicate, since a file descriptor
+        // can't be registered with the runtime more than once at a time
+        let fd = tokio::io::unix::AsyncFd::with_interest(
+            self.0.try_clone()?,
+            tokio::io::Interest::READABLE,
+        )?;
+        // the pidfd stays readable once the process exits, so the
+        // readiness never needs to be cleared
+        let _ = fd.readable().await?;
+        Ok(())
+    }
+}
+
+impl std::os::fd::AsFd for Pidfd {
+    fn as_fd(&self) -> std::os::fd::BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
+impl std::os::fd::AsRawFd for Pidfd {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl From<Pidfd> for std::os::fd::OwnedFd {
+    fn from(pidfd: Pidfd) -> Self {
+        pidfd.0
+    }
+}
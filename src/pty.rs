@@ -43,6 +43,188 @@ impl Pty {
         self.0.get_ref().set_term_size(size)
     }
 
+    /// Discard data which is queued in the pty, without it being read,
+    /// like `tcflush`.
+    ///
+    /// # Errors
+    /// Returns an error if the queue couldn't be flushed.
+    pub fn discard(&self, queue: crate::Queue) -> crate::Result<()> {
+        self.0.get_ref().discard(queue)
+    }
+
+    /// Wait until the child has read all of the input written to the pty,
+    /// like `tcdrain`. In canonical mode, an incomplete line is not
+    /// waited for, since the child can't read it until it is finished.
+    ///
+    /// This waits indefinitely if the child never reads its input.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the input queue couldn't be
+    /// determined.
+    pub async fn drain(&self) -> crate::Result<()> {
+        drain(self.0.get_ref()).await
+    }
+
+    /// Suspend output from the child, as if `^S` had been typed, like
+    /// `tcflow` with `TCOOFF`. Writes to the pts will block until
+    /// [`resume_output`](Self::resume_output) is called.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be suspended.
+    pub fn suspend_output(&self) -> crate::Result<()> {
+        self.0.get_ref().set_output_suspended(true)
+    }
+
+    /// Resume output from the child after
+    /// [`suspend_output`](Self::suspend_output), like `tcflow` with
+    /// `TCOON`.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be resumed.
+    pub fn resume_output(&self) -> crate::Result<()> {
+        self.0.get_ref().set_output_suspended(false)
+    }
+
+    /// Send a break, like `tcsendbreak`. Note that ptys on Linux ignore
+    /// breaks.
+    ///
+    /// # Errors
+    /// Returns an error if the break couldn't be sent.
+    pub fn send_break(&self) -> crate::Result<()> {
+        self.0.get_ref().send_break()
+    }
+
+    /// Returns the number of bytes of input which have been written to the
+    /// pty but not yet read by the child (`FIONREAD` on the pts). In
+    /// canonical mode, an incomplete line is not counted.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn input_queue_len(&self) -> crate::Result<usize> {
+        self.0.get_ref().input_queue_len()
+    }
+
+    /// Returns the number of bytes of output which have been written by
+    /// the child but not yet read from the pty (`TIOCOUTQ`, or `FIONREAD`
+    /// on Linux, where `TIOCOUTQ` is always 0 for ptys).
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn output_queue_len(&self) -> crate::Result<usize> {
+        self.0.get_ref().output_queue_len()
+    }
+
+    /// Hang up the pty, as if the terminal had been disconnected. This sends
+    /// `SIGHUP` (followed by `SIGCONT`) to the foreground process group and
+    /// the session leader of the session attached to the pty, and then
+    /// closes the pty. Processes which are still reading from or writing to
+    /// the pts will see end of file or errors afterwards.
+    pub fn hangup(self) {
+        self.0.get_ref().signal_hangup();
+    }
+
+    /// Returns true if the pts currently has echo disabled, as is usually
+    /// the case while a program is reading a password.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal settings couldn't be read.
+    pub fn is_echo_disabled(&self) -> crate::Result<bool> {
+        self.0.get_ref().echo_disabled()
+    }
+
+    /// Waits until the pts is in the state typically used for reading a
+    /// password (echo disabled, but still reading a line at a time), or
+    /// until the given timeout (if any) elapses. Returns false if the
+    /// timeout elapsed.
+    ///
+    /// The terminal settings are polled, so this doesn't read any output
+    /// from the pty.
+    ///
+    /// # Errors
+    /// Returns an error if the terminal settings couldn't be read.
+    pub async fn wait_for_password_prompt(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> crate::Result<bool> {
+        let deadline =
+            timeout.map(|timeout| tokio::time::Instant::now() + timeout);
+        loop {
+            if self.0.get_ref().password_prompt()? {
+                return Ok(true);
+            }
+            if deadline.is_some_and(|deadline| {
+                tokio::time::Instant::now() >= deadline
+            }) {
+                return Ok(false);
+            }
+            tokio::time::sleep(crate::sys::PROMPT_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Send a secret, such as a password, to the pty. Include the trailing
+    /// newline in the secret if the program expects one.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub async fn send_secret(
+        &mut self,
+        secret: &crate::SecretBytes,
+    ) -> std::io::Result<()> {
+        tokio::io::AsyncWriteExt::write_all(self, secret.expose()).await
+    }
+
+    /// Starts tracking the terminal modes which affect how input is
+    /// encoded (see [`modes`](Self::modes)) from the output read from this
+    /// pty. Tracking is off by default, since it requires scanning all of
+    /// the output, so this should be called before reading anything that
+    /// the child writes.
+    pub fn track_modes(&self) {
+        self.0.get_ref().track_modes();
+    }
+
+    /// Returns the terminal modes which affect how input is encoded, as
+    /// set by the output which has been read from this pty since
+    /// [`track_modes`](Self::track_modes) was called. Without tracking,
+    /// this always returns the default modes.
+    #[must_use]
+    pub fn modes(&self) -> crate::keys::Modes {
+        self.0.get_ref().modes()
+    }
+
+    /// Send a key press to the pty, encoded according to the current
+    /// [`modes`](Self::modes).
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub async fn send_key(
+        &mut self,
+        key: impl Into<crate::keys::KeyPress>,
+    ) -> std::io::Result<()> {
+        let buf = key.into().encode(self.modes());
+        tokio::io::AsyncWriteExt::write_all(self, &buf).await
+    }
+
+    /// Send text to the pty as if it was pasted into the terminal. See
+    /// [`keys::encode_paste`](crate::keys::encode_paste).
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub async fn send_paste(&mut self, text: &[u8]) -> std::io::Result<()> {
+        let buf = crate::keys::encode_paste(text, self.modes());
+        tokio::io::AsyncWriteExt::write_all(self, &buf).await
+    }
+
+    /// Send a mouse event to the pty, using the SGR mouse protocol.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the pty fails.
+    pub async fn send_mouse(
+        &mut self,
+        event: crate::keys::MouseEvent,
+    ) -> std::io::Result<()> {
+        tokio::io::AsyncWriteExt::write_all(self, &event.encode()).await
+    }
+
     /// Splits a `Pty` into a read half and a write half, which can be used to
     /// read from and write to the pty concurrently. Does not allocate, but
     /// the returned halves cannot be moved to independent tasks.
@@ -148,6 +330,15 @@ impl std::os::fd::AsRawFd for Pts {
 /// Borrowed read half of a [`Pty`]
 pub struct ReadPty<'a>(&'a AsyncPty);
 
+impl ReadPty<'_> {
+    /// Returns a [`Stream`](futures_core::Stream) of the lines of output
+    /// read from the pty.
+    #[must_use]
+    pub fn lines(self) -> crate::Lines<Self> {
+        crate::Lines::new(self)
+    }
+}
+
 impl tokio::io::AsyncRead for ReadPty<'_> {
     fn poll_read(
         self: std::pin::Pin<&mut Self>,
@@ -169,6 +360,77 @@ impl WritePty<'_> {
     pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
         self.0.get_ref().set_term_size(size)
     }
+
+    /// Discard data which is queued in the pty, without it being read,
+    /// like `tcflush`.
+    ///
+    /// # Errors
+    /// Returns an error if the queue couldn't be flushed.
+    pub fn discard(&self, queue: crate::Queue) -> crate::Result<()> {
+        self.0.get_ref().discard(queue)
+    }
+
+    /// Wait until the child has read all of the input written to the pty,
+    /// like `tcdrain`. In canonical mode, an incomplete line is not
+    /// waited for, since the child can't read it until it is finished.
+    ///
+    /// This waits indefinitely if the child never reads its input.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the input queue couldn't be
+    /// determined.
+    pub async fn drain(&self) -> crate::Result<()> {
+        drain(self.0.get_ref()).await
+    }
+
+    /// Suspend output from the child, as if `^S` had been typed, like
+    /// `tcflow` with `TCOOFF`. Writes to the pts will block until
+    /// [`resume_output`](Self::resume_output) is called.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be suspended.
+    pub fn suspend_output(&self) -> crate::Result<()> {
+        self.0.get_ref().set_output_suspended(true)
+    }
+
+    /// Resume output from the child after
+    /// [`suspend_output`](Self::suspend_output), like `tcflow` with
+    /// `TCOON`.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be resumed.
+    pub fn resume_output(&self) -> crate::Result<()> {
+        self.0.get_ref().set_output_suspended(false)
+    }
+
+    /// Send a break, like `tcsendbreak`. Note that ptys on Linux ignore
+    /// breaks.
+    ///
+    /// # Errors
+    /// Returns an error if the break couldn't be sent.
+    pub fn send_break(&self) -> crate::Result<()> {
+        self.0.get_ref().send_break()
+    }
+
+    /// Returns the number of bytes of input which have been written to the
+    /// pty but not yet read by the child (`FIONREAD` on the pts). In
+    /// canonical mode, an incomplete line is not counted.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn input_queue_len(&self) -> crate::Result<usize> {
+        self.0.get_ref().input_queue_len()
+    }
+
+    /// Returns the number of bytes of output which have been written by
+    /// the child but not yet read from the pty (`TIOCOUTQ`, or `FIONREAD`
+    /// on Linux, where `TIOCOUTQ` is always 0 for ptys).
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn output_queue_len(&self) -> crate::Result<usize> {
+        self.0.get_ref().output_queue_len()
+    }
 }
 
 impl tokio::io::AsyncWrite for WritePty<'_> {
@@ -200,6 +462,20 @@ impl tokio::io::AsyncWrite for WritePty<'_> {
 pub struct OwnedReadPty(std::sync::Arc<AsyncPty>);
 
 impl OwnedReadPty {
+    /// Returns a [`Stream`](futures_core::Stream) of the lines of output
+    /// read from the pty.
+    #[must_use]
+    pub fn lines(self) -> crate::Lines<Self> {
+        crate::Lines::new(self)
+    }
+
+    /// Returns a [`Stream`](futures_core::Stream) of chunks of output read
+    /// from the pty, each of which is at most `capacity` bytes long.
+    #[must_use]
+    pub fn into_chunks(self, capacity: usize) -> crate::Chunks<Self> {
+        crate::Chunks::new(self, capacity)
+    }
+
     /// Attempt to join the two halves of a `Pty` back into a single instance.
     /// The two halves must have originated from calling
     /// [`into_split`](Pty::into_split) on a single instance.
@@ -247,6 +523,77 @@ impl OwnedWritePty {
     pub fn resize(&self, size: crate::Size) -> crate::Result<()> {
         self.0.get_ref().set_term_size(size)
     }
+
+    /// Discard data which is queued in the pty, without it being read,
+    /// like `tcflush`.
+    ///
+    /// # Errors
+    /// Returns an error if the queue couldn't be flushed.
+    pub fn discard(&self, queue: crate::Queue) -> crate::Result<()> {
+        self.0.get_ref().discard(queue)
+    }
+
+    /// Wait until the child has read all of the input written to the pty,
+    /// like `tcdrain`. In canonical mode, an incomplete line is not
+    /// waited for, since the child can't read it until it is finished.
+    ///
+    /// This waits indefinitely if the child never reads its input.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the input queue couldn't be
+    /// determined.
+    pub async fn drain(&self) -> crate::Result<()> {
+        drain(self.0.get_ref()).await
+    }
+
+    /// Suspend output from the child, as if `^S` had been typed, like
+    /// `tcflow` with `TCOOFF`. Writes to the pts will block until
+    /// [`resume_output`](Self::resume_output) is called.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be suspended.
+    pub fn suspend_output(&self) -> crate::Result<()> {
+        self.0.get_ref().set_output_suspended(true)
+    }
+
+    /// Resume output from the child after
+    /// [`suspend_output`](Self::suspend_output), like `tcflow` with
+    /// `TCOON`.
+    ///
+    /// # Errors
+    /// Returns an error if output couldn't be resumed.
+    pub fn resume_output(&self) -> crate::Result<()> {
+        self.0.get_ref().set_output_suspended(false)
+    }
+
+    /// Send a break, like `tcsendbreak`. Note that ptys on Linux ignore
+    /// breaks.
+    ///
+    /// # Errors
+    /// Returns an error if the break couldn't be sent.
+    pub fn send_break(&self) -> crate::Result<()> {
+        self.0.get_ref().send_break()
+    }
+
+    /// Returns the number of bytes of input which have been written to the
+    /// pty but not yet read by the child (`FIONREAD` on the pts). In
+    /// canonical mode, an incomplete line is not counted.
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn input_queue_len(&self) -> crate::Result<usize> {
+        self.0.get_ref().input_queue_len()
+    }
+
+    /// Returns the number of bytes of output which have been written by
+    /// the child but not yet read from the pty (`TIOCOUTQ`, or `FIONREAD`
+    /// on Linux, where `TIOCOUTQ` is always 0 for ptys).
+    ///
+    /// # Errors
+    /// Returns an error if the size of the queue couldn't be determined.
+    pub fn output_queue_len(&self) -> crate::Result<usize> {
+        self.0.get_ref().output_queue_len()
+    }
 }
 
 impl tokio::io::AsyncWrite for OwnedWritePty {
@@ -340,3 +687,11 @@ fn poll_flush(
         }
     }
 }
+
+async fn drain(pty: &crate::sys::Pty) -> crate::Result<()> {
+    let pts = pty.start_drain()?;
+    while crate::sys::queue_len(&pts)? > 0 {
+        tokio::time::sleep(crate::sys::DRAIN_POLL_INTERVAL).await;
+    }
+    Ok(())
+}
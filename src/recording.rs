@@ -0,0 +1,468 @@
+//! Reading and writing recordings of pty sessions in formats understood by
+//! other tools.
+//!
+//! Recordings are made up of the same [`Entry`](crate::transcript::Entry)
+//! values that a [`Transcript`](crate::transcript::Transcript) collects, so
+//! a session can be recorded with a transcript and then written out in
+//! whichever [`Format`] is needed (or several of them), and recordings
+//! which have been read back in can be inspected the same way as a
+//! transcript.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! use std::io::Write as _;
+//!
+//! let (pty, pts) = pty_process::blocking::open()?;
+//! let mut child = pty_process::blocking::Command::new("sh").spawn(pts)?;
+//! let transcript = pty_process::transcript::Transcript::new();
+//! let mut pty = transcript.wrap(pty);
+//! pty.write_all(b"exit\n")?;
+//! child.wait()?;
+//!
+//! let mut writer = pty_process::recording::Writer::new(
+//!     pty_process::recording::Format::Ttyrec,
+//!     std::fs::File::create("session.ttyrec")?,
+//! );
+//! writer.write_transcript(&transcript)?;
+//! writer.flush()?;
+//! # Ok(())
+//! # }
+//! ```
+
+/// A recording format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Format {
+    /// The format used by `ttyrec` and `ttyplay`: a sequence of frames,
+    /// each of which is a header holding a timestamp and length, followed
+    /// by the data. Only output is recorded.
+    Ttyrec,
+    /// The format used by `script` from util-linux, with both input and
+    /// output logged to the typescript (as with `script -B`), and the
+    /// timing file (as written by `script -T`) in the advanced format,
+    /// which marks each chunk as input or output and records window size
+    /// changes. When reading, the classic timing file format (which only
+    /// records output) is also accepted.
+    ///
+    /// As with `script`, the typescript starts with a line saying when the
+    /// recording started, which isn't covered by the timing file.
+    Script,
+}
+
+/// Writes [`Entry`](crate::transcript::Entry) values to a recording in the
+/// given [`Format`].
+///
+/// For [`Format::Script`], the typescript is written to the stream given
+/// to [`new`](Self::new), and the timing file to the stream given to
+/// [`timing`](Self::timing). If no timing stream is given, only the
+/// typescript is written. Entries which can't be represented in the format
+/// (such as input in a ttyrec file, or redacted input in either format)
+/// are skipped.
+#[derive(Debug)]
+pub struct Writer<W> {
+    format: Format,
+    data: W,
+    timing: Option<W>,
+    start_time: Option<std::time::SystemTime>,
+    // the start time used if none is set before the first entry is written
+    created: std::time::SystemTime,
+    last: std::time::Duration,
+    started: bool,
+}
+
+impl<W: std::io::Write> Writer<W> {
+    /// Creates a new `Writer` for the given format, writing to the given
+    /// stream. Unless a start time is set with
+    /// [`start_time`](Self::start_time) or taken from a transcript by
+    /// [`write_transcript`](Self::write_transcript), entry timestamps are
+    /// taken as relative to the time that this is called.
+    pub fn new(format: Format, data: W) -> Self {
+        Self {
+            format,
+            data,
+            timing: None,
+            start_time: None,
+            created: std::time::SystemTime::now(),
+            last: std::time::Duration::ZERO,
+            started: false,
+        }
+    }
+
+    /// Sets the stream to write the timing file to, for
+    /// [`Format::Script`]. This is ignored for other formats.
+    #[must_use]
+    pub fn timing(mut self, timing: W) -> Self {
+        self.timing = Some(timing);
+        self
+    }
+
+    /// Sets the time which entry timestamps are relative to, which is used
+    /// for the absolute times stored by [`Format::Ttyrec`] and for the
+    /// start time in the header of [`Format::Script`].
+    #[must_use]
+    pub fn start_time(mut self, start_time: std::time::SystemTime) -> Self {
+        self.start_time = Some(start_time);
+        self
+    }
+
+    /// Writes a single entry. Entries must be written in order of their
+    /// timestamps.
+    ///
+    /// # Errors
+    /// Returns an error if writing to either stream fails, or if the entry
+    /// can't be represented in a ttyrec file (because it is larger than
+    /// 4GiB or its timestamp is after 2106).
+    pub fn write_entry(
+        &mut self,
+        entry: &crate::transcript::Entry,
+    ) -> std::io::Result<()> {
+        match self.format {
+            Format::Ttyrec => self.write_ttyrec(entry),
+            Format::Script => self.write_script(entry),
+        }
+    }
+
+    /// Writes all of the entries recorded in the given transcript so far.
+    /// If no start time has been set (and no entries have been written
+    /// yet), the time at which the transcript was created is used as the
+    /// start time.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails (see
+    /// [`write_entry`](Self::write_entry)).
+    pub fn write_transcript(
+        &mut self,
+        transcript: &crate::transcript::Transcript,
+    ) -> std::io::Result<()> {
+        if self.start_time.is_none() {
+            self.start_time = std::time::SystemTime::now()
+                .checked_sub(transcript.started().elapsed());
+        }
+        for entry in transcript.entries() {
+            self.write_entry(&entry)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes both streams.
+    ///
+    /// # Errors
+    /// Returns an error if flushing either stream fails.
+    pub fn flush(&mut self) -> std::io::Result<()> {
+        self.data.flush()?;
+        if let Some(timing) = &mut self.timing {
+            timing.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Returns the underlying streams: the main stream, and the timing
+    /// stream if one was given.
+    pub fn into_inner(self) -> (W, Option<W>) {
+        (self.data, self.timing)
+    }
+
+    fn write_ttyrec(
+        &mut self,
+        entry: &crate::transcript::Entry,
+    ) -> std::io::Result<()> {
+        let crate::transcript::Event::Output(data) = &entry.event else {
+            return Ok(());
+        };
+        let time = (self.fixed_start_time() + entry.elapsed)
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default();
+        let sec = u32::try_from(time.as_secs())
+            .map_err(|_| invalid("timestamp out of range"))?;
+        let len = u32::try_from(data.len())
+            .map_err(|_| invalid("frame too large"))?;
+        let mut header = [0; 12];
+        header[..4].copy_from_slice(&sec.to_le_bytes());
+        header[4..8].copy_from_slice(&time.subsec_micros().to_le_bytes());
+        header[8..].copy_from_slice(&len.to_le_bytes());
+        self.data.write_all(&header)?;
+        self.data.write_all(data)
+    }
+
+    fn write_script(
+        &mut self,
+        entry: &crate::transcript::Entry,
+    ) -> std::io::Result<()> {
+        if !self.started {
+            self.started = true;
+            let start_time = format_time(self.fixed_start_time());
+            writeln!(self.data, "Script started on {start_time}")?;
+            if let Some(timing) = &mut self.timing {
+                writeln!(timing, "H 0.000000 START_TIME {start_time}")?;
+            }
+        }
+
+        let (kind, data) = match &entry.event {
+            crate::transcript::Event::Input(data) => ("I", data),
+            crate::transcript::Event::Output(data) => ("O", data),
+            crate::transcript::Event::Resize { rows, cols } => {
+                let delay = self.delay(entry);
+                if let Some(timing) = &mut self.timing {
+                    writeln!(
+                        timing,
+                        "S {delay:.6} SIGWINCH ROWS={rows} COLS={cols}"
+                    )?;
+                }
+                return Ok(());
+            }
+            crate::transcript::Event::RedactedInput(_) => return Ok(()),
+        };
+        let delay = self.delay(entry);
+        self.data.write_all(data)?;
+        if let Some(timing) = &mut self.timing {
+            writeln!(timing, "{kind} {delay:.6} {}", data.len())?;
+        }
+        Ok(())
+    }
+
+    // the start time can't change once entries have been written relative
+    // to it
+    fn fixed_start_time(&mut self) -> std::time::SystemTime {
+        *self.start_time.get_or_insert(self.created)
+    }
+
+    fn delay(&mut self, entry: &crate::transcript::Entry) -> f64 {
+        let delay = entry.elapsed.saturating_sub(self.last);
+        self.last = self.last.max(entry.elapsed);
+        delay.as_secs_f64()
+    }
+}
+
+/// Reads [`Entry`](crate::transcript::Entry) values from a recording in
+/// the given [`Format`], as an [`Iterator`].
+///
+/// For [`Format::Script`], the typescript is read from the stream given to
+/// [`new`](Self::new), and the timing file from the stream given to
+/// [`timing`](Self::timing). The first line of the typescript is skipped,
+/// since it is the header written by `script` rather than part of the
+/// recording. If no timing stream is given, the rest of the typescript is
+/// returned as a single chunk of output. Entry timestamps
+/// are relative to the start of the recording.
+#[derive(Debug)]
+pub struct Reader<R> {
+    format: Format,
+    data: R,
+    timing: Option<std::io::BufReader<R>>,
+    start: Option<std::time::Duration>,
+    elapsed: std::time::Duration,
+    header_skipped: bool,
+    done: bool,
+}
+
+impl<R: std::io::Read> Reader<R> {
+    /// Creates a new `Reader` for the given format, reading from the given
+    /// stream.
+    pub fn new(format: Format, data: R) -> Self {
+        Self {
+            format,
+            data,
+            timing: None,
+            start: None,
+            elapsed: std::time::Duration::ZERO,
+            header_skipped: false,
+            done: false,
+        }
+    }
+
+    /// Sets the stream to read the timing file from, for
+    /// [`Format::Script`]. This is ignored for other formats.
+    #[must_use]
+    pub fn timing(mut self, timing: R) -> Self {
+        self.timing = Some(std::io::BufReader::new(timing));
+        self
+    }
+
+    fn read_ttyrec(
+        &mut self,
+    ) -> std::io::Result<Option<crate::transcript::Entry>> {
+        let mut header = [0; 12];
+        if !read_exact_or_eof(&mut self.data, &mut header)? {
+            return Ok(None);
+        }
+        let [s0, s1, s2, s3, u0, u1, u2, u3, l0, l1, l2, l3] = header;
+        let sec = u32::from_le_bytes([s0, s1, s2, s3]);
+        let usec = u32::from_le_bytes([u0, u1, u2, u3]);
+        let len = u32::from_le_bytes([l0, l1, l2, l3]);
+        if usec >= 1_000_000 {
+            return Err(invalid("invalid ttyrec timestamp"));
+        }
+        let time = std::time::Duration::new(sec.into(), usec * 1000);
+        let start = *self.start.get_or_insert(time);
+
+        let data = read_data(&mut self.data, len.into())?;
+        Ok(Some(crate::transcript::Entry {
+            elapsed: time.saturating_sub(start),
+            event: crate::transcript::Event::Output(data),
+        }))
+    }
+
+    fn read_script(
+        &mut self,
+    ) -> std::io::Result<Option<crate::transcript::Entry>> {
+        if !self.header_skipped {
+            self.header_skipped = true;
+            let mut byte = [0];
+            while read_exact_or_eof(&mut self.data, &mut byte)?
+                && byte[0] != b'\n'
+            {}
+        }
+
+        let Some(timing) = &mut self.timing else {
+            if self.done {
+                return Ok(None);
+            }
+            self.done = true;
+            let mut data = vec![];
+            self.data.read_to_end(&mut data)?;
+            return Ok((!data.is_empty()).then_some(
+                crate::transcript::Entry {
+                    elapsed: std::time::Duration::ZERO,
+                    event: crate::transcript::Event::Output(data),
+                },
+            ));
+        };
+
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if std::io::BufRead::read_line(timing, &mut line)? == 0 {
+                return Ok(None);
+            }
+            let fields: Vec<_> = line.split_whitespace().collect();
+            // the classic format has no type field
+            let (kind, fields) = match fields.as_slice() {
+                [] => continue,
+                [kind, fields @ ..] if kind.len() == 1 => (*kind, fields),
+                fields => ("O", fields),
+            };
+            let Some(delay) = fields.first() else {
+                return Err(invalid("invalid script timing line"));
+            };
+            let delay = delay
+                .parse()
+                .ok()
+                .and_then(|delay| {
+                    std::time::Duration::try_from_secs_f64(delay).ok()
+                })
+                .ok_or_else(|| invalid("invalid script timing delay"))?;
+            self.elapsed = self
+                .elapsed
+                .checked_add(delay)
+                .ok_or_else(|| invalid("invalid script timing delay"))?;
+
+            let event = match (kind, &fields[1..]) {
+                ("I" | "O", [len]) => {
+                    let len = len.parse().map_err(|_| {
+                        invalid("invalid script timing length")
+                    })?;
+                    let data = read_data(&mut self.data, len)?;
+                    if kind == "I" {
+                        crate::transcript::Event::Input(data)
+                    } else {
+                        crate::transcript::Event::Output(data)
+                    }
+                }
+                ("S", ["SIGWINCH", rows, cols]) => {
+                    let size = |field: &str, name| {
+                        field
+                            .strip_prefix(name)
+                            .and_then(|n| n.parse().ok())
+                            .ok_or_else(|| invalid("invalid window size"))
+                    };
+                    crate::transcript::Event::Resize {
+                        rows: size(rows, "ROWS=")?,
+                        cols: size(cols, "COLS=")?,
+                    }
+                }
+                // other signals and header information
+                ("S" | "H", _) => continue,
+                _ => return Err(invalid("invalid script timing line")),
+            };
+            return Ok(Some(crate::transcript::Entry {
+                elapsed: self.elapsed,
+                event,
+            }));
+        }
+    }
+}
+
+impl<R: std::io::Read> Iterator for Reader<R> {
+    type Item = std::io::Result<crate::transcript::Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.format {
+            Format::Ttyrec => self.read_ttyrec(),
+            Format::Script => self.read_script(),
+        }
+        .transpose()
+    }
+}
+
+// returns false if the stream is already at end of file, but an error if
+// it ends partway through
+fn read_exact_or_eof(
+    r: &mut impl std::io::Read,
+    buf: &mut [u8],
+) -> std::io::Result<bool> {
+    let mut len = 0;
+    while len < buf.len() {
+        match r.read(&mut buf[len..]) {
+            Ok(0) if len == 0 => return Ok(false),
+            Ok(0) => return Err(std::io::ErrorKind::UnexpectedEof.into()),
+            Ok(bytes) => len += bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+// the length comes from the recording, so the data is read as it arrives
+// rather than allocating the whole length up front
+fn read_data(r: impl std::io::Read, len: u64) -> std::io::Result<Vec<u8>> {
+    let mut data = vec![];
+    std::io::Read::read_to_end(&mut r.take(len), &mut data)?;
+    if u64::try_from(data.len()).unwrap_or(u64::MAX) < len {
+        return Err(std::io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(data)
+}
+
+// formatted the same way as script does, in utc
+fn format_time(time: std::time::SystemTime) -> String {
+    let secs = time
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (hour, min, sec) = (secs / 3600 % 24, secs / 60 % 60, secs % 60);
+
+    // converts days since the epoch to a date in the proleptic gregorian
+    // calendar, from http://howardhinnant.github.io/date_algorithms.html
+    let days = secs / 86400 + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year = day_of_era
+        - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02} {hour:02}:{min:02}:{sec:02}+00:00")
+}
+
+fn invalid(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
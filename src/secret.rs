@@ -0,0 +1,66 @@
+/// Sensitive data, such as a password, to be written to a pty with
+/// `send_secret` (see [`Pty::send_secret`](crate::blocking::Pty::send_secret)).
+///
+/// The contents are overwritten with zeros when this is dropped, and are
+/// never included in the [`Debug`](std::fmt::Debug) output. Writing a
+/// secret through a [`Filtered`](crate::filter::Filtered) stream bypasses
+/// the filters, which are only told how many bytes were written (see
+/// [`Filter::redacted_input`](crate::filter::Filter::redacted_input)).
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes {
+    /// Wraps the given data.
+    #[must_use]
+    pub fn new(data: Vec<u8>) -> Self {
+        Self(data)
+    }
+
+    /// Returns the secret data.
+    #[must_use]
+    pub fn expose(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the length of the secret data.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns true if the secret data is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes {
+    fn from(data: Vec<u8>) -> Self {
+        Self::new(data)
+    }
+}
+
+impl From<String> for SecretBytes {
+    fn from(data: String) -> Self {
+        Self::new(data.into_bytes())
+    }
+}
+
+impl Drop for SecretBytes {
+    fn drop(&mut self) {
+        // volatile writes can't be optimized away, even though the buffer
+        // is about to be freed
+        for byte in &mut self.0 {
+            unsafe { std::ptr::write_volatile(byte, 0) };
+        }
+        std::sync::atomic::compiler_fence(
+            std::sync::atomic::Ordering::SeqCst,
+        );
+    }
+}
+
+impl std::fmt::Debug for SecretBytes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretBytes([REDACTED])")
+    }
+}
@@ -0,0 +1,635 @@
+//! Detachable sessions, which keep a child process and its pty running in
+//! the background and let any number of clients attach to it over a Unix
+//! domain socket, similar to `dtach` or `tmux`.
+//!
+//! A [`SessionServer`] owns the child process and its pty, and a
+//! [`SessionClient`] connects to it to send input, receive output, and
+//! report its window size. Clients which attach after the session has
+//! started will first receive the most recent output (the scrollback), so
+//! that they can redraw the current state of the terminal.
+//!
+//! ```no_run
+//! # #[tokio::main]
+//! # async fn main() -> pty_process::Result<()> {
+//! let (pty, pts) = pty_process::open()?;
+//! let child = pty_process::Command::new("bash").spawn(pts)?;
+//! let listener = tokio::net::UnixListener::bind("/tmp/bash.sock")?;
+//! let status = pty_process::session::SessionServer::new(child, pty)
+//!     .resize_policy(pty_process::session::ResizePolicy::Smallest)
+//!     .run(listener)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+// frames larger than this are rejected, to avoid a misbehaving peer
+// causing unbounded allocations
+const MAX_FRAME_LEN: u32 = 1024 * 1024;
+
+// the number of messages from clients which can be waiting to be handled
+// before clients stop being read from
+const EVENT_QUEUE_LEN: usize = 64;
+
+// how long to wait before accepting again after accepting a client fails
+const ACCEPT_BACKOFF: std::time::Duration =
+    std::time::Duration::from_millis(100);
+
+// how long to wait for more output after the child exits, since something
+// else may still have the pty open
+const EXIT_OUTPUT_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_millis(100);
+
+// how long clients have to receive the rest of the output and the exit
+// status after the child exits, before they are disconnected regardless
+const EXIT_CLIENT_TIMEOUT: std::time::Duration =
+    std::time::Duration::from_secs(1);
+
+// client to server
+const INPUT: u8 = 0;
+const RESIZE: u8 = 1;
+const DETACH: u8 = 2;
+// server to client
+const OUTPUT: u8 = 0;
+const EXIT: u8 = 1;
+
+/// How the size of the pty is chosen when multiple clients are attached.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResizePolicy {
+    /// Use the smallest number of rows and columns of any attached client,
+    /// so that the whole terminal is visible to every client.
+    #[default]
+    Smallest,
+    /// Use the size most recently reported by any client.
+    Latest,
+}
+
+/// Keeps a child process and its pty running, and serves them to clients
+/// connecting over a Unix domain socket.
+pub struct SessionServer {
+    child: crate::Child,
+    pty: crate::Pty,
+    scrollback: usize,
+    resize_policy: ResizePolicy,
+}
+
+impl SessionServer {
+    /// Creates a server for the given child process, which should have
+    /// been spawned on the given pty.
+    #[must_use]
+    pub fn new(child: crate::Child, pty: crate::Pty) -> Self {
+        Self {
+            child,
+            pty,
+            scrollback: 64 * 1024,
+            resize_policy: ResizePolicy::default(),
+        }
+    }
+
+    /// Sets the number of bytes of recent output to send to newly attached
+    /// clients. Defaults to 64KiB.
+    #[must_use]
+    pub fn scrollback(mut self, scrollback: usize) -> Self {
+        self.scrollback = scrollback;
+        self
+    }
+
+    /// Sets how the size of the pty is chosen when multiple clients are
+    /// attached. Defaults to [`ResizePolicy::Smallest`].
+    #[must_use]
+    pub fn resize_policy(mut self, resize_policy: ResizePolicy) -> Self {
+        self.resize_policy = resize_policy;
+        self
+    }
+
+    /// Accepts clients on the given listener until the child process
+    /// exits, and returns its exit status. Attached clients are sent the
+    /// remaining output and the exit status, and are then disconnected,
+    /// before this returns. Clients which aren't reading quickly enough to
+    /// receive them within a short time are disconnected without them.
+    ///
+    /// Errors accepting connections (such as running out of file
+    /// descriptors) are logged, and don't stop the session.
+    ///
+    /// # Errors
+    /// Returns an error if waiting for the child or writing to or
+    /// resizing the pty fails.
+    pub async fn run(
+        mut self,
+        listener: tokio::net::UnixListener,
+    ) -> crate::Result<std::process::ExitStatus> {
+        let (pty_r, pty_w) = self.pty.into_split();
+        let broadcast = crate::broadcast::PtyBroadcast::spawn(
+            pty_r,
+            crate::broadcast::Config::new()
+                .capacity(self.scrollback.max(4096))
+                .replay(self.scrollback),
+        );
+        // input and resizes are handled in their own task, so that a child
+        // which stops reading its input doesn't hold up anything else
+        let (events_tx, events_rx) =
+            tokio::sync::mpsc::channel(EVENT_QUEUE_LEN);
+        let mut pty_task = tokio::spawn(handle_events(
+            pty_w,
+            events_rx,
+            Sizes::new(self.resize_policy),
+        ));
+        let (exit_tx, exit_rx) = tokio::sync::watch::channel(None);
+        let mut clients = tokio::task::JoinSet::new();
+        let mut next_id = 0;
+        let backoff = tokio::time::sleep(std::time::Duration::ZERO);
+        tokio::pin!(backoff);
+        let mut backing_off = false;
+
+        let status = loop {
+            tokio::select! {
+                stream = listener.accept(), if !backing_off => match stream {
+                    Ok((stream, _)) => {
+                        clients.spawn(serve_client(
+                            next_id,
+                            stream,
+                            broadcast.subscribe(),
+                            events_tx.clone(),
+                            exit_rx.clone(),
+                        ));
+                        next_id += 1;
+                    }
+                    Err(e) => {
+                        // these are usually temporary, so keep going, but
+                        // don't spin while the problem persists
+                        log::warn!("failed to accept session client: {e}");
+                        backoff
+                            .as_mut()
+                            .reset(tokio::time::Instant::now() + ACCEPT_BACKOFF);
+                        backing_off = true;
+                    }
+                },
+                () = &mut backoff, if backing_off => backing_off = false,
+                // this only finishes if writing to or resizing the pty
+                // fails, since the channel stays open while this loop runs
+                e = &mut pty_task => {
+                    return Err(e.unwrap_or_else(|e| {
+                        std::io::Error::other(e).into()
+                    }));
+                }
+                status = self.child.wait() => break status?,
+            }
+        };
+
+        // the child is gone, so there is nothing left to send input to, and
+        // clients shouldn't wait for room to send it
+        pty_task.abort();
+        let _ = exit_tx.send(Some(status));
+        // a client which has stopped reading would otherwise keep this from
+        // ever returning
+        let _ = tokio::time::timeout(EXIT_CLIENT_TIMEOUT, async {
+            while clients.join_next().await.is_some() {}
+        })
+        .await;
+        clients.abort_all();
+        Ok(status)
+    }
+}
+
+impl std::fmt::Debug for SessionServer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SessionServer")
+            .field("scrollback", &self.scrollback)
+            .field("resize_policy", &self.resize_policy)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Something received from a [`SessionServer`].
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum SessionEvent {
+    /// Output from the pty. The first output received after attaching
+    /// contains the scrollback.
+    Output(Vec<u8>),
+    /// The child process exited with the given status. No more events
+    /// will be received after this.
+    Exit(std::process::ExitStatus),
+}
+
+/// A client attached to a [`SessionServer`].
+///
+/// This can be split into its read and write halves with
+/// [`into_split`](Self::into_split), so that input can be sent while
+/// waiting for output.
+#[derive(Debug)]
+pub struct SessionClient {
+    reader: SessionReader,
+    writer: SessionWriter,
+}
+
+impl SessionClient {
+    /// Attaches to the session server listening on the given path.
+    ///
+    /// # Errors
+    /// Returns an error if connecting to the socket fails.
+    pub async fn connect(
+        path: impl AsRef<std::path::Path>,
+    ) -> std::io::Result<Self> {
+        let stream = tokio::net::UnixStream::connect(path).await?;
+        Ok(Self::from_stream(stream))
+    }
+
+    /// Wraps a stream which is already connected to a session server.
+    #[must_use]
+    pub fn from_stream(stream: tokio::net::UnixStream) -> Self {
+        let (read, write) = stream.into_split();
+        Self {
+            reader: SessionReader(read),
+            writer: SessionWriter(write),
+        }
+    }
+
+    /// See [`SessionReader::recv`].
+    ///
+    /// # Errors
+    /// Returns an error if reading from the socket fails, or if the server
+    /// sends an invalid message.
+    pub async fn recv(&mut self) -> std::io::Result<Option<SessionEvent>> {
+        self.reader.recv().await
+    }
+
+    /// See [`SessionWriter::send_input`].
+    ///
+    /// # Errors
+    /// Returns an error if writing to the socket fails.
+    pub async fn send_input(&mut self, data: &[u8]) -> std::io::Result<()> {
+        self.writer.send_input(data).await
+    }
+
+    /// See [`SessionWriter::resize`].
+    ///
+    /// # Errors
+    /// Returns an error if writing to the socket fails.
+    pub async fn resize(&mut self, size: crate::Size) -> std::io::Result<()> {
+        self.writer.resize(size).await
+    }
+
+    /// See [`SessionWriter::detach`].
+    ///
+    /// # Errors
+    /// Returns an error if writing to the socket fails.
+    pub async fn detach(self) -> std::io::Result<()> {
+        self.writer.detach().await
+    }
+
+    /// Splits the client into a read half and a write half.
+    #[must_use]
+    pub fn into_split(self) -> (SessionReader, SessionWriter) {
+        (self.reader, self.writer)
+    }
+}
+
+/// The read half of a [`SessionClient`].
+#[derive(Debug)]
+pub struct SessionReader(tokio::net::unix::OwnedReadHalf);
+
+impl SessionReader {
+    /// Receives the next event from the server, returning `None` if the
+    /// server closed the connection.
+    ///
+    /// This is not cancel safe: if the returned future is dropped before
+    /// it completes, part of a message may have been read and discarded.
+    ///
+    /// # Errors
+    /// Returns an error if reading from the socket fails, or if the server
+    /// sends an invalid message.
+    pub async fn recv(&mut self) -> std::io::Result<Option<SessionEvent>> {
+        use std::os::unix::process::ExitStatusExt as _;
+
+        loop {
+            let Some((kind, data)) = read_frame(&mut self.0).await? else {
+                return Ok(None);
+            };
+            match kind {
+                OUTPUT => return Ok(Some(SessionEvent::Output(data))),
+                EXIT => {
+                    let status = <[u8; 4]>::try_from(data.as_slice())
+                        .map_err(|_| invalid_data("invalid exit message"))?;
+                    return Ok(Some(SessionEvent::Exit(
+                        std::process::ExitStatus::from_raw(
+                            i32::from_be_bytes(status),
+                        ),
+                    )));
+                }
+                // unknown messages are ignored, for forwards compatibility
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The write half of a [`SessionClient`].
+#[derive(Debug)]
+pub struct SessionWriter(tokio::net::unix::OwnedWriteHalf);
+
+impl SessionWriter {
+    /// Sends input to be written to the pty.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the socket fails.
+    pub async fn send_input(&mut self, data: &[u8]) -> std::io::Result<()> {
+        for chunk in data.chunks(max_frame_len()) {
+            write_frame(&mut self.0, INPUT, chunk).await?;
+        }
+        Ok(())
+    }
+
+    /// Reports the size of this client's terminal. The size of the pty is
+    /// then chosen according to the server's [`ResizePolicy`].
+    ///
+    /// # Errors
+    /// Returns an error if writing to the socket fails.
+    pub async fn resize(&mut self, size: crate::Size) -> std::io::Result<()> {
+        let size = rustix::termios::Winsize::from(size);
+        let mut data = [0; 8];
+        for (dst, src) in data.chunks_mut(2).zip([
+            size.ws_row,
+            size.ws_col,
+            size.ws_xpixel,
+            size.ws_ypixel,
+        ]) {
+            dst.copy_from_slice(&src.to_be_bytes());
+        }
+        write_frame(&mut self.0, RESIZE, &data).await
+    }
+
+    /// Detaches from the session, leaving the child process running.
+    ///
+    /// # Errors
+    /// Returns an error if writing to the socket fails.
+    pub async fn detach(mut self) -> std::io::Result<()> {
+        use tokio::io::AsyncWriteExt as _;
+
+        write_frame(&mut self.0, DETACH, &[]).await?;
+        self.0.shutdown().await
+    }
+}
+
+enum Event {
+    Input(Vec<u8>),
+    Resize(usize, Winsize),
+    Detach(usize),
+}
+
+// crate::Size has no accessors or equality, so the raw fields are tracked
+// here instead
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Winsize {
+    rows: u16,
+    cols: u16,
+    xpixel: u16,
+    ypixel: u16,
+}
+
+impl From<Winsize> for crate::Size {
+    fn from(size: Winsize) -> Self {
+        Self::new_with_pixel(size.rows, size.cols, size.xpixel, size.ypixel)
+    }
+}
+
+struct Sizes {
+    policy: ResizePolicy,
+    clients: std::collections::HashMap<usize, Winsize>,
+    current: Option<Winsize>,
+}
+
+impl Sizes {
+    fn new(policy: ResizePolicy) -> Self {
+        Self {
+            policy,
+            clients: std::collections::HashMap::new(),
+            current: None,
+        }
+    }
+
+    // these return the new size of the pty, if it changed
+
+    fn set(&mut self, id: usize, size: Winsize) -> Option<crate::Size> {
+        self.clients.insert(id, size);
+        match self.policy {
+            ResizePolicy::Smallest => self.update(),
+            ResizePolicy::Latest => self.change(size),
+        }
+    }
+
+    fn remove(&mut self, id: usize) -> Option<crate::Size> {
+        self.clients.remove(&id)?;
+        match self.policy {
+            ResizePolicy::Smallest => self.update(),
+            ResizePolicy::Latest => None,
+        }
+    }
+
+    fn update(&mut self) -> Option<crate::Size> {
+        let smallest = self
+            .clients
+            .values()
+            .copied()
+            .reduce(|a, b| if a.rows < b.rows { a } else { b })?;
+        let narrowest = self
+            .clients
+            .values()
+            .copied()
+            .reduce(|a, b| if a.cols < b.cols { a } else { b })?;
+        // pixel sizes are only meaningful along with the matching number
+        // of rows or columns
+        self.change(Winsize {
+            rows: smallest.rows,
+            cols: narrowest.cols,
+            xpixel: narrowest.xpixel,
+            ypixel: smallest.ypixel,
+        })
+    }
+
+    fn change(&mut self, size: Winsize) -> Option<crate::Size> {
+        (self.current != Some(size)).then(|| {
+            self.current = Some(size);
+            size.into()
+        })
+    }
+}
+
+async fn handle_events(
+    mut pty: crate::OwnedWritePty,
+    mut events: tokio::sync::mpsc::Receiver<Event>,
+    mut sizes: Sizes,
+) -> crate::Error {
+    use tokio::io::AsyncWriteExt as _;
+
+    while let Some(event) = events.recv().await {
+        let res = match event {
+            Event::Input(data) => {
+                pty.write_all(&data).await.map_err(crate::Error::from)
+            }
+            Event::Resize(id, size) => {
+                sizes.set(id, size).map_or(Ok(()), |size| pty.resize(size))
+            }
+            Event::Detach(id) => {
+                sizes.remove(id).map_or(Ok(()), |size| pty.resize(size))
+            }
+        };
+        if let Err(e) = res {
+            return e;
+        }
+    }
+    // the session has ended, and this task is about to be aborted
+    std::future::pending().await
+}
+
+async fn serve_client(
+    id: usize,
+    stream: tokio::net::UnixStream,
+    mut output: crate::broadcast::Subscriber,
+    events: tokio::sync::mpsc::Sender<Event>,
+    mut exit: tokio::sync::watch::Receiver<Option<std::process::ExitStatus>>,
+) {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let (mut read, mut write) = stream.into_split();
+    let input = async {
+        while let Ok(Some((kind, data))) = read_frame(&mut read).await {
+            match kind {
+                // these wait while the pty isn't accepting input, which stops
+                // reading from this client until it does
+                INPUT => {
+                    let _ = events.send(Event::Input(data)).await;
+                }
+                RESIZE => {
+                    if let Some(size) = decode_size(&data) {
+                        let _ = events.send(Event::Resize(id, size)).await;
+                    }
+                }
+                DETACH => break,
+                // unknown messages are ignored, for forwards compatibility
+                _ => {}
+            }
+        }
+    };
+    let output = async {
+        let mut exited = false;
+        loop {
+            let data = if exited {
+                tokio::time::timeout(
+                    EXIT_OUTPUT_TIMEOUT,
+                    next_output(&mut output),
+                )
+                .await
+                .unwrap_or(None)
+            } else {
+                tokio::select! {
+                    data = next_output(&mut output) => data,
+                    _ = exit.wait_for(Option::is_some) => {
+                        exited = true;
+                        continue;
+                    }
+                }
+            };
+            let Some(data) = data else {
+                break;
+            };
+            // a client which falls behind misses some output, but is
+            // otherwise unaffected
+            let Ok(data) = data else {
+                continue;
+            };
+            if write_frame(&mut write, OUTPUT, &data).await.is_err() {
+                return;
+            }
+        }
+        let status = exit
+            .wait_for(Option::is_some)
+            .await
+            .ok()
+            .and_then(|status| *status);
+        if let Some(status) = status {
+            let _ = write_frame(
+                &mut write,
+                EXIT,
+                &status.into_raw().to_be_bytes(),
+            )
+            .await;
+        }
+    };
+    tokio::select! {
+        () = input => {}
+        () = output => {}
+    }
+    let _ = events.send(Event::Detach(id)).await;
+}
+
+async fn next_output(
+    output: &mut crate::broadcast::Subscriber,
+) -> Option<Result<Vec<u8>, crate::broadcast::Lagged>> {
+    use futures_core::Stream as _;
+
+    std::future::poll_fn(|cx| std::pin::Pin::new(&mut *output).poll_next(cx))
+        .await
+}
+
+fn decode_size(data: &[u8]) -> Option<Winsize> {
+    let data = <[u8; 8]>::try_from(data).ok()?;
+    let field = |i: usize| u16::from_be_bytes([data[i], data[i + 1]]);
+    Some(Winsize {
+        rows: field(0),
+        cols: field(2),
+        xpixel: field(4),
+        ypixel: field(6),
+    })
+}
+
+fn max_frame_len() -> usize {
+    usize::try_from(MAX_FRAME_LEN).unwrap_or(usize::MAX)
+}
+
+fn invalid_data(msg: &str) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, msg)
+}
+
+// each message is a one byte type, followed by the length of the payload
+// as a big endian u32, followed by the payload
+
+async fn read_frame<R: tokio::io::AsyncRead + Unpin>(
+    reader: &mut R,
+) -> std::io::Result<Option<(u8, Vec<u8>)>> {
+    use tokio::io::AsyncReadExt as _;
+
+    let mut header = [0; 5];
+    match reader.read_exact(&mut header).await {
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => {
+            return Ok(None);
+        }
+        Err(e) => return Err(e),
+    }
+    let [kind, len @ ..] = header;
+    let len = u32::from_be_bytes(len);
+    if len > MAX_FRAME_LEN {
+        return Err(invalid_data("message too large"));
+    }
+    let mut data = vec![0; usize::try_from(len).unwrap_or(usize::MAX)];
+    reader.read_exact(&mut data).await?;
+    Ok(Some((kind, data)))
+}
+
+async fn write_frame<W: tokio::io::AsyncWrite + Unpin>(
+    writer: &mut W,
+    kind: u8,
+    data: &[u8],
+) -> std::io::Result<()> {
+    use tokio::io::AsyncWriteExt as _;
+
+    let len = u32::try_from(data.len())
+        .ok()
+        .filter(|&len| len <= MAX_FRAME_LEN)
+        .ok_or_else(|| invalid_data("message too large"))?;
+    let mut frame = Vec::with_capacity(data.len() + 5);
+    frame.push(kind);
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(data);
+    writer.write_all(&frame).await
+}
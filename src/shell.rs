@@ -0,0 +1,391 @@
+//! Support for the shell integration escape sequences which shells can emit
+//! to mark the boundaries of prompts and commands (`OSC 133`) and to report
+//! their current directory (`OSC 7`).
+//!
+//! Most shells need to be configured to emit these sequences. [`Shell`] can
+//! spawn `bash`, `zsh`, or `fish` with that configuration added on top of
+//! the user's own.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! use std::io::Read as _;
+//!
+//! let (mut pty, pts) = pty_process::blocking::open()?;
+//! let mut child =
+//!     pty_process::shell::Shell::Bash.blocking_command()?.spawn(pts)?;
+//! let mut parser = pty_process::shell::Parser::new();
+//! let mut buf = [0; 4096];
+//! loop {
+//!     let bytes = pty.read(&mut buf)?;
+//!     for event in parser.process(&buf[..bytes]) {
+//!         if let pty_process::shell::Event::CommandEnd { exit } = event {
+//!             println!("command exited with {exit:?}");
+//!         }
+//!     }
+//! }
+//! # }
+//! ```
+
+mod session;
+pub use session::{ShellOutput, ShellSession};
+
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::fs::DirBuilderExt as _;
+use std::os::unix::fs::MetadataExt as _;
+
+/// A shell integration event reported by the shell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// The shell is about to draw its prompt (`OSC 133 ; A`).
+    PromptStart,
+    /// The prompt has been drawn, and the user can start typing a command
+    /// (`OSC 133 ; B`).
+    PromptEnd,
+    /// The command line has been submitted, and the command is about to
+    /// start running. Anything after this is the command's output
+    /// (`OSC 133 ; C`).
+    CommandStart,
+    /// The command has finished (`OSC 133 ; D`). The exit status is
+    /// included if the shell reported it.
+    CommandEnd {
+        /// The exit status of the command.
+        exit: Option<i32>,
+    },
+    /// The shell's current directory has changed (`OSC 7`).
+    Cwd(std::path::PathBuf),
+}
+
+/// A streaming parser which extracts shell integration [`Event`]s from the
+/// output of a pty.
+///
+/// The output can be given in chunks of any size, since escape sequences
+/// which are split across chunks are buffered until they are complete.
+#[derive(Debug, Default)]
+pub struct Parser {
+    parser: crate::ansi::Parser,
+}
+
+impl Parser {
+    /// Creates a new parser.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Processes the next chunk of output from the pty, returning any
+    /// events it completed.
+    pub fn process(&mut self, data: &[u8]) -> Vec<Event> {
+        let mut events = vec![];
+        for &byte in data {
+            if let Some(crate::ansi::Action::String(b']', osc)) =
+                self.parser.advance(byte)
+                && let Some(event) = parse_osc(osc)
+            {
+                events.push(event);
+            }
+        }
+        events
+    }
+}
+
+/// Returns a [`Filter`](crate::filter::Filter) which passes each
+/// shell integration [`Event`] in the output of the pty to the given
+/// function. The output itself is passed through unchanged.
+///
+/// ```no_run
+/// # fn main() -> pty_process::Result<()> {
+/// let (pty, pts) = pty_process::blocking::open()?;
+/// let mut child =
+///     pty_process::shell::Shell::Zsh.blocking_command()?.spawn(pts)?;
+/// let pty = pty_process::filter::Filtered::new(pty).filter(
+///     pty_process::shell::events(|event| eprintln!("{event:?}")),
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn events<F>(f: F) -> impl crate::filter::Filter
+where
+    F: FnMut(Event) + Send,
+{
+    Events {
+        parser: Parser::new(),
+        f,
+    }
+}
+
+struct Events<F> {
+    parser: Parser,
+    f: F,
+}
+
+impl<F: FnMut(Event) + Send> crate::filter::Filter for Events<F> {
+    fn output(
+        &mut self,
+        data: &mut Vec<u8>,
+        _: &mut crate::filter::Context<'_>,
+    ) {
+        for event in self.parser.process(data) {
+            (self.f)(event);
+        }
+    }
+}
+
+fn parse_osc(osc: &[u8]) -> Option<Event> {
+    let mut fields = osc.split(|&byte| byte == b';');
+    match fields.next()? {
+        b"133" => match fields.next()? {
+            b"A" => Some(Event::PromptStart),
+            b"B" => Some(Event::PromptEnd),
+            b"C" => Some(Event::CommandStart),
+            b"D" => Some(Event::CommandEnd {
+                exit: fields
+                    .next()
+                    .and_then(|exit| std::str::from_utf8(exit).ok())
+                    .and_then(|exit| exit.parse().ok()),
+            }),
+            _ => None,
+        },
+        b"7" => {
+            // the rest of the sequence is the url, which may itself contain
+            // semicolons
+            let url = osc.strip_prefix(b"7;")?;
+            let url = url.strip_prefix(b"file://")?;
+            // skip the hostname
+            let path = &url[url.iter().position(|&byte| byte == b'/')?..];
+            Some(Event::Cwd(std::path::PathBuf::from(
+                std::ffi::OsStr::from_bytes(&percent_decode(path)),
+            )))
+        }
+        _ => None,
+    }
+}
+
+fn percent_decode(s: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(s.len());
+    let mut i = 0;
+    while i < s.len() {
+        if s[i] == b'%'
+            && let Some(hex) = s.get(i + 1..i + 3)
+            && let Ok(hex) = std::str::from_utf8(hex)
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(s[i]);
+            i += 1;
+        }
+    }
+    decoded
+}
+
+/// A shell which can be configured to emit shell integration sequences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Shell {
+    /// GNU bash, version 4.4 or later
+    Bash,
+    /// zsh
+    Zsh,
+    /// fish
+    Fish,
+}
+
+impl Shell {
+    /// Returns the shell code which configures this shell to emit shell
+    /// integration sequences. This can be added to the end of the shell's
+    /// startup file by hand, if [`command`](Self::command) or
+    /// [`blocking_command`](Self::blocking_command) aren't suitable.
+    #[must_use]
+    pub fn integration_script(self) -> &'static str {
+        match self {
+            Self::Bash => BASH_INTEGRATION,
+            Self::Zsh => ZSH_INTEGRATION,
+            Self::Fish => FISH_INTEGRATION,
+        }
+    }
+
+    /// Returns a [`Command`](crate::Command) which runs this shell, with
+    /// [`integration_script`](Self::integration_script) run after the
+    /// user's usual startup files.
+    ///
+    /// For `bash` and `zsh`, this writes the startup files needed to do so
+    /// into a private directory under [`std::env::temp_dir`].
+    ///
+    /// # Errors
+    /// Returns an error if the startup files couldn't be written.
+    #[cfg(feature = "async")]
+    pub fn command(self) -> crate::Result<crate::Command> {
+        Ok(tokio::process::Command::from(self.std_command()?).into())
+    }
+
+    /// Returns a [`blocking::Command`](crate::blocking::Command) which runs
+    /// this shell, with [`integration_script`](Self::integration_script)
+    /// run after the user's usual startup files.
+    ///
+    /// For `bash` and `zsh`, this writes the startup files needed to do so
+    /// into a private directory under [`std::env::temp_dir`].
+    ///
+    /// # Errors
+    /// Returns an error if the startup files couldn't be written.
+    pub fn blocking_command(self) -> crate::Result<crate::blocking::Command> {
+        Ok(self.std_command()?.into())
+    }
+
+    fn std_command(self) -> std::io::Result<std::process::Command> {
+        let mut cmd;
+        match self {
+            Self::Bash => {
+                let dir = integration_dir()?;
+                let rcfile = dir.join("bashrc");
+                write_file(
+                    &rcfile,
+                    &format!(
+                        "if [ -f ~/.bashrc ]; then . ~/.bashrc; fi\n\
+                        {BASH_INTEGRATION}"
+                    ),
+                )?;
+                cmd = std::process::Command::new("bash");
+                cmd.arg("--rcfile").arg(rcfile);
+            }
+            Self::Zsh => {
+                let dir = integration_dir()?.join("zsh");
+                create_private_dir(&dir)?;
+                for name in [".zshenv", ".zprofile"] {
+                    write_file(
+                        &dir.join(name),
+                        &ZSH_WRAPPER.replace("@NAME@", name),
+                    )?;
+                }
+                write_file(
+                    &dir.join(".zshrc"),
+                    &format!("{ZSH_RC}{ZSH_INTEGRATION}"),
+                )?;
+                cmd = std::process::Command::new("zsh");
+                if let Some(zdotdir) = std::env::var_os("ZDOTDIR") {
+                    cmd.env("PTY_PROCESS_ZDOTDIR", zdotdir);
+                }
+                cmd.env("ZDOTDIR", dir);
+            }
+            Self::Fish => {
+                cmd = std::process::Command::new("fish");
+                cmd.arg("--init-command").arg(FISH_INTEGRATION);
+            }
+        }
+        Ok(cmd)
+    }
+}
+
+// $PWD is sent as the path of a file:// url, which the parser percent
+// decodes, so any % in it has to be escaped first.
+//
+// PS0 can't run commands in the current shell, but array subscripts are
+// evaluated arithmetically, so indexing into an unset array is used to set
+// a variable without printing anything
+const BASH_INTEGRATION: &str = r#"__pty_process_prompt() {
+    local ret=$?
+    if [ -n "$__pty_process_running" ]; then
+        printf '\033]133;D;%s\007' "$ret"
+        __pty_process_running=
+    fi
+    printf '\033]7;file://%s%s\007\033]133;A\007' "$HOSTNAME" "${PWD//\%/%25}"
+    return $ret
+}
+PROMPT_COMMAND="__pty_process_prompt${PROMPT_COMMAND:+;$PROMPT_COMMAND}"
+PS1="$PS1"'\[\033]133;B\007\]'
+PS0="$PS0"'${__pty_process_unset[__pty_process_running=1]}\033]133;C\007'
+"#;
+
+const ZSH_INTEGRATION: &str = r#"__pty_process_precmd() {
+    local ret=$?
+    if [[ -n $__pty_process_running ]]; then
+        printf '\033]133;D;%s\007' $ret
+        __pty_process_running=
+    fi
+    printf '\033]7;file://%s%s\007\033]133;A\007' $HOST ${PWD//\%/%25}
+}
+__pty_process_preexec() {
+    __pty_process_running=1
+    printf '\033]133;C\007'
+}
+precmd_functions=(__pty_process_precmd $precmd_functions)
+preexec_functions+=(__pty_process_preexec)
+PS1="$PS1"$'%{\033]133;B\007%}'
+"#;
+
+const FISH_INTEGRATION: &str = r"function __pty_process_prompt --on-event fish_prompt
+    set -l ret $status
+    if set -q __pty_process_running
+        printf '\e]133;D;%s\a' $ret
+        set -e __pty_process_running
+    end
+    printf '\e]7;file://%s%s\a\e]133;A\a' $hostname \
+        (string replace -a '%' '%25' -- $PWD)
+end
+function __pty_process_preexec --on-event fish_preexec
+    set -g __pty_process_running 1
+    printf '\e]133;C\a'
+end
+";
+
+// zsh reads its startup files from $ZDOTDIR, so that is pointed at a
+// directory containing these, which run the user's startup files from
+// their original location
+const ZSH_WRAPPER: &str = r"__pty_process_zdotdir=$ZDOTDIR
+ZDOTDIR=${PTY_PROCESS_ZDOTDIR:-$HOME}
+[[ -f $ZDOTDIR/@NAME@ ]] && . $ZDOTDIR/@NAME@
+PTY_PROCESS_ZDOTDIR=$ZDOTDIR
+ZDOTDIR=$__pty_process_zdotdir
+";
+
+const ZSH_RC: &str = r"ZDOTDIR=${PTY_PROCESS_ZDOTDIR:-$HOME}
+unset PTY_PROCESS_ZDOTDIR __pty_process_zdotdir
+[[ -f $ZDOTDIR/.zshrc ]] && . $ZDOTDIR/.zshrc
+";
+
+fn integration_dir() -> std::io::Result<std::path::PathBuf> {
+    let dir = std::env::temp_dir().join(format!(
+        "pty-process-{}",
+        rustix::process::getuid().as_raw()
+    ));
+    create_private_dir(&dir)?;
+    Ok(dir)
+}
+
+// the directory is in a shared location, so make sure that nobody else
+// could have put anything in it
+fn create_private_dir(dir: &std::path::Path) -> std::io::Result<()> {
+    match std::fs::DirBuilder::new().mode(0o700).create(dir) {
+        Ok(()) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    let metadata = std::fs::symlink_metadata(dir)?;
+    if !metadata.is_dir()
+        || metadata.uid() != rustix::process::getuid().as_raw()
+        || metadata.mode() & 0o077 != 0
+    {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            format!("{} is not a private directory", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+// written to a temporary file and then renamed, so that shells starting
+// concurrently never see a partially written file
+fn write_file(path: &std::path::Path, contents: &str) -> std::io::Result<()> {
+    static COUNTER: std::sync::atomic::AtomicUsize =
+        std::sync::atomic::AtomicUsize::new(0);
+
+    let mut tmp = path.as_os_str().to_os_string();
+    tmp.push(format!(
+        ".{}.{}",
+        std::process::id(),
+        COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+    ));
+    std::fs::write(&tmp, contents)?;
+    std::fs::rename(&tmp, path)
+}
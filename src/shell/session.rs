@@ -0,0 +1,222 @@
+// output before the start marker is discarded as it arrives, but this much
+// is kept in case the marker itself is split across reads
+const MAX_DISCARD_LOOKBEHIND: usize = 64;
+
+/// Runs commands one at a time in a long-lived interactive shell running on
+/// a pty, capturing the output and exit status of each.
+///
+/// Each command is run in the shell itself (via `eval`) rather than in a
+/// new process, so changes it makes to the shell's state, such as changing
+/// directories or setting variables, are visible to later commands. The
+/// shell must use POSIX `sh` syntax (such as `bash`, `zsh`, or `dash`).
+///
+/// The end of each command's output is detected by having the shell print
+/// a unique marker after the command finishes, so this works regardless of
+/// how the shell's prompt is configured. The shell should be left at its
+/// prompt between commands, and anything else that the shell prints (such
+/// as the prompt and the echoed command line) is discarded.
+///
+/// ```no_run
+/// # fn main() -> pty_process::Result<()> {
+/// let (pty, pts) = pty_process::blocking::open()?;
+/// let mut child =
+///     pty_process::blocking::Command::new("bash").arg("-l").spawn(pts)?;
+/// let mut session = pty_process::shell::ShellSession::new(pty);
+/// session.run("cd /tmp")?;
+/// let output = session.run("ls")?;
+/// assert!(output.success());
+/// print!("{}", String::from_utf8_lossy(&output.output));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct ShellSession<P> {
+    pty: P,
+    buf: Vec<u8>,
+    count: u64,
+}
+
+/// The result of a command run by a [`ShellSession`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShellOutput {
+    /// Everything the command wrote to the terminal, as the terminal
+    /// received it. Note that with the default terminal settings, newlines
+    /// written by the command will be translated to `\r\n`.
+    pub output: Vec<u8>,
+    /// The exit status of the command, as reported by the shell's `$?`.
+    pub status: i32,
+}
+
+impl ShellOutput {
+    /// Returns true if the command exited with a status of 0.
+    #[must_use]
+    pub fn success(&self) -> bool {
+        self.status == 0
+    }
+}
+
+impl<P> ShellSession<P> {
+    /// Wraps the given pty, which should be attached to a running shell.
+    pub fn new(pty: P) -> Self {
+        Self {
+            pty,
+            buf: vec![],
+            count: 0,
+        }
+    }
+
+    /// Returns a reference to the wrapped pty.
+    pub fn get_ref(&self) -> &P {
+        &self.pty
+    }
+
+    /// Returns a mutable reference to the wrapped pty.
+    pub fn get_mut(&mut self) -> &mut P {
+        &mut self.pty
+    }
+
+    /// Returns the wrapped pty.
+    pub fn into_inner(self) -> P {
+        self.pty
+    }
+
+    fn start(&mut self, command: &str) -> (Vec<u8>, Markers) {
+        self.count += 1;
+        self.buf.clear();
+        let id = format!(
+            "__pty_process_{}_{}_{}",
+            std::process::id(),
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .subsec_nanos(),
+            self.count
+        );
+        // the markers are printed in two pieces, so that the echoed command
+        // line doesn't contain them
+        let line = format!(
+            "printf '%s_%s\\n' {id} begin; eval '{}'; \
+            printf '%s_%s %s\\n' {id} end \"$?\"\n",
+            command.replace('\'', "'\\''")
+        );
+        (
+            line.into_bytes(),
+            Markers {
+                begin: format!("{id}_begin").into_bytes(),
+                end: format!("{id}_end ").into_bytes(),
+                started: false,
+            },
+        )
+    }
+}
+
+impl<P: std::io::Read + std::io::Write> ShellSession<P> {
+    /// Runs the given command line in the shell, and waits for it to
+    /// finish.
+    ///
+    /// # Errors
+    /// Returns an error if writing to or reading from the pty fails, or if
+    /// the pty is closed before the command finishes.
+    pub fn run(&mut self, command: &str) -> std::io::Result<ShellOutput> {
+        let (line, mut markers) = self.start(command);
+        self.pty.write_all(&line)?;
+        self.pty.flush()?;
+        let mut data = [0_u8; 4096];
+        loop {
+            if let Some(output) = markers.process(&mut self.buf) {
+                return Ok(output);
+            }
+            match self.pty.read(&mut data) {
+                Ok(0) => return Err(unexpected_eof()),
+                Ok(bytes) => self.buf.extend_from_slice(&data[..bytes]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<P: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin>
+    ShellSession<P>
+{
+    /// Runs the given command line in the shell, and waits for it to
+    /// finish.
+    ///
+    /// # Errors
+    /// Returns an error if writing to or reading from the pty fails, or if
+    /// the pty is closed before the command finishes.
+    pub async fn run_async(
+        &mut self,
+        command: &str,
+    ) -> std::io::Result<ShellOutput> {
+        use tokio::io::{AsyncReadExt as _, AsyncWriteExt as _};
+
+        let (line, mut markers) = self.start(command);
+        self.pty.write_all(&line).await?;
+        self.pty.flush().await?;
+        let mut data = [0_u8; 4096];
+        loop {
+            if let Some(output) = markers.process(&mut self.buf) {
+                return Ok(output);
+            }
+            match self.pty.read(&mut data).await? {
+                0 => return Err(unexpected_eof()),
+                bytes => self.buf.extend_from_slice(&data[..bytes]),
+            }
+        }
+    }
+}
+
+struct Markers {
+    begin: Vec<u8>,
+    end: Vec<u8>,
+    started: bool,
+}
+
+impl Markers {
+    fn process(&mut self, buf: &mut Vec<u8>) -> Option<ShellOutput> {
+        if !self.started {
+            let Some(line_end) = find(buf, &self.begin)
+                .and_then(|pos| find_newline(buf, pos + self.begin.len()))
+            else {
+                let keep = self.begin.len() + MAX_DISCARD_LOOKBEHIND;
+                buf.drain(..buf.len().saturating_sub(keep));
+                return None;
+            };
+            buf.drain(..=line_end);
+            self.started = true;
+        }
+
+        let pos = find(buf, &self.end)?;
+        let status_start = pos + self.end.len();
+        let line_end = find_newline(buf, status_start)?;
+        let status = std::str::from_utf8(&buf[status_start..line_end])
+            .ok()
+            .and_then(|status| status.trim_end_matches('\r').parse().ok())
+            .unwrap_or(-1);
+        let output = buf[..pos].to_vec();
+        buf.drain(..=line_end);
+        Some(ShellOutput { output, status })
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+fn find_newline(buf: &[u8], start: usize) -> Option<usize> {
+    buf.get(start..)?
+        .iter()
+        .position(|&byte| byte == b'\n')
+        .map(|pos| start + pos)
+}
+
+fn unexpected_eof() -> std::io::Error {
+    std::io::Error::new(
+        std::io::ErrorKind::UnexpectedEof,
+        "pty closed before the command finished",
+    )
+}
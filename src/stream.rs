@@ -0,0 +1,145 @@
+/// A [`Stream`](futures_core::Stream) of the lines of output read from a
+/// pty, returned by [`ReadPty::lines`](crate::ReadPty::lines) and
+/// [`OwnedReadPty::lines`](crate::OwnedReadPty::lines).
+///
+/// Each line is returned without its trailing newline (or `\r\n`). The
+/// stream ends once the child process exits (and nothing else has the pty
+/// open), or after the first error.
+#[derive(Debug)]
+pub struct Lines<R> {
+    inner: tokio::io::Lines<tokio::io::BufReader<PtyEof<R>>>,
+    done: bool,
+}
+
+impl<R: tokio::io::AsyncRead + Unpin> Lines<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        use tokio::io::AsyncBufReadExt as _;
+
+        Self {
+            inner: tokio::io::BufReader::new(PtyEof(inner)).lines(),
+            done: false,
+        }
+    }
+
+    /// Returns the wrapped reader. Any buffered data is discarded.
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner().into_inner().0
+    }
+}
+
+impl<R: tokio::io::AsyncRead + Unpin> futures_core::Stream for Lines<R> {
+    type Item = std::io::Result<String>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return std::task::Poll::Ready(None);
+        }
+        let line = std::task::ready!(
+            std::pin::Pin::new(&mut this.inner).poll_next_line(cx)
+        );
+        this.done = !matches!(line, Ok(Some(_)));
+        std::task::Poll::Ready(line.transpose())
+    }
+}
+
+/// A [`Stream`](futures_core::Stream) of chunks of output read from a pty,
+/// returned by [`OwnedReadPty::into_chunks`](crate::OwnedReadPty::into_chunks).
+///
+/// Each chunk contains whatever data was available from a single read, up
+/// to the configured capacity. The stream ends once the child process
+/// exits (and nothing else has the pty open), or after the first error.
+#[derive(Debug)]
+pub struct Chunks<R> {
+    inner: R,
+    capacity: usize,
+    buf: bytes::BytesMut,
+    done: bool,
+}
+
+impl<R> Chunks<R> {
+    pub(crate) fn new(inner: R, capacity: usize) -> Self {
+        Self {
+            inner,
+            capacity: capacity.max(1),
+            buf: bytes::BytesMut::new(),
+            done: false,
+        }
+    }
+
+    /// Returns the wrapped reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
+
+impl<R: tokio::io::AsyncRead + Unpin> futures_core::Stream for Chunks<R> {
+    type Item = std::io::Result<bytes::Bytes>;
+
+    fn poll_next(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if this.done {
+            return std::task::Poll::Ready(None);
+        }
+        // the buffer can reuse its allocation once the chunks split off
+        // from it have been dropped
+        this.buf.reserve(this.capacity);
+        let mut read_buf = tokio::io::ReadBuf::uninit(
+            &mut this.buf.spare_capacity_mut()[..this.capacity],
+        );
+        let res = std::task::ready!(
+            std::pin::Pin::new(&mut this.inner).poll_read(cx, &mut read_buf)
+        );
+        let len = read_buf.filled().len();
+        match res {
+            Ok(()) if len == 0 => {
+                this.done = true;
+                std::task::Poll::Ready(None)
+            }
+            Ok(()) => {
+                // Safety: poll_read initialized the first len bytes of the
+                // spare capacity
+                unsafe { this.buf.set_len(len) };
+                std::task::Poll::Ready(Some(Ok(this.buf.split().freeze())))
+            }
+            Err(e) if is_eof(&e) => {
+                this.done = true;
+                std::task::Poll::Ready(None)
+            }
+            Err(e) => {
+                this.done = true;
+                std::task::Poll::Ready(Some(Err(e)))
+            }
+        }
+    }
+}
+
+// reports the EIO which reading from a pty returns once the child has
+// exited as a normal end of file
+#[derive(Debug)]
+struct PtyEof<R>(R);
+
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for PtyEof<R> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        match std::task::ready!(
+            std::pin::Pin::new(&mut self.get_mut().0).poll_read(cx, buf)
+        ) {
+            Err(e) if is_eof(&e) => std::task::Poll::Ready(Ok(())),
+            res => std::task::Poll::Ready(res),
+        }
+    }
+}
+
+fn is_eof(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(rustix::io::Errno::IO.raw_os_error())
+}
@@ -0,0 +1,333 @@
+//! Adapters for reading the output of a pty as plain text.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! use std::io::BufRead as _;
+//!
+//! let (pty, pts) = pty_process::blocking::open()?;
+//! let mut child = pty_process::blocking::Command::new("cargo")
+//!     .arg("build")
+//!     .spawn(pts)?;
+//! let output = pty_process::strip::StripAnsi::new(&pty).overwrite(true);
+//! for line in std::io::BufReader::new(output).lines() {
+//!     println!("{}", line?);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+// cursor movement past this column is clamped, since the parameters come
+// from the child and would otherwise let it make us allocate arbitrarily
+// long lines
+const MAX_LINE: usize = 4096;
+
+/// A reader which removes escape sequences (such as colors, cursor
+/// movement, and window titles) from the data read from the wrapped
+/// reader.
+///
+/// This implements [`std::io::Read`] when the wrapped reader does (such as
+/// [`blocking::Pty`](crate::blocking::Pty)), and [`tokio::io::AsyncRead`]
+/// when the wrapped reader does (such as [`Pty`](crate::Pty),
+/// [`ReadPty`](crate::ReadPty), or [`OwnedReadPty`](crate::OwnedReadPty)).
+///
+/// Control characters other than newline, carriage return, tab, and
+/// backspace are also removed.
+pub struct StripAnsi<R> {
+    inner: R,
+    parser: crate::ansi::Parser,
+    line: Option<Line>,
+    readable: std::collections::VecDeque<u8>,
+    eof: bool,
+}
+
+impl<R> StripAnsi<R> {
+    /// Wraps the given reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            parser: crate::ansi::Parser::default(),
+            line: None,
+            readable: std::collections::VecDeque::new(),
+            eof: false,
+        }
+    }
+
+    /// If set, carriage returns, backspaces, and the escape sequences for
+    /// moving the cursor horizontally and erasing within a line are
+    /// applied to each line of output the way a terminal would, so that
+    /// only the final contents of the line are returned. For instance,
+    /// with this set, a progress bar which is redrawn in place will only
+    /// produce its final state. Defaults to false.
+    ///
+    /// Note that this requires buffering each line of output until the
+    /// newline at the end of it is read.
+    #[must_use]
+    pub fn overwrite(mut self, overwrite: bool) -> Self {
+        self.line = overwrite.then(Line::default);
+        self
+    }
+
+    /// Returns a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped reader. Any buffered data is discarded.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    fn process(&mut self, data: &[u8]) {
+        for &byte in data {
+            let Some(action) = self.parser.advance(byte) else {
+                continue;
+            };
+            if let Some(line) = &mut self.line {
+                line.apply(&action, &mut self.readable);
+            } else {
+                match action {
+                    crate::ansi::Action::Print(byte)
+                    | crate::ansi::Action::Control(
+                        byte @ (b'\n' | b'\r' | b'\t' | 0x08),
+                    ) => self.readable.push_back(byte),
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        if let Some(line) = &mut self.line {
+            line.flush(&mut self.readable);
+        }
+    }
+
+    fn take_readable(&mut self, buf: &mut [u8]) -> usize {
+        let len = buf.len().min(self.readable.len());
+        for (dst, src) in buf.iter_mut().zip(self.readable.drain(..len)) {
+            *dst = src;
+        }
+        len
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for StripAnsi<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let mut data = [0_u8; 4096];
+        loop {
+            if !self.readable.is_empty() {
+                return Ok(self.take_readable(buf));
+            }
+            if self.eof {
+                return Ok(0);
+            }
+            match self.inner.read(&mut data) {
+                Ok(0) => {
+                    self.eof = true;
+                    self.finish();
+                }
+                Ok(bytes) => self.process(&data[..bytes]),
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.finish();
+                    if self.readable.is_empty() {
+                        return Err(e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncRead + Unpin> tokio::io::AsyncRead for StripAnsi<R> {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut tokio::io::ReadBuf<'_>,
+    ) -> std::task::Poll<std::io::Result<()>> {
+        let this = self.get_mut();
+        let mut data = [0_u8; 4096];
+        loop {
+            if !this.readable.is_empty() {
+                let len = this.take_readable(buf.initialize_unfilled());
+                buf.advance(len);
+                return std::task::Poll::Ready(Ok(()));
+            }
+            if this.eof {
+                return std::task::Poll::Ready(Ok(()));
+            }
+            let mut read_buf = tokio::io::ReadBuf::new(&mut data);
+            match std::task::ready!(
+                std::pin::Pin::new(&mut this.inner)
+                    .poll_read(cx, &mut read_buf)
+            ) {
+                Ok(()) if read_buf.filled().is_empty() => {
+                    this.eof = true;
+                    this.finish();
+                }
+                Ok(()) => this.process(read_buf.filled()),
+                Err(e) => {
+                    this.finish();
+                    if this.readable.is_empty() {
+                        return std::task::Poll::Ready(Err(e));
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl<R: std::fmt::Debug> std::fmt::Debug for StripAnsi<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StripAnsi")
+            .field("inner", &self.inner)
+            .field("overwrite", &self.line.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+// a single character on the current line, stored as utf8
+#[derive(Clone, Copy)]
+struct Cell {
+    len: u8,
+    bytes: [u8; 4],
+}
+
+impl Cell {
+    const BLANK: Self = Self::new(b' ');
+
+    const fn new(byte: u8) -> Self {
+        Self {
+            len: 1,
+            bytes: [byte, 0, 0, 0],
+        }
+    }
+
+    fn append(&mut self, byte: u8) -> bool {
+        // only continuation bytes following a multibyte start byte can be
+        // appended
+        let max_len = match self.bytes[0] {
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => 1,
+        };
+        if !(0x80..=0xbf).contains(&byte) || usize::from(self.len) >= max_len
+        {
+            return false;
+        }
+        self.bytes[usize::from(self.len)] = byte;
+        self.len += 1;
+        true
+    }
+
+    fn as_bytes(&self) -> &[u8] {
+        &self.bytes[..usize::from(self.len)]
+    }
+}
+
+#[derive(Default)]
+struct Line {
+    cells: Vec<Cell>,
+    cursor: usize,
+}
+
+impl Line {
+    fn apply(
+        &mut self,
+        action: &crate::ansi::Action<'_>,
+        out: &mut std::collections::VecDeque<u8>,
+    ) {
+        match *action {
+            crate::ansi::Action::Print(byte)
+            | crate::ansi::Action::Control(byte @ b'\t') => self.print(byte),
+            crate::ansi::Action::Control(b'\n') => {
+                self.flush(out);
+                out.push_back(b'\n');
+            }
+            crate::ansi::Action::Control(b'\r') => self.cursor = 0,
+            crate::ansi::Action::Control(0x08) => {
+                self.cursor = self.cursor.saturating_sub(1);
+            }
+            crate::ansi::Action::Csi(seq) => self.csi(seq),
+            _ => {}
+        }
+    }
+
+    fn print(&mut self, byte: u8) {
+        if let Some(prev) = self.cursor.checked_sub(1)
+            && let Some(cell) = self.cells.get_mut(prev)
+            && cell.append(byte)
+        {
+            return;
+        }
+        if self.cursor < self.cells.len() {
+            self.cells[self.cursor] = Cell::new(byte);
+        } else {
+            self.cells.resize(self.cursor, Cell::BLANK);
+            self.cells.push(Cell::new(byte));
+        }
+        self.cursor += 1;
+    }
+
+    fn csi(&mut self, seq: &[u8]) {
+        let Some((&c, params)) = seq.split_last() else {
+            return;
+        };
+        // sequences with private parameters or intermediate bytes don't
+        // affect the contents of the line
+        if !params
+            .iter()
+            .all(|&byte| byte.is_ascii_digit() || byte == b';')
+        {
+            return;
+        }
+        let param = params
+            .split(|&byte| byte == b';')
+            .next()
+            .and_then(|param| std::str::from_utf8(param).ok())
+            .and_then(|param| param.parse::<usize>().ok());
+        match c {
+            b'K' => match param.unwrap_or(0) {
+                0 => self.cells.truncate(self.cursor),
+                1 => {
+                    let end = (self.cursor + 1).min(self.cells.len());
+                    self.cells[..end].fill(Cell::BLANK);
+                }
+                2 => self.cells.clear(),
+                _ => {}
+            },
+            b'C' => {
+                self.move_to(
+                    self.cursor.saturating_add(param.unwrap_or(1).max(1)),
+                );
+            }
+            b'D' => {
+                self.cursor =
+                    self.cursor.saturating_sub(param.unwrap_or(1).max(1));
+            }
+            b'G' => self.move_to(param.unwrap_or(1).saturating_sub(1)),
+            _ => {}
+        }
+    }
+
+    // printed text can make the line longer than MAX_LINE, but moving the
+    // cursor can't
+    fn move_to(&mut self, col: usize) {
+        self.cursor = col.min(self.cells.len().max(MAX_LINE));
+    }
+
+    fn flush(&mut self, out: &mut std::collections::VecDeque<u8>) {
+        for cell in &self.cells {
+            out.extend(cell.as_bytes());
+        }
+        self.cells.clear();
+        self.cursor = 0;
+    }
+}
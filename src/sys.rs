@@ -4,7 +4,12 @@ use std::os::{
 };
 
 #[derive(Debug)]
-pub struct Pty(std::os::fd::OwnedFd);
+pub struct Pty(
+    std::os::fd::OwnedFd,
+    // only initialized once mode tracking has been requested, so that
+    // reads don't pay for it otherwise
+    std::sync::OnceLock<std::sync::Mutex<crate::keys::ModeTracker>>,
+);
 
 impl Pty {
     pub fn open() -> crate::Result<Self> {
@@ -19,11 +24,11 @@ impl Pty {
         flags |= rustix::io::FdFlags::CLOEXEC;
         rustix::io::fcntl_setfd(&pt, flags)?;
 
-        Ok(Self(pt))
+        Ok(Self(pt, std::sync::OnceLock::new()))
     }
 
     pub unsafe fn from_fd(fd: std::os::fd::OwnedFd) -> Self {
-        Self(fd)
+        Self(fd, std::sync::OnceLock::new())
     }
 
     pub fn set_term_size(&self, size: crate::Size) -> crate::Result<()> {
@@ -46,7 +51,25 @@ impl Pty {
             .into()))
     }
 
-    #[cfg(feature = "async")]
+    pub fn signal_hangup(&self) {
+        // ioctls on the pty report the foreground process group and session
+        // of the pts
+        let targets = [
+            rustix::termios::tcgetpgrp(&self.0).ok(),
+            rustix::termios::tcgetsid(&self.0).ok(),
+        ];
+        for signal in
+            [rustix::process::Signal::HUP, rustix::process::Signal::CONT]
+        {
+            if let Some(pgrp) = targets[0] {
+                let _ = rustix::process::kill_process_group(pgrp, signal);
+            }
+            if let Some(sid) = targets[1] {
+                let _ = rustix::process::kill_process(sid, signal);
+            }
+        }
+    }
+
     pub fn set_nonblocking(&self) -> rustix::io::Result<()> {
         let mut opts = rustix::fs::fcntl_getfl(&self.0)?;
         opts |= rustix::fs::OFlags::NONBLOCK;
@@ -55,18 +78,167 @@ impl Pty {
         Ok(())
     }
 
+    pub fn set_blocking(&self) -> rustix::io::Result<()> {
+        let mut opts = rustix::fs::fcntl_getfl(&self.0)?;
+        opts.remove(rustix::fs::OFlags::NONBLOCK);
+        rustix::fs::fcntl_setfl(&self.0, opts)?;
+
+        Ok(())
+    }
+
+    pub fn echo_disabled(&self) -> crate::Result<bool> {
+        let termios = rustix::termios::tcgetattr(&self.0)?;
+        Ok(!termios
+            .local_modes
+            .contains(rustix::termios::LocalModes::ECHO))
+    }
+
+    // programs reading a password typically disable echo while still
+    // reading a whole line at a time
+    pub fn password_prompt(&self) -> crate::Result<bool> {
+        let termios = rustix::termios::tcgetattr(&self.0)?;
+        Ok(termios
+            .local_modes
+            .contains(rustix::termios::LocalModes::ICANON)
+            && !termios
+                .local_modes
+                .contains(rustix::termios::LocalModes::ECHO))
+    }
+
+    // the queue of input which the child hasn't read yet belongs to the
+    // pts, so operations on it have to go through a pts fd. this isn't kept
+    // open between calls, since the pty only reports that the child has
+    // gone away once every fd for the pts has been closed
+    fn peer(&self) -> crate::Result<Pts> {
+        // this doesn't depend on the pts being reachable by path in the
+        // current mount namespace, but requires linux 4.13
+        #[cfg(target_os = "linux")]
+        if let Ok(fd) = rustix::pty::ioctl_tiocgptpeer(
+            &self.0,
+            rustix::pty::OpenptFlags::RDWR
+                | rustix::pty::OpenptFlags::NOCTTY
+                | rustix::pty::OpenptFlags::CLOEXEC,
+        ) {
+            return Ok(Pts(fd));
+        }
+        self.pts()
+    }
+
+    pub fn discard(&self, queue: crate::Queue) -> crate::Result<()> {
+        if matches!(queue, crate::Queue::Input | crate::Queue::Both) {
+            rustix::termios::tcflush(
+                self.peer()?,
+                rustix::termios::QueueSelector::IFlush,
+            )?;
+        }
+        if matches!(queue, crate::Queue::Output | crate::Queue::Both) {
+            rustix::termios::tcflush(
+                &self.0,
+                rustix::termios::QueueSelector::IFlush,
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn set_output_suspended(&self, suspended: bool) -> crate::Result<()> {
+        rustix::termios::tcflow(
+            self.peer()?,
+            if suspended {
+                rustix::termios::Action::OOff
+            } else {
+                rustix::termios::Action::OOn
+            },
+        )?;
+        Ok(())
+    }
+
+    pub fn send_break(&self) -> crate::Result<()> {
+        rustix::termios::tcsendbreak(&self.0)?;
+        Ok(())
+    }
+
+    pub fn input_queue_len(&self) -> crate::Result<usize> {
+        queue_len(self.peer()?)
+    }
+
+    pub fn output_queue_len(&self) -> crate::Result<usize> {
+        // linux moves output from the pts straight into the pty's own input
+        // buffer, so TIOCOUTQ always reports 0 there
+        #[cfg(target_os = "linux")]
+        let len = rustix::io::ioctl_fionread(&self.0)?;
+        #[cfg(not(target_os = "linux"))]
+        let len = {
+            const TIOCOUTQ: rustix::ioctl::Opcode =
+                rustix::ioctl::opcode::read::<libc::c_int>(b't', 115);
+            // Safety: TIOCOUTQ writes a single int
+            unsafe {
+                rustix::ioctl::ioctl(
+                    &self.0,
+                    rustix::ioctl::Getter::<TIOCOUTQ, libc::c_int>::new(),
+                )
+            }?
+        };
+        Ok(usize::try_from(len).unwrap_or(usize::MAX))
+    }
+
+    // returns the pts, for waiting until its input queue is empty. the
+    // same fd is used for the whole wait.
+    pub fn start_drain(&self) -> crate::Result<Pts> {
+        let pts = self.peer()?;
+        // input is only queued on the pts once it has been transmitted
+        rustix::termios::tcdrain(&self.0)?;
+        Ok(pts)
+    }
+
     pub fn read_buf<'a>(
         &self,
         buf: &'a mut [std::mem::MaybeUninit<u8>],
     ) -> std::io::Result<(&'a mut [u8], &'a mut [std::mem::MaybeUninit<u8>])>
     {
-        rustix::io::read(&self.0, buf).map_err(std::io::Error::from)
+        let (filled, unfilled) = rustix::io::read(&self.0, buf)?;
+        if let Some(tracker) = self.1.get() {
+            track_modes(tracker, filled);
+        }
+        Ok((filled, unfilled))
+    }
+
+    pub fn track_modes(&self) {
+        self.1.get_or_init(std::sync::Mutex::default);
     }
+
+    pub fn modes(&self) -> crate::keys::Modes {
+        self.1
+            .get()
+            .map_or_else(crate::keys::Modes::default, |tracker| {
+                tracker
+                    .lock()
+                    .unwrap_or_else(std::sync::PoisonError::into_inner)
+                    .modes()
+            })
+    }
+
+    fn read(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let bytes = rustix::io::read(&self.0, &mut *buf)?;
+        if let Some(tracker) = self.1.get() {
+            track_modes(tracker, &buf[..bytes]);
+        }
+        Ok(bytes)
+    }
+}
+
+fn track_modes(
+    tracker: &std::sync::Mutex<crate::keys::ModeTracker>,
+    data: &[u8],
+) {
+    tracker
+        .lock()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .process(data);
 }
 
 impl From<Pty> for std::os::fd::OwnedFd {
     fn from(pty: Pty) -> Self {
-        let Pty(nix_ptymaster) = pty;
+        let Pty(nix_ptymaster, _) = pty;
         let raw_fd = nix_ptymaster.as_raw_fd();
         std::mem::forget(nix_ptymaster);
 
@@ -95,7 +267,7 @@ impl std::os::fd::AsRawFd for Pty {
 
 impl std::io::Read for Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        rustix::io::read(&self.0, buf).map_err(std::io::Error::from)
+        Self::read(self, buf)
     }
 }
 
@@ -111,7 +283,7 @@ impl std::io::Write for Pty {
 
 impl std::io::Read for &Pty {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
-        rustix::io::read(&self.0, buf).map_err(std::io::Error::from)
+        Pty::read(self, buf)
     }
 }
 
@@ -158,6 +330,24 @@ impl Pts {
             Ok(())
         }
     }
+
+    // the equivalent of setup_subprocess and session_leader for children
+    // which are forked directly rather than spawned through a Command, so
+    // this has to be async-signal-safe
+    pub fn setup_forked_child(
+        &self,
+    ) -> impl FnMut() -> std::io::Result<()> + use<> {
+        let mut session_leader = self.session_leader();
+        let pts_fd = self.0.as_raw_fd();
+        move || {
+            session_leader()?;
+            let pts = unsafe { std::os::fd::BorrowedFd::borrow_raw(pts_fd) };
+            rustix::stdio::dup2_stdin(pts)?;
+            rustix::stdio::dup2_stdout(pts)?;
+            rustix::stdio::dup2_stderr(pts)?;
+            Ok(())
+        }
+    }
 }
 
 impl From<Pts> for std::os::fd::OwnedFd {
@@ -177,3 +367,238 @@ impl std::os::fd::AsRawFd for Pts {
         self.0.as_raw_fd()
     }
 }
+
+// the child is a session leader, so its pid is also the id of the process
+// group containing everything it spawned (unless those processes created
+// process groups of their own)
+pub fn terminate_session(pid: rustix::process::Pid) {
+    for signal in [
+        rustix::process::Signal::HUP,
+        rustix::process::Signal::TERM,
+        rustix::process::Signal::CONT,
+    ] {
+        let _ = rustix::process::kill_process_group(pid, signal);
+    }
+}
+
+pub fn kill_session(pid: rustix::process::Pid) {
+    let _ = rustix::process::kill_process_group(
+        pid,
+        rustix::process::Signal::KILL,
+    );
+}
+
+// the session leader stays in the process group as a zombie until it is
+// reaped, so this can only return true after the leader has been reaped
+pub fn session_exited(pid: rustix::process::Pid) -> bool {
+    rustix::process::test_kill_process_group(pid).is_err()
+}
+
+// true if the given pid is a child of this process which hasn't been reaped
+// yet (whether or not it is still running)
+pub fn is_unreaped_child(pid: rustix::process::Pid) -> bool {
+    rustix::process::waitid(
+        rustix::process::WaitId::Pid(pid),
+        rustix::process::WaitIdOptions::EXITED
+            | rustix::process::WaitIdOptions::NOHANG
+            | rustix::process::WaitIdOptions::NOWAIT,
+    )
+    .is_ok()
+}
+
+pub fn queue_len(fd: impl std::os::fd::AsFd) -> crate::Result<usize> {
+    let len = rustix::io::ioctl_fionread(fd)?;
+    Ok(usize::try_from(len).unwrap_or(usize::MAX))
+}
+
+pub const PROMPT_POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(10);
+
+pub const DRAIN_POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(10);
+
+// waits for the given file descriptor to become readable, returning false
+// if the timeout elapses first
+pub fn wait_readable(
+    fd: impl std::os::fd::AsFd,
+    timeout: Option<std::time::Duration>,
+) -> rustix::io::Result<bool> {
+    let deadline = timeout.map(|timeout| std::time::Instant::now() + timeout);
+    loop {
+        let timeout = deadline
+            .map(|deadline| {
+                rustix::event::Timespec::try_from(
+                    deadline
+                        .saturating_duration_since(std::time::Instant::now()),
+                )
+            })
+            .transpose()
+            .map_err(|_| rustix::io::Errno::INVAL)?;
+        let mut fds = [rustix::event::PollFd::new(
+            &fd,
+            rustix::event::PollFlags::IN,
+        )];
+        match rustix::event::poll(&mut fds, timeout.as_ref()) {
+            Ok(ready) => return Ok(ready > 0),
+            Err(rustix::io::Errno::INTR) => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+pub const SESSION_POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(10);
+
+// leader is a file descriptor which becomes readable once the session
+// leader exits (such as a pidfd), so that it can be waited for directly.
+// other processes in the session can only be polled for.
+pub fn teardown_session(
+    pid: rustix::process::Pid,
+    grace_period: std::time::Duration,
+    leader: Option<std::os::fd::BorrowedFd<'_>>,
+    mut try_reap: impl FnMut() -> bool,
+) {
+    terminate_session(pid);
+    let deadline = std::time::Instant::now() + grace_period;
+    loop {
+        let reaped = try_reap();
+        if reaped && session_exited(pid) {
+            return;
+        }
+        let remaining =
+            deadline.saturating_duration_since(std::time::Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        match leader {
+            Some(leader) if !reaped => {
+                if wait_readable(leader, Some(remaining)).is_err() {
+                    std::thread::sleep(SESSION_POLL_INTERVAL.min(remaining));
+                }
+            }
+            _ => std::thread::sleep(SESSION_POLL_INTERVAL.min(remaining)),
+        }
+    }
+    kill_session(pid);
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ChildOptions {
+    pub rlimits: Vec<(crate::Resource, u64, u64)>,
+    pub nice: Option<i32>,
+    pub umask: Option<u32>,
+    pub groups: Option<Vec<u32>>,
+    pub gid: Option<u32>,
+    pub uid: Option<u32>,
+    // only set by prepare, when the working directory has to be changed
+    // again after dropping privileges
+    pub current_dir: Option<std::ffi::CString>,
+    #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+    pub namespaces: crate::namespaces::NamespaceOptions,
+}
+
+impl ChildOptions {
+    pub fn prepare(
+        &self,
+        pts: &Pts,
+        current_dir: Option<&std::path::Path>,
+    ) -> crate::Result<Self> {
+        let mut options = self.clone();
+        #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+        {
+            options.namespaces = self.namespaces.prepare(pts)?;
+        }
+        #[cfg(not(all(target_os = "linux", feature = "linux-namespaces")))]
+        let _ = pts;
+
+        // Command changes directory before running pre_exec, which is
+        // before we drop privileges, so do it again afterwards to make sure
+        // the new user is actually allowed in there. the path is resolved
+        // now since the child will already be in the new directory.
+        if options.uid.is_some()
+            && let Some(dir) = current_dir
+        {
+            let dir = std::path::absolute(dir)?;
+            options.current_dir = Some(
+                std::ffi::CString::new(
+                    std::os::unix::ffi::OsStringExt::into_vec(
+                        dir.into_os_string(),
+                    ),
+                )
+                .map_err(|_| {
+                    std::io::Error::from(std::io::ErrorKind::InvalidInput)
+                })?,
+            );
+        }
+
+        Ok(options)
+    }
+
+    // this runs in the child between fork and exec, so it must only call
+    // async-signal-safe functions (and in particular must not allocate)
+    pub fn apply(&self) -> std::io::Result<()> {
+        #[cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+        self.namespaces.apply()?;
+
+        for &(resource, soft, hard) in &self.rlimits {
+            rustix::process::setrlimit(
+                resource.into(),
+                rustix::process::Rlimit {
+                    current: Some(soft),
+                    maximum: Some(hard),
+                },
+            )?;
+        }
+        if let Some(nice) = self.nice {
+            rustix::process::setpriority_process(None, nice)?;
+        }
+        if let Some(umask) = self.umask {
+            // mode_t is smaller than u32 on some platforms
+            #[allow(clippy::useless_conversion)]
+            let umask = rustix::fs::RawMode::try_from(umask & 0o7777)
+                .unwrap_or_else(|_| unreachable!());
+            rustix::process::umask(rustix::fs::Mode::from_bits_truncate(
+                umask,
+            ));
+        }
+
+        // supplementary groups and the gid can only be changed while we are
+        // still privileged, so they have to be set before the uid
+        if let Some(groups) = &self.groups {
+            set_groups(groups)?;
+        } else if self.uid.is_some() && rustix::process::getuid().is_root() {
+            // match the behavior of std::process::Command by dropping any
+            // supplementary groups inherited from a privileged parent
+            set_groups(&[])?;
+        }
+        if let Some(gid) = self.gid
+            && unsafe { libc::setgid(gid) } != 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        if let Some(uid) = self.uid
+            && unsafe { libc::setuid(uid) } != 0
+        {
+            return Err(std::io::Error::last_os_error());
+        }
+        if let Some(dir) = &self.current_dir {
+            rustix::process::chdir(dir.as_c_str())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn set_groups(groups: &[u32]) -> std::io::Result<()> {
+    // the length argument to setgroups is an int on some platforms
+    #[allow(clippy::useless_conversion)]
+    let len = groups.len().try_into().map_err(|_| {
+        std::io::Error::from(std::io::ErrorKind::InvalidInput)
+    })?;
+    // Safety: the pointer and length come from a valid slice, and setgroups
+    // only reads from it
+    if unsafe { libc::setgroups(len, groups.as_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
@@ -0,0 +1,317 @@
+//! Adapters for reading the output of a pty as text.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! let (pty, pts) = pty_process::blocking::open()?;
+//! let mut child = pty_process::blocking::Command::new("ls").spawn(pts)?;
+//! let mut output = pty_process::text::Utf8Decoder::new(&pty)
+//!     .invalid(pty_process::text::InvalidUtf8::Replace);
+//! while let Some(line) = output.read_line()? {
+//!     print!("{line}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+/// What to do with bytes read from the pty which aren't valid UTF-8.
+///
+/// Since the decoded text is returned as [`String`]s, invalid bytes can
+/// never be passed through unchanged. [`Replace`](Self::Replace) is the
+/// lossy equivalent of passing them through, since it keeps a marker at
+/// each position where invalid data was read, while [`Skip`](Self::Skip)
+/// passes through only the valid text around them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InvalidUtf8 {
+    /// Replace each invalid sequence with `U+FFFD REPLACEMENT CHARACTER`,
+    /// the same way as [`String::from_utf8_lossy`].
+    #[default]
+    Replace,
+    /// Return an error of kind [`std::io::ErrorKind::InvalidData`]. The
+    /// invalid bytes are discarded, so reading can continue afterwards.
+    Error,
+    /// Silently discard invalid sequences.
+    Skip,
+}
+
+/// A reader which decodes the data read from the wrapped reader as UTF-8,
+/// returning it as strings.
+///
+/// Multibyte characters which are split across reads from the wrapped
+/// reader are held back until the rest of the character is read, so each
+/// returned string only ever contains whole characters.
+///
+/// This supports [`read_chunk`](Self::read_chunk) and
+/// [`read_line`](Self::read_line) when the wrapped reader implements
+/// [`std::io::Read`] (such as [`blocking::Pty`](crate::blocking::Pty)), and
+/// [`read_chunk_async`](Self::read_chunk_async) and
+/// [`read_line_async`](Self::read_line_async) when the wrapped reader
+/// implements [`tokio::io::AsyncRead`] (such as [`Pty`](crate::Pty),
+/// [`ReadPty`](crate::ReadPty), or [`OwnedReadPty`](crate::OwnedReadPty)).
+#[derive(Debug)]
+pub struct Utf8Decoder<R> {
+    inner: R,
+    invalid: InvalidUtf8,
+    bytes: Vec<u8>,
+    text: String,
+    // set when decoding stopped at an error before reaching the end of the
+    // buffered data
+    undecoded: bool,
+    error: Option<std::io::Error>,
+    eof: bool,
+}
+
+impl<R> Utf8Decoder<R> {
+    /// Wraps the given reader.
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            invalid: InvalidUtf8::default(),
+            bytes: vec![],
+            text: String::new(),
+            undecoded: false,
+            error: None,
+            eof: false,
+        }
+    }
+
+    /// Sets what to do with invalid UTF-8. Defaults to
+    /// [`InvalidUtf8::Replace`].
+    #[must_use]
+    pub fn invalid(mut self, invalid: InvalidUtf8) -> Self {
+        self.invalid = invalid;
+        self
+    }
+
+    /// Returns a reference to the wrapped reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped reader.
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped reader. Any buffered data is discarded.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    // decodes as much of the buffered data as possible, leaving any
+    // incomplete character at the end buffered
+    fn decode(&mut self) {
+        self.undecoded = false;
+        let mut consumed = 0;
+        while consumed < self.bytes.len() {
+            match std::str::from_utf8(&self.bytes[consumed..]) {
+                Ok(s) => {
+                    self.text.push_str(s);
+                    consumed = self.bytes.len();
+                }
+                Err(e) => {
+                    let valid = e.valid_up_to();
+                    self.text.push_str(
+                        std::str::from_utf8(
+                            &self.bytes[consumed..consumed + valid],
+                        )
+                        .unwrap_or_default(),
+                    );
+                    consumed += valid;
+                    let Some(len) = e.error_len() else {
+                        break;
+                    };
+                    consumed += len;
+                    if let Err(e) = self.invalid_sequence() {
+                        self.undecoded = true;
+                        self.error = Some(e);
+                        break;
+                    }
+                }
+            }
+        }
+        self.bytes.drain(..consumed);
+    }
+
+    fn finish(&mut self) {
+        self.eof = true;
+        if !self.bytes.is_empty() {
+            self.bytes.clear();
+            self.error = self.invalid_sequence().err();
+        }
+    }
+
+    fn invalid_sequence(&mut self) -> std::io::Result<()> {
+        match self.invalid {
+            InvalidUtf8::Replace => {
+                self.text.push(char::REPLACEMENT_CHARACTER);
+                Ok(())
+            }
+            InvalidUtf8::Error => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )),
+            InvalidUtf8::Skip => Ok(()),
+        }
+    }
+
+    fn take_chunk(&mut self) -> Option<String> {
+        (!self.text.is_empty()).then(|| std::mem::take(&mut self.text))
+    }
+
+    fn take_line(&mut self) -> Option<String> {
+        let end = self.text.find('\n')? + 1;
+        let rest = self.text.split_off(end);
+        Some(std::mem::replace(&mut self.text, rest))
+    }
+
+    fn process(&mut self, data: &[u8]) {
+        if data.is_empty() {
+            self.finish();
+        } else {
+            self.bytes.extend_from_slice(data);
+            self.decode();
+        }
+    }
+}
+
+impl<R: std::io::Read> Utf8Decoder<R> {
+    /// Reads the next chunk of text, returning `None` at the end of the
+    /// stream.
+    ///
+    /// # Errors
+    /// Returns an error if reading from the wrapped reader fails, or if
+    /// invalid UTF-8 is read and the policy is [`InvalidUtf8::Error`].
+    pub fn read_chunk(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            if let Some(chunk) = self.take_chunk() {
+                return Ok(Some(chunk));
+            }
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            self.fill()?;
+        }
+    }
+
+    /// Reads the next line of text, including the trailing newline,
+    /// returning `None` at the end of the stream. The last line may not
+    /// end with a newline.
+    ///
+    /// # Errors
+    /// Returns an error if reading from the wrapped reader fails, or if
+    /// invalid UTF-8 is read and the policy is [`InvalidUtf8::Error`].
+    pub fn read_line(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(Some(line));
+            }
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+            if self.eof {
+                return Ok(self.take_chunk());
+            }
+            self.fill()?;
+        }
+    }
+
+    fn fill(&mut self) -> std::io::Result<()> {
+        if self.undecoded {
+            self.decode();
+            return Ok(());
+        }
+        let mut data = [0_u8; 4096];
+        match self.inner.read(&mut data) {
+            Ok(bytes) => {
+                self.process(&data[..bytes]);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => Ok(()),
+            Err(e) if is_eof(&e) => {
+                self.finish();
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<R: tokio::io::AsyncRead + Unpin> Utf8Decoder<R> {
+    /// Reads the next chunk of text, returning `None` at the end of the
+    /// stream.
+    ///
+    /// # Errors
+    /// Returns an error if reading from the wrapped reader fails, or if
+    /// invalid UTF-8 is read and the policy is [`InvalidUtf8::Error`].
+    pub async fn read_chunk_async(
+        &mut self,
+    ) -> std::io::Result<Option<String>> {
+        loop {
+            if let Some(chunk) = self.take_chunk() {
+                return Ok(Some(chunk));
+            }
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+            if self.eof {
+                return Ok(None);
+            }
+            self.fill_async().await?;
+        }
+    }
+
+    /// Reads the next line of text, including the trailing newline,
+    /// returning `None` at the end of the stream. The last line may not
+    /// end with a newline.
+    ///
+    /// # Errors
+    /// Returns an error if reading from the wrapped reader fails, or if
+    /// invalid UTF-8 is read and the policy is [`InvalidUtf8::Error`].
+    pub async fn read_line_async(
+        &mut self,
+    ) -> std::io::Result<Option<String>> {
+        loop {
+            if let Some(line) = self.take_line() {
+                return Ok(Some(line));
+            }
+            if let Some(e) = self.error.take() {
+                return Err(e);
+            }
+            if self.eof {
+                return Ok(self.take_chunk());
+            }
+            self.fill_async().await?;
+        }
+    }
+
+    async fn fill_async(&mut self) -> std::io::Result<()> {
+        use tokio::io::AsyncReadExt as _;
+
+        if self.undecoded {
+            self.decode();
+            return Ok(());
+        }
+        let mut data = [0_u8; 4096];
+        match self.inner.read(&mut data).await {
+            Ok(bytes) => {
+                self.process(&data[..bytes]);
+                Ok(())
+            }
+            Err(e) if is_eof(&e) => {
+                self.finish();
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// reading from a pty fails with EIO rather than returning 0 once the child
+// (and anything else with the pts open) has exited
+fn is_eof(e: &std::io::Error) -> bool {
+    e.raw_os_error() == Some(rustix::io::Errno::IO.raw_os_error())
+}
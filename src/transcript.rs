@@ -0,0 +1,381 @@
+//! Timestamped records of the data written to and read from a pty.
+//!
+//! A [`Transcript`] collects every chunk of input and output passing
+//! through a [`Filtered`](crate::filter::Filtered) stream, along with the
+//! time (relative to when the transcript was created) at which it was seen.
+//! The transcript can then be queried, written out as JSON lines, or
+//! rendered as a human-readable log.
+//!
+//! ```no_run
+//! # fn main() -> pty_process::Result<()> {
+//! use std::io::Write as _;
+//!
+//! let (pty, pts) = pty_process::blocking::open()?;
+//! let mut child = pty_process::blocking::Command::new("sh").spawn(pts)?;
+//! let transcript = pty_process::transcript::Transcript::new();
+//! let mut pty = transcript.wrap(pty);
+//! pty.write_all(b"exit\n")?;
+//! child.wait()?;
+//! transcript.write_log(std::io::stdout())?;
+//! # Ok(())
+//! # }
+//! ```
+
+/// The direction in which data was sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Data written to the pty.
+    Input,
+    /// Data read from the pty.
+    Output,
+}
+
+/// The contents of an [`Entry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Event {
+    /// Data written to the pty.
+    Input(Vec<u8>),
+    /// Data read from the pty.
+    Output(Vec<u8>),
+    /// A secret of the given length written to the pty with
+    /// [`send_secret`](crate::filter::Filtered::send_secret). The secret
+    /// itself is never recorded.
+    RedactedInput(usize),
+    /// The pty was resized to the given number of rows and columns. These
+    /// are recorded when the pty is resized with
+    /// [`Filtered::resize`](crate::filter::Filtered::resize), and can also
+    /// be recorded by calling [`Transcript::record`] directly.
+    Resize {
+        /// The new number of rows.
+        rows: u16,
+        /// The new number of columns.
+        cols: u16,
+    },
+}
+
+/// A single chunk of data recorded in a [`Transcript`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The time at which the data was seen, relative to when the
+    /// transcript was created.
+    pub elapsed: std::time::Duration,
+    /// The data itself.
+    pub event: Event,
+}
+
+impl Entry {
+    /// Returns the direction in which the data was sent. Resizes count as
+    /// input, since they are sent to the pty.
+    #[must_use]
+    pub fn direction(&self) -> Direction {
+        match self.event {
+            Event::Input(_)
+            | Event::RedactedInput(_)
+            | Event::Resize { .. } => Direction::Input,
+            Event::Output(_) => Direction::Output,
+        }
+    }
+
+    /// Returns the recorded data, or an empty slice for redacted input and
+    /// resizes.
+    #[must_use]
+    pub fn data(&self) -> &[u8] {
+        match &self.event {
+            Event::Input(data) | Event::Output(data) => data,
+            Event::RedactedInput(_) | Event::Resize { .. } => &[],
+        }
+    }
+
+    /// Returns the entry as a single line of JSON (without the trailing
+    /// newline), in the format used by
+    /// [`Transcript::write_json_lines`].
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let time = self.elapsed.as_secs_f64();
+        match &self.event {
+            Event::Input(data) => format!(
+                r#"{{"time":{time:.6},"direction":"input","data":{}}}"#,
+                json_string(data)
+            ),
+            Event::Output(data) => format!(
+                r#"{{"time":{time:.6},"direction":"output","data":{}}}"#,
+                json_string(data)
+            ),
+            Event::RedactedInput(len) => format!(
+                r#"{{"time":{time:.6},"direction":"input","redacted":{len}}}"#
+            ),
+            Event::Resize { rows, cols } => format!(
+                r#"{{"time":{time:.6},"direction":"input","resize":[{rows},{cols}]}}"#
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Entry {
+    /// Formats the entry as a line of the log written by
+    /// [`Transcript::write_log`]: the elapsed time, `>` for input or `<`
+    /// for output, and the data with control characters and invalid utf8
+    /// escaped.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = self.elapsed.as_secs_f64();
+        match &self.event {
+            Event::Input(data) => {
+                write!(f, "[{time:12.6}] > {}", escape(data))
+            }
+            Event::Output(data) => {
+                write!(f, "[{time:12.6}] < {}", escape(data))
+            }
+            Event::RedactedInput(len) => {
+                write!(f, "[{time:12.6}] > <{len} bytes redacted>")
+            }
+            Event::Resize { rows, cols } => {
+                write!(f, "[{time:12.6}] > <resized to {rows}x{cols}>")
+            }
+        }
+    }
+}
+
+/// A timestamped record of the input and output of a pty.
+///
+/// A `Transcript` is a cheaply cloneable handle to a shared record, so it
+/// can be queried from one place while being filled from another (or from
+/// several streams at once). Entries are added by the
+/// [`Filter`](crate::filter::Filter) returned by [`filter`](Self::filter),
+/// which records whatever passes through its position in a
+/// [`Filtered`](crate::filter::Filtered) stream's chain of filters: if it
+/// is added first, input is recorded as it was written before any other
+/// filters modify it, and output is recorded as it is returned to the
+/// reader after all other filters have modified it.
+///
+/// Timestamps are monotonic, and so aren't affected by changes to the
+/// system clock.
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    inner: std::sync::Arc<std::sync::Mutex<Inner>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    started: std::time::Instant,
+    entries: Vec<Entry>,
+}
+
+impl Transcript {
+    /// Creates a new empty transcript. Timestamps are measured from this
+    /// point.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            inner: std::sync::Arc::new(std::sync::Mutex::new(Inner {
+                started: std::time::Instant::now(),
+                entries: vec![],
+            })),
+        }
+    }
+
+    /// Returns a [`Filter`](crate::filter::Filter) which records the data
+    /// passing through it into this transcript.
+    #[must_use]
+    pub fn filter(&self) -> impl crate::filter::Filter + use<> {
+        TranscriptFilter(self.clone())
+    }
+
+    /// Wraps the given stream (such as a [`Pty`](crate::Pty) or
+    /// [`blocking::Pty`](crate::blocking::Pty)) in a
+    /// [`Filtered`](crate::filter::Filtered) stream which records into
+    /// this transcript. Further filters can be added to the returned
+    /// stream, and will see the data after it has been recorded.
+    #[must_use]
+    pub fn wrap<T>(&self, inner: T) -> crate::filter::Filtered<T> {
+        crate::filter::Filtered::new(inner).filter(self.filter())
+    }
+
+    /// Adds an entry to the transcript, timestamped with the current time.
+    pub fn record(&self, event: Event) {
+        let mut inner = self.lock();
+        let elapsed = inner.started.elapsed();
+        inner.entries.push(Entry { elapsed, event });
+    }
+
+    /// Returns the time at which the transcript was created.
+    #[must_use]
+    pub fn started(&self) -> std::time::Instant {
+        self.lock().started
+    }
+
+    /// Returns a copy of all of the entries recorded so far.
+    #[must_use]
+    pub fn entries(&self) -> Vec<Entry> {
+        self.lock().entries.clone()
+    }
+
+    /// Returns a copy of the entries which were recorded within the given
+    /// range of times, relative to when the transcript was created.
+    #[must_use]
+    pub fn entries_between(
+        &self,
+        range: impl std::ops::RangeBounds<std::time::Duration>,
+    ) -> Vec<Entry> {
+        self.lock()
+            .entries
+            .iter()
+            .filter(|entry| range.contains(&entry.elapsed))
+            .cloned()
+            .collect()
+    }
+
+    /// Returns all of the data recorded in the given direction,
+    /// concatenated together. Redacted input is left out.
+    #[must_use]
+    pub fn data(&self, direction: Direction) -> Vec<u8> {
+        self.lock()
+            .entries
+            .iter()
+            .filter(|entry| entry.direction() == direction)
+            .flat_map(|entry| entry.data().iter().copied())
+            .collect()
+    }
+
+    /// Returns the number of entries recorded so far.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    /// Returns true if no entries have been recorded yet.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.lock().entries.is_empty()
+    }
+
+    /// Removes all of the entries recorded so far. Timestamps of later
+    /// entries are still measured from when the transcript was created.
+    pub fn clear(&self) {
+        self.lock().entries.clear();
+    }
+
+    /// Writes the transcript as JSON lines, with one object per entry.
+    /// Each object has a `time` field with the number of seconds since the
+    /// transcript was created, a `direction` field of either `"input"` or
+    /// `"output"`, and either a `data` field with the data as a string (with
+    /// any invalid utf8 replaced by U+FFFD), a `redacted` field with the
+    /// length of the secret for redacted input, or a `resize` field with
+    /// the new number of rows and columns for resizes.
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn write_json_lines(
+        &self,
+        mut w: impl std::io::Write,
+    ) -> std::io::Result<()> {
+        for entry in self.entries() {
+            writeln!(w, "{}", entry.to_json())?;
+        }
+        Ok(())
+    }
+
+    /// Writes the transcript as a human-readable log, with one line per
+    /// entry (see the [`Display`](std::fmt::Display) implementation for
+    /// [`Entry`]).
+    ///
+    /// # Errors
+    /// Returns an error if writing fails.
+    pub fn write_log(
+        &self,
+        mut w: impl std::io::Write,
+    ) -> std::io::Result<()> {
+        for entry in self.entries() {
+            writeln!(w, "{entry}")?;
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+}
+
+impl Default for Transcript {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+struct TranscriptFilter(Transcript);
+
+impl crate::filter::Filter for TranscriptFilter {
+    fn input(
+        &mut self,
+        data: &mut Vec<u8>,
+        _: &mut crate::filter::Context<'_>,
+    ) {
+        if !data.is_empty() {
+            self.0.record(Event::Input(data.clone()));
+        }
+    }
+
+    fn redacted_input(&mut self, len: usize) {
+        self.0.record(Event::RedactedInput(len));
+    }
+
+    fn resize(&mut self, size: crate::Size) {
+        let size = rustix::termios::Winsize::from(size);
+        self.0.record(Event::Resize {
+            rows: size.ws_row,
+            cols: size.ws_col,
+        });
+    }
+
+    fn output(
+        &mut self,
+        data: &mut Vec<u8>,
+        _: &mut crate::filter::Context<'_>,
+    ) {
+        if !data.is_empty() {
+            self.0.record(Event::Output(data.clone()));
+        }
+    }
+}
+
+fn json_string(data: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    let mut s = String::from("\"");
+    for c in String::from_utf8_lossy(data).chars() {
+        match c {
+            '"' => s.push_str("\\\""),
+            '\\' => s.push_str("\\\\"),
+            '\n' => s.push_str("\\n"),
+            '\r' => s.push_str("\\r"),
+            '\t' => s.push_str("\\t"),
+            c if c < ' ' || c == '\x7f' => {
+                let _ = write!(s, "\\u{:04x}", u32::from(c));
+            }
+            c => s.push(c),
+        }
+    }
+    s.push('"');
+    s
+}
+
+fn escape(data: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    let mut s = String::new();
+    for chunk in data.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            if c.is_control() {
+                s.extend(c.escape_default());
+            } else {
+                s.push(c);
+            }
+        }
+        for byte in chunk.invalid() {
+            let _ = write!(s, "\\x{byte:02x}");
+        }
+    }
+    s
+}
@@ -38,6 +38,17 @@ impl Size {
     }
 }
 
+impl From<rustix::termios::Winsize> for Size {
+    fn from(winsize: rustix::termios::Winsize) -> Self {
+        Self {
+            row: winsize.ws_row,
+            col: winsize.ws_col,
+            xpixel: winsize.ws_xpixel,
+            ypixel: winsize.ws_ypixel,
+        }
+    }
+}
+
 impl From<Size> for rustix::termios::Winsize {
     fn from(size: Size) -> Self {
         Self {
@@ -48,3 +59,46 @@ impl From<Size> for rustix::termios::Winsize {
         }
     }
 }
+
+/// Selects the data to discard with
+/// [`Pty::discard`](crate::blocking::Pty::discard).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Queue {
+    /// Input which has been written to the pty but not yet read by the
+    /// child.
+    Input,
+    /// Output which has been written by the child but not yet read from
+    /// the pty.
+    Output,
+    /// Both input and output.
+    Both,
+}
+
+/// A resource whose usage can be limited for a spawned child. See
+/// [`Command::rlimit`](crate::blocking::Command::rlimit).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Resource {
+    /// `RLIMIT_CPU`: the amount of CPU time the child may use, in seconds
+    Cpu,
+    /// `RLIMIT_AS`: the size of the child's virtual address space, in bytes
+    #[cfg(not(target_os = "openbsd"))]
+    AddressSpace,
+    /// `RLIMIT_NOFILE`: one more than the largest file descriptor number
+    /// the child may open
+    OpenFiles,
+    /// `RLIMIT_CORE`: the size of core files the child may create, in bytes
+    CoreSize,
+}
+
+impl From<Resource> for rustix::process::Resource {
+    fn from(resource: Resource) -> Self {
+        match resource {
+            Resource::Cpu => Self::Cpu,
+            #[cfg(not(target_os = "openbsd"))]
+            Resource::AddressSpace => Self::As,
+            Resource::OpenFiles => Self::Nofile,
+            Resource::CoreSize => Self::Core,
+        }
+    }
+}
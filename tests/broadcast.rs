@@ -0,0 +1,153 @@
+mod helpers;
+
+// waits for a line of input before printing each argument
+const SCRIPT: &str =
+    "$| = 1; for (@ARGV) { <STDIN>; print qq($_\\n) } <STDIN>";
+
+// the newlines written to trigger the output shouldn't also be echoed
+fn disable_echo(fd: impl std::os::fd::AsFd) {
+    let mut termios = nix::sys::termios::tcgetattr(&fd).unwrap();
+    termios
+        .local_flags
+        .remove(nix::sys::termios::LocalFlags::ECHO);
+    nix::sys::termios::tcsetattr(
+        &fd,
+        nix::sys::termios::SetArg::TCSANOW,
+        &termios,
+    )
+    .unwrap();
+}
+
+fn collect(
+    sub: &mut pty_process::broadcast::Subscriber,
+    expected: &[u8],
+) -> Vec<u8> {
+    let mut output = vec![];
+    while output.len() < expected.len() {
+        output.extend_from_slice(&sub.blocking_recv().unwrap().unwrap());
+    }
+    output
+}
+
+#[test]
+fn test_broadcast_blocking() {
+    use std::io::Write as _;
+    use std::os::fd::AsFd as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    disable_echo(&pty);
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-e", SCRIPT, "foo", "bar"])
+        .spawn(pts)
+        .unwrap();
+
+    let reader =
+        std::fs::File::from(pty.as_fd().try_clone_to_owned().unwrap());
+    let broadcast = pty_process::broadcast::PtyBroadcast::spawn_blocking(
+        reader,
+        pty_process::broadcast::Config::new().replay(3),
+    );
+    let mut early = broadcast.subscribe();
+
+    (&pty).write_all(b"\n").unwrap();
+    assert_eq!(collect(&mut early, b"foo\r\n"), b"foo\r\n");
+
+    // late subscribers get the configured amount of replay first
+    let mut late = broadcast.subscribe();
+    assert_eq!(collect(&mut late, b"o\r\n"), b"o\r\n");
+
+    (&pty).write_all(b"\n").unwrap();
+    assert_eq!(collect(&mut early, b"bar\r\n"), b"bar\r\n");
+    assert_eq!(collect(&mut late, b"bar\r\n"), b"bar\r\n");
+
+    (&pty).write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+
+    // subscribers end once the child exits
+    assert_eq!(early.blocking_recv(), None);
+    assert_eq!(late.blocking_iter().count(), 0);
+    assert!(broadcast.is_closed());
+}
+
+#[test]
+fn test_broadcast_lagged() {
+    use std::io::Write as _;
+    use std::os::fd::AsFd as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    disable_echo(&pty);
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-e", SCRIPT, "abcdefghij"])
+        .spawn(pts)
+        .unwrap();
+
+    let reader =
+        std::fs::File::from(pty.as_fd().try_clone_to_owned().unwrap());
+    let broadcast = pty_process::broadcast::PtyBroadcast::spawn_blocking(
+        reader,
+        pty_process::broadcast::Config::new().capacity(4),
+    );
+    let mut sub = broadcast.subscribe();
+
+    (&pty).write_all(b"\n").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    assert_eq!(
+        sub.blocking_recv().unwrap(),
+        Err(pty_process::broadcast::Lagged(8))
+    );
+    assert_eq!(sub.blocking_recv().unwrap().unwrap(), b"ij\r\n");
+
+    (&pty).write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_broadcast_backpressure_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    disable_echo(&pty);
+    let mut child = pty_process::Command::new("perl")
+        .args(["-e", SCRIPT, "abcdefghij"])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    let broadcast = pty_process::broadcast::PtyBroadcast::spawn(
+        pty_r,
+        pty_process::broadcast::Config::new()
+            .capacity(4)
+            .lag(pty_process::broadcast::Lag::Backpressure),
+    );
+    let mut fast = broadcast.subscribe();
+    let mut slow = broadcast.subscribe();
+
+    pty_w.write_all(b"\n").await.unwrap();
+    let mut fast_output = vec![];
+    let mut slow_output = vec![];
+    while slow_output.len() < 12 {
+        // the slow subscriber holds up the fast one, but nothing is lost
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+        let chunk = slow.next().await.unwrap().unwrap();
+        assert!(chunk.len() <= 4);
+        slow_output.extend_from_slice(&chunk);
+        while fast_output.len() < slow_output.len() {
+            fast_output
+                .extend_from_slice(&fast.next().await.unwrap().unwrap());
+        }
+    }
+    assert_eq!(slow_output, b"abcdefghij\r\n");
+    assert_eq!(fast_output, b"abcdefghij\r\n");
+
+    pty_w.write_all(b"\n").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+    assert!(slow.next().await.is_none());
+}
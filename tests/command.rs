@@ -0,0 +1,76 @@
+mod helpers;
+
+#[test]
+fn test_from_std_command() {
+    use std::io::Write as _;
+
+    let mut std_cmd = std::process::Command::new("sh");
+    std_cmd
+        .args(["-c", "echo \"$FOO\"; pwd; read x"])
+        .env("FOO", "bar")
+        .current_dir("/");
+    let cmd = pty_process::blocking::Command::from(std_cmd);
+
+    assert_eq!(cmd.get_program(), "sh");
+    assert_eq!(
+        cmd.get_args().collect::<Vec<_>>(),
+        ["-c", "echo \"$FOO\"; pwd; read x"]
+    );
+    assert_eq!(
+        cmd.get_envs().collect::<Vec<_>>(),
+        [(
+            std::ffi::OsStr::new("FOO"),
+            Some(std::ffi::OsStr::new("bar"))
+        )]
+    );
+    assert_eq!(cmd.get_current_dir(), Some(std::path::Path::new("/")));
+    assert_eq!(cmd.as_std().get_program(), "sh");
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = cmd.spawn(pts).unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "bar\r\n");
+    assert_eq!(output.next().unwrap(), "/\r\n");
+
+    (&pty).write_all(b"\n").unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_from_tokio_command() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let mut tokio_cmd = tokio::process::Command::new("sh");
+    tokio_cmd
+        .args(["-c", "echo \"$FOO\"; pwd; read x"])
+        .env("FOO", "bar")
+        .current_dir("/");
+    let cmd = pty_process::Command::from(tokio_cmd);
+
+    assert_eq!(cmd.get_program(), "sh");
+    assert_eq!(
+        cmd.get_args().collect::<Vec<_>>(),
+        ["-c", "echo \"$FOO\"; pwd; read x"]
+    );
+    assert_eq!(cmd.get_current_dir(), Some(std::path::Path::new("/")));
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = cmd.spawn(pts).unwrap();
+
+    let (pty_r, mut pty_w) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "bar\r\n");
+    assert_eq!(output.next().await.unwrap(), "/\r\n");
+
+    pty_w.write_all(b"\n").await.unwrap();
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
@@ -0,0 +1,112 @@
+mod helpers;
+
+struct Highlight;
+
+impl pty_process::filter::Filter for Highlight {
+    fn output(
+        &mut self,
+        data: &mut Vec<u8>,
+        cx: &mut pty_process::filter::Context<'_>,
+    ) {
+        let re = regex::bytes::Regex::new("\x1b\\[31m").unwrap();
+        if re.is_match(data) {
+            *data = re.replace_all(data, &b"<red>"[..]).into_owned();
+            cx.reply(b"ok\n");
+        }
+    }
+}
+
+#[test]
+fn test_filter_blocking() {
+    use std::io::{BufRead as _, Write as _};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .arg(
+            "-E$| = 1; system 'stty -echo'; print qq(a\\e[3); sleep 1; \
+            say qq(1mb); my $l = <STDIN>; print qq(got $l); <STDIN>",
+        )
+        .spawn(pts)
+        .unwrap();
+
+    let pty = pty_process::filter::Filtered::new(pty).filter(Highlight);
+    let mut pty = std::io::BufReader::new(pty);
+    let mut line = vec![];
+    pty.read_until(b'\n', &mut line).unwrap();
+    assert_eq!(line, b"a<red>b\r\n");
+    line.clear();
+    pty.read_until(b'\n', &mut line).unwrap();
+    assert_eq!(line, b"got ok\r\n");
+    pty.get_mut().write_all(b"\n").unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_filter_order_blocking() {
+    use std::io::{BufRead as _, Write as _};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(pts)
+        .unwrap();
+
+    let mut pty = std::io::BufReader::new(
+        pty_process::filter::Filtered::new(pty)
+            .filter(pty_process::filter::map_input(|data| {
+                data.retain(|&byte| byte != b'x');
+            }))
+            .filter(pty_process::filter::map_input(|data| {
+                *data = data.repeat(2);
+            }))
+            .filter(pty_process::filter::map_output(|data| {
+                data.make_ascii_uppercase();
+            }))
+            .filter(pty_process::filter::map_output(|data| {
+                data.retain(|&byte| byte != b'f');
+            })),
+    );
+    pty.get_mut().write_all(b"fxoo\n").unwrap();
+    // the output filters are applied in reverse order, so the f is removed
+    // before it can be uppercased
+    for _ in 0..4 {
+        let mut line = vec![];
+        pty.read_until(b'\n', &mut line).unwrap();
+        assert_eq!(line, b"OO\r\n");
+    }
+
+    pty.get_mut().write_all(&[4]).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_filter_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .arg(
+            "-E$| = 1; system 'stty -echo'; print qq(a\\e[3); sleep 1; \
+            say qq(1mb); my $l = <STDIN>; print uc qq(got $l); \
+            <STDIN>",
+        )
+        .spawn(pts)
+        .unwrap();
+
+    let pty = pty_process::filter::Filtered::new(pty).filter(Highlight);
+    let (pty_r, mut pty_w) = tokio::io::split(pty);
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "a<red>b\r\n");
+    assert_eq!(output.next().await.unwrap(), "GOT OK\r\n");
+    pty_w.write_all(b"\n").await.unwrap();
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
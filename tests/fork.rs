@@ -0,0 +1,66 @@
+mod helpers;
+
+#[test]
+fn test_fork_with_pty() {
+    let (mut child, pty) = unsafe {
+        pty_process::blocking::fork_with_pty(
+            pty_process::Size::new(24, 80),
+            || {
+                let tty = nix::fcntl::open(
+                    "/dev/tty",
+                    nix::fcntl::OFlag::O_RDWR,
+                    nix::sys::stat::Mode::empty(),
+                );
+                let msg: &[u8] =
+                    if tty.is_ok() { b"true\n" } else { b"false\n" };
+                let _ = nix::unistd::write(std::io::stdout(), msg);
+                23
+            },
+        )
+    }
+    .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "true\r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 23);
+}
+
+#[test]
+fn test_fork_with_pty_size() {
+    let (mut child, pty) = unsafe {
+        pty_process::blocking::fork_with_pty(
+            pty_process::Size::new(25, 81),
+            || {
+                let mut size = nix::libc::winsize {
+                    ws_row: 0,
+                    ws_col: 0,
+                    ws_xpixel: 0,
+                    ws_ypixel: 0,
+                };
+                let ret =
+                    nix::libc::ioctl(0, nix::libc::TIOCGWINSZ, &mut size);
+                if ret != 0 {
+                    return 1;
+                }
+                let _ = nix::unistd::write(
+                    std::io::stdout(),
+                    if size.ws_row == 25 && size.ws_col == 81 {
+                        b"ok\n"
+                    } else {
+                        b"no\n"
+                    },
+                );
+                0
+            },
+        )
+    }
+    .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ok\r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
@@ -0,0 +1,104 @@
+mod helpers;
+
+#[test]
+fn test_hangup_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{HUP} = sub { exit 3 }; say 'ready'; sleep 1000",
+        ])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ready\r\n");
+    drop(output);
+
+    pty.hangup();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 3);
+}
+
+#[test]
+fn test_shutdown_blocking() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{HUP} = 'IGNORE'; say 'ready'; sleep 1000",
+        ])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ready\r\n");
+    drop(output);
+
+    let status = child
+        .shutdown(pty, std::time::Duration::from_millis(100))
+        .unwrap();
+    assert_eq!(status.signal().unwrap(), 15);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_shutdown_async() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{HUP} = sub { exit 3 }; say 'ready'; sleep 1000",
+        ])
+        .spawn(pts)
+        .unwrap();
+
+    {
+        let (pty_r, _) = pty.split();
+        let mut output = helpers::output_async(pty_r);
+        assert_eq!(output.next().await.unwrap(), "ready\r\n");
+    }
+
+    let status = child
+        .shutdown(pty, std::time::Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(status.code().unwrap(), 3);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_shutdown_escalates_async() {
+    use futures::stream::StreamExt as _;
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{HUP} = $SIG{TERM} = 'IGNORE'; \
+            say 'ready'; sleep 1000",
+        ])
+        .spawn(pts)
+        .unwrap();
+
+    {
+        let (pty_r, _) = pty.split();
+        let mut output = helpers::output_async(pty_r);
+        assert_eq!(output.next().await.unwrap(), "ready\r\n");
+    }
+
+    let status = child
+        .shutdown(pty, std::time::Duration::from_millis(100))
+        .await
+        .unwrap();
+    assert_eq!(status.signal().unwrap(), 9);
+}
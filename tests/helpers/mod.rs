@@ -8,6 +8,8 @@ pub struct Output<'a> {
 
 impl<'a> Output<'a> {
     fn new(pty: &'a pty_process::blocking::Pty) -> Self {
+        pty.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+            .unwrap();
         Self {
             pty: std::io::BufReader::new(pty),
         }
@@ -19,9 +21,7 @@ impl Iterator for Output<'_> {
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut buf = vec![];
-        nix::unistd::alarm::set(5);
         self.pty.read_until(b'\n', &mut buf).unwrap();
-        nix::unistd::alarm::cancel();
         Some(std::string::String::from_utf8(buf).unwrap())
     }
 }
@@ -0,0 +1,118 @@
+mod helpers;
+
+// prints the given escape sequences to set terminal modes, and then prints
+// the bytes it reads (in hex) once it sees a q
+fn hexdump(modes: &str) -> String {
+    format!(
+        "system 'stty raw -echo'; $| = 1; print \"{modes}ready\\r\\n\"; \
+        while (sysread STDIN, my $c, 1) {{ \
+            last if $c eq 'q'; printf '%02x ', ord $c \
+        }} \
+        print \"\\r\\n\"; system 'stty sane'"
+    )
+}
+
+#[test]
+fn test_send_key_modes_blocking() {
+    use pty_process::keys::{Key, Modifiers};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.track_modes();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-E", &hexdump("\\e[?1;2004h\\e=")])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert!(output.next().unwrap().ends_with("ready\r\n"));
+    let modes = pty.modes();
+    assert!(modes.application_cursor());
+    assert!(modes.application_keypad());
+    assert!(modes.bracketed_paste());
+
+    pty.send_key(Key::Up).unwrap();
+    pty.send_key(Key::Keypad('5')).unwrap();
+    pty.send_key(Key::Char('c').with(Modifiers::CTRL)).unwrap();
+    pty.send_key(Key::Char('x').with(Modifiers::ALT)).unwrap();
+    pty.send_paste(b"a\x1b[201~b").unwrap();
+    pty.send_key(Key::Char('q')).unwrap();
+    assert_eq!(
+        output.next().unwrap(),
+        "1b 4f 41 1b 4f 75 03 1b 78 1b 5b 32 30 30 7e 61 62 1b 5b 32 30 31 \
+        7e \r\n"
+    );
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_modes_untracked_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-E", &hexdump("\\e[?1;2004h\\e=")])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert!(output.next().unwrap().ends_with("ready\r\n"));
+    assert_eq!(pty.modes(), pty_process::keys::Modes::default());
+
+    pty.send_key(pty_process::keys::Key::Up).unwrap();
+    pty.send_key(pty_process::keys::Key::Char('q')).unwrap();
+    assert_eq!(output.next().unwrap(), "1b 5b 41 \r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_send_key_async() {
+    use futures::stream::StreamExt as _;
+    use pty_process::keys::{
+        Key, Modifiers, MouseButton, MouseEvent, MouseEventKind,
+    };
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.track_modes();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-E", &hexdump("\\e[?1h\\e=\\e[?1l\\e>")])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert!(output.next().await.unwrap().ends_with("ready\r\n"));
+    drop(output);
+    assert_eq!(pty.modes(), pty_process::keys::Modes::default());
+
+    pty.send_key(Key::Up).await.unwrap();
+    pty.send_key(Key::Left.with(Modifiers::CTRL | Modifiers::SHIFT))
+        .await
+        .unwrap();
+    pty.send_key(Key::F(5)).await.unwrap();
+    pty.send_key(Key::Tab.with(Modifiers::SHIFT)).await.unwrap();
+    pty.send_paste(b"p").await.unwrap();
+    pty.send_mouse(
+        MouseEvent::new(MouseEventKind::Release(MouseButton::Right), 1, 2)
+            .modifiers(Modifiers::CTRL),
+    )
+    .await
+    .unwrap();
+    pty.send_key(Key::Char('q')).await.unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(
+        output.next().await.unwrap(),
+        "1b 5b 41 1b 5b 31 3b 36 44 1b 5b 31 35 7e 1b 5b 5a 70 1b 5b 3c 31 \
+        38 3b 33 3b 32 6d \r\n"
+    );
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
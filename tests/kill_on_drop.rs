@@ -0,0 +1,182 @@
+mod helpers;
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_kill_on_drop_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "sleep 1000 & echo $!; wait"])
+        .kill_on_drop(true)
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    let grandchild: i32 = output.next().unwrap().trim().parse().unwrap();
+    assert!(is_running(grandchild));
+
+    drop(child);
+    wait_for_exit(grandchild);
+}
+
+#[test]
+fn test_kill_session_grace_period_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{TERM} = sub { say 'TERM'; exit 7 }; \
+            $SIG{HUP} = 'IGNORE'; say 'ready'; sleep 1000",
+        ])
+        .kill_grace_period(std::time::Duration::from_secs(5))
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ready\r\n");
+
+    let status = child.kill_session().unwrap();
+    assert_eq!(status.code().unwrap(), 7);
+    assert_eq!(output.next().unwrap(), "TERM\r\n");
+}
+
+#[test]
+fn test_kill_session_escalates_blocking() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{TERM} = $SIG{HUP} = 'IGNORE'; \
+            say 'ready'; sleep 1000",
+        ])
+        .kill_grace_period(std::time::Duration::from_millis(100))
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ready\r\n");
+
+    let status = child.kill_session().unwrap();
+    assert_eq!(status.signal().unwrap(), 9);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_kill_session_after_exit_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "trap '' HUP; sleep 1000 & echo $!"])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    let grandchild: i32 = output.next().unwrap().trim().parse().unwrap();
+    assert!(child.wait().unwrap().success());
+    assert!(is_running(grandchild));
+
+    // the child has already exited, but the rest of its session hasn't
+    assert!(child.kill_session().unwrap().success());
+    wait_for_exit(grandchild);
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_kill_on_drop_async() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let child = pty_process::Command::new("sh")
+        .args(["-c", "sleep 1000 & echo $!; wait"])
+        .kill_on_drop(true)
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    let grandchild: i32 =
+        output.next().await.unwrap().trim().parse().unwrap();
+    assert!(is_running(grandchild));
+
+    drop(child);
+    wait_for_exit(grandchild);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_kill_session_grace_period_async() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args([
+            "-E",
+            "$| = 1; $SIG{TERM} = sub { say 'TERM'; exit 7 }; \
+            $SIG{HUP} = 'IGNORE'; say 'ready'; sleep 1000",
+        ])
+        .kill_grace_period(std::time::Duration::from_secs(5))
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "ready\r\n");
+
+    let status = child.kill_session().await.unwrap();
+    assert_eq!(status.code().unwrap(), 7);
+    assert_eq!(output.next().await.unwrap(), "TERM\r\n");
+}
+
+#[cfg(target_os = "linux")]
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_kill_session_after_exit_async() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("sh")
+        .args(["-c", "trap '' HUP; sleep 1000 & echo $!"])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    let grandchild: i32 =
+        output.next().await.unwrap().trim().parse().unwrap();
+    assert!(child.wait().await.unwrap().success());
+    assert!(is_running(grandchild));
+
+    // the child has already exited, but the rest of its session hasn't
+    assert!(child.kill_session().await.unwrap().success());
+    wait_for_exit(grandchild);
+}
+
+#[cfg(target_os = "linux")]
+fn is_running(pid: i32) -> bool {
+    // the grandchild is reparented when its parent is killed, and the new
+    // parent may not reap it immediately, so zombies count as exited
+    std::fs::read_to_string(format!("/proc/{pid}/stat")).is_ok_and(|stat| {
+        stat.rsplit_once(") ")
+            .is_some_and(|(_, rest)| !rest.starts_with('Z'))
+    })
+}
+
+#[cfg(target_os = "linux")]
+#[track_caller]
+fn wait_for_exit(pid: i32) {
+    for _ in 0..500 {
+        if !is_running(pid) {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("process {pid} is still running");
+}
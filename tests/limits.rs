@@ -0,0 +1,127 @@
+mod helpers;
+
+#[test]
+fn test_limits_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "ulimit -n; ulimit -c; ulimit -t; umask; nice"])
+        .rlimit(pty_process::Resource::OpenFiles, 64, 128)
+        .rlimit(pty_process::Resource::CoreSize, 0, 0)
+        .rlimit(pty_process::Resource::Cpu, 100, 200)
+        .umask(0o027)
+        .nice(5)
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "64\r\n");
+    assert_eq!(output.next().unwrap(), "0\r\n");
+    assert_eq!(output.next().unwrap(), "100\r\n");
+    assert_eq!(output.next().unwrap(), "0027\r\n");
+    assert_eq!(output.next().unwrap(), "5\r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_limits_with_pre_exec_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let cmd = pty_process::blocking::Command::new("sh")
+        .args(["-c", "ulimit -n"])
+        .rlimit(pty_process::Resource::OpenFiles, 64, 128);
+    // limits are applied before the custom pre_exec closure runs
+    let cmd = unsafe {
+        cmd.pre_exec(|| {
+            let (soft, _) = nix::sys::resource::getrlimit(
+                nix::sys::resource::Resource::RLIMIT_NOFILE,
+            )?;
+            if soft == 64 {
+                Ok(())
+            } else {
+                Err(std::io::Error::other("limit not applied"))
+            }
+        })
+    };
+    let mut child = cmd.spawn(pts).unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "64\r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_limits_error_blocking() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let err = pty_process::blocking::Command::new("true")
+        .rlimit(pty_process::Resource::OpenFiles, 128, 64)
+        .spawn(pts)
+        .unwrap_err();
+    assert!(matches!(err, pty_process::Error::Io(_)));
+}
+
+#[test]
+fn test_uid_current_dir_blocking() {
+    if !nix::unistd::geteuid().is_root() {
+        return;
+    }
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("pwd")
+        .current_dir("/tmp")
+        .uid(65534)
+        .gid(65534)
+        .spawn(pts)
+        .unwrap();
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "/tmp\r\n");
+    assert!(child.wait().unwrap().success());
+
+    // the directory is checked with the new user's permissions, not ours
+    let dir = std::env::temp_dir()
+        .join(format!("pty-process-test-{}", std::process::id()));
+    std::fs::create_dir(&dir).unwrap();
+    std::fs::set_permissions(
+        &dir,
+        std::os::unix::fs::PermissionsExt::from_mode(0o700),
+    )
+    .unwrap();
+    let (_pty, pts) = pty_process::blocking::open().unwrap();
+    let err = pty_process::blocking::Command::new("pwd")
+        .current_dir(&dir)
+        .uid(65534)
+        .gid(65534)
+        .spawn(pts)
+        .unwrap_err();
+    std::fs::remove_dir(&dir).unwrap();
+    assert!(matches!(err, pty_process::Error::Io(_)));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_limits_async() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("sh")
+        .args(["-c", "ulimit -n; umask"])
+        .rlimit(pty_process::Resource::OpenFiles, 64, 128)
+        .umask(0o077)
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "64\r\n");
+    assert_eq!(output.next().await.unwrap(), "0077\r\n");
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
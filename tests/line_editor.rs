@@ -0,0 +1,211 @@
+fn next_line(pty: &mut impl std::io::BufRead) -> String {
+    let mut line = vec![];
+    pty.read_until(b'\n', &mut line).unwrap();
+    String::from_utf8(line).unwrap()
+}
+
+fn erase(len: usize) -> String {
+    "\x08 \x08".repeat(len)
+}
+
+#[test]
+fn test_line_editor() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(pts)
+        .unwrap();
+
+    let editor = pty_process::filter::LineEditor::new(&pty).unwrap();
+    let mut pty = std::io::BufReader::new(
+        pty_process::filter::Filtered::new(pty).filter(editor),
+    );
+
+    // the edits are echoed locally and the echo from the pty is removed,
+    // so each line only appears once before the output from cat
+    pty.get_mut().write_all(b"hellp\x7fo\r").unwrap();
+    assert_eq!(next_line(&mut pty), format!("hellp{}o\r\n", erase(1)));
+    assert_eq!(next_line(&mut pty), "hello\r\n");
+
+    pty.get_mut().write_all(b"foo bar\x17baz\r").unwrap();
+    assert_eq!(next_line(&mut pty), format!("foo bar{}baz\r\n", erase(3)));
+    assert_eq!(next_line(&mut pty), "foo baz\r\n");
+
+    pty.get_mut()
+        .write_all(b"junk\x15\x1b[A\x1b[A\x1b[B\r")
+        .unwrap();
+    assert_eq!(
+        next_line(&mut pty),
+        format!(
+            "junk{}foo baz{}hello{}foo baz\r\n",
+            erase(4),
+            erase(7),
+            erase(5)
+        )
+    );
+    assert_eq!(next_line(&mut pty), "foo baz\r\n");
+
+    // other escape sequences are sent on to the pty along with the line so
+    // far, and the echo of the rest of the line is still removed after the
+    // pty's echo of the sequence
+    pty.get_mut().write_all(b"ab\x1b[Dc\r").unwrap();
+    assert_eq!(next_line(&mut pty), "abc\r\n");
+    assert_eq!(next_line(&mut pty), "^[[Dab\x1b[Dc\r\n");
+
+    pty.get_mut().write_all(b"\x04").unwrap();
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_line_editor_disabled() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .arg(
+            "-E$| = 1; my $l = <STDIN>; system 'stty -icanon -echo'; \
+             say 'ready'; $l = <STDIN>; print qq(got $l)",
+        )
+        .spawn(pts)
+        .unwrap();
+
+    let editor = pty_process::filter::LineEditor::new(&pty).unwrap();
+    let mut pty = std::io::BufReader::new(
+        pty_process::filter::Filtered::new(pty).filter(editor),
+    );
+    pty.get_mut().write_all(b"x\x7fy\n").unwrap();
+    assert_eq!(next_line(&mut pty), format!("x{}y\r\n", erase(1)));
+    // the terminal settings are checked again after the child writes
+    // something
+    assert_eq!(next_line(&mut pty), "ready\r\n");
+
+    // the erase character is passed through to the child rather than
+    // being handled locally
+    pty.get_mut().write_all(b"ab\x7fc\n").unwrap();
+    assert_eq!(next_line(&mut pty), "got ab\x7fc\r\n");
+    assert!(child.wait().unwrap().success());
+}
+
+// a stream which returns the given chunks of output, to control exactly
+// how the echo is split across reads
+struct Scripted(std::collections::VecDeque<&'static [u8]>);
+
+impl std::io::Read for Scripted {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let Some(chunk) = self.0.pop_front() else {
+            return Ok(0);
+        };
+        buf[..chunk.len()].copy_from_slice(chunk);
+        Ok(chunk.len())
+    }
+}
+
+impl std::io::Write for Scripted {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn scripted_output(input: &[u8], chunks: &[&'static [u8]]) -> String {
+    use std::io::{Read as _, Write as _};
+
+    // only used for its terminal settings
+    let (pty, _pts) = pty_process::blocking::open().unwrap();
+    let editor = pty_process::filter::LineEditor::new(&pty).unwrap();
+    let mut stream = pty_process::filter::Filtered::new(Scripted(
+        chunks.iter().copied().collect(),
+    ))
+    .filter(editor);
+    stream.write_all(input).unwrap();
+    let mut output = String::new();
+    stream.read_to_string(&mut output).unwrap();
+    output
+}
+
+#[test]
+fn test_line_editor_echo_sync() {
+    // a partial echo at the end of a read which isn't continued is passed
+    // through
+    assert_eq!(
+        scripted_output(b"ls\r", &[b"l", b"x\r\n", b"ls\r\nfoo\r\n"]),
+        "ls\r\nlx\r\nfoo\r\n"
+    );
+    // an echo which never arrives doesn't stop later echoes from being
+    // removed
+    assert_eq!(
+        scripted_output(b"a\rb\r", &[b"out\r\n", b"b\r\nmore\r\n"]),
+        "a\r\nb\r\nout\r\nmore\r\n"
+    );
+}
+
+#[test]
+fn test_line_editor_echo_disabled_silently() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .arg(
+            "-E$| = 1; my $l = <STDIN>; system 'stty -echo'; \
+             $l = <STDIN>; print qq(got $l)",
+        )
+        .spawn(pts)
+        .unwrap();
+
+    let editor = pty_process::filter::LineEditor::new(&pty).unwrap();
+    let mut pty = std::io::BufReader::new(
+        pty_process::filter::Filtered::new(pty).filter(editor),
+    );
+    pty.get_mut().write_all(b"x\n").unwrap();
+    // nothing has been read from the pty since the first line was sent
+    for _ in 0..500 {
+        let termios =
+            nix::sys::termios::tcgetattr(pty.get_ref().get_ref()).unwrap();
+        if !termios
+            .local_flags
+            .contains(nix::sys::termios::LocalFlags::ECHO)
+        {
+            break;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    pty.get_mut().write_all(b"secret\n").unwrap();
+    assert_eq!(next_line(&mut pty), "x\r\n");
+    assert_eq!(next_line(&mut pty), "got secret\r\n");
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_line_editor_async() {
+    use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _};
+
+    let (pty, pts) = pty_process::open().unwrap();
+    let mut child = pty_process::Command::new("cat").spawn(pts).unwrap();
+
+    let editor = pty_process::filter::LineEditor::new(&pty).unwrap();
+    let mut pty = tokio::io::BufReader::new(
+        pty_process::filter::Filtered::new(pty).filter(editor),
+    );
+
+    pty.get_mut().write_all(b"hellp\x7fo\r").await.unwrap();
+    let mut line = vec![];
+    pty.read_until(b'\n', &mut line).await.unwrap();
+    assert_eq!(line, format!("hellp{}o\r\n", erase(1)).as_bytes());
+    line.clear();
+    pty.read_until(b'\n', &mut line).await.unwrap();
+    assert_eq!(line, b"hello\r\n");
+
+    pty.get_mut().write_all(b"\x04").await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
@@ -0,0 +1,82 @@
+#![cfg(all(target_os = "linux", feature = "linux-namespaces"))]
+
+mod helpers;
+
+#[test]
+fn test_unshare_user_uts() {
+    let uid = nix::unistd::geteuid().as_raw();
+    let gid = nix::unistd::getegid().as_raw();
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args([
+            "-c",
+            "hostname pty-process-test; hostname; id -u; id -g; \
+            test -t 0 && echo tty",
+        ])
+        .unshare(pty_process::Namespaces::USER | pty_process::Namespaces::UTS)
+        .uid_map(0, uid, 1)
+        .gid_map(0, gid, 1)
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "pty-process-test\r\n");
+    assert_eq!(output.next().unwrap(), "0\r\n");
+    assert_eq!(output.next().unwrap(), "0\r\n");
+    assert_eq!(output.next().unwrap(), "tty\r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_private_devpts() {
+    let uid = nix::unistd::geteuid().as_raw();
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .arg(
+            "-E$| = 1; opendir my $d, '/dev/pts' or die; \
+            say join ',', sort grep { !/^\\./ } readdir $d; \
+            open my $fh, '<', '/dev/tty' or die; say -t $fh ? 'tty' : 'no'",
+        )
+        .unshare(pty_process::Namespaces::USER)
+        .uid_map(0, uid, 1)
+        .private_devpts(true)
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ptmx\r\n");
+    assert_eq!(output.next().unwrap(), "tty\r\n");
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_unshare_async() {
+    use futures::stream::StreamExt as _;
+
+    let uid = nix::unistd::geteuid().as_raw();
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("id")
+        .arg("-u")
+        .unshare(pty_process::Namespaces::USER)
+        .uid_map(1000, uid, 1)
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "1000\r\n");
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
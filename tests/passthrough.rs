@@ -0,0 +1,186 @@
+mod helpers;
+
+// the passthrough helpers operate on the stdin and stdout of the current
+// process, so run them in a child whose stdin and stdout are a pty that we
+// control
+fn passthrough(
+    passthrough: impl FnOnce() -> pty_process::passthrough::Passthrough
+    + Send
+    + Sync
+    + 'static,
+    cmd: &'static [&'static str],
+) -> (
+    pty_process::blocking::ForkedChild,
+    pty_process::blocking::Pty,
+) {
+    unsafe {
+        pty_process::blocking::fork_with_pty(
+            pty_process::Size::new(24, 80),
+            move || {
+                let (mut pty, pts) = pty_process::blocking::open().unwrap();
+                // start the child at the size it would be given anyway, so
+                // that setting it doesn't race with the child installing a
+                // SIGWINCH handler
+                pty.resize(pty_process::Size::new(24, 80)).unwrap();
+                let mut child = pty_process::blocking::Command::new(cmd[0])
+                    .args(&cmd[1..])
+                    .spawn(pts)
+                    .unwrap();
+                let status = passthrough().run(&mut child, &mut pty).unwrap();
+                status.code().unwrap_or(-1)
+            },
+        )
+    }
+    .unwrap()
+}
+
+#[test]
+fn test_passthrough() {
+    use std::io::Write as _;
+
+    let (mut child, mut pty) = passthrough(
+        || {
+            pty_process::passthrough::Passthrough::new()
+                .input_filter(|buf| {
+                    if buf == b"quit" {
+                        *buf = vec![4];
+                    }
+                })
+                .output_filter(|buf| {
+                    for byte in buf.iter_mut() {
+                        if *byte == b'o' {
+                            *byte = b'0';
+                        }
+                    }
+                })
+        },
+        &["sh", "-c", "echo ready; exec cat"],
+    );
+
+    // wait for the terminal to be in raw mode before sending input
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "ready\r\n");
+    drop(output);
+
+    pty.write_all(b"foo\n").unwrap();
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "f00\r\n");
+    assert_eq!(output.next().unwrap(), "f00\r\n");
+    drop(output);
+
+    pty.write_all(b"quit").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_passthrough_resize() {
+    use std::io::Write as _;
+
+    let (mut child, mut pty) = passthrough(
+        pty_process::passthrough::Passthrough::new,
+        &[
+            "perl",
+            "-E",
+            "$| = 1; $SIG{WINCH} = sub { say `stty size` }; say `stty size`; \
+            <STDIN>",
+        ],
+    );
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "24 80\r\n");
+    assert_eq!(output.next().unwrap(), "\r\n");
+
+    pty.resize(pty_process::Size::new(30, 100)).unwrap();
+    assert_eq!(output.next().unwrap(), "30 100\r\n");
+    assert_eq!(output.next().unwrap(), "\r\n");
+    drop(output);
+
+    pty.write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_passthrough_shares_signal_handlers() {
+    use std::io::Write as _;
+
+    let (mut child, mut pty) = unsafe {
+        pty_process::blocking::fork_with_pty(
+            pty_process::Size::new(24, 80),
+            || {
+                // handlers registered elsewhere in the process should still
+                // run while the passthrough is active
+                let resized = std::sync::Arc::new(
+                    std::sync::atomic::AtomicBool::new(false),
+                );
+                signal_hook::flag::register(
+                    signal_hook::consts::SIGWINCH,
+                    std::sync::Arc::clone(&resized),
+                )
+                .unwrap();
+
+                let (mut pty, pts) = pty_process::blocking::open().unwrap();
+                pty.resize(pty_process::Size::new(24, 80)).unwrap();
+                let mut child = pty_process::blocking::Command::new("perl")
+                    .args([
+                        "-E",
+                        "$| = 1; $SIG{WINCH} = sub { say `stty size` }; \
+                         say `stty size`; <STDIN>",
+                    ])
+                    .spawn(pts)
+                    .unwrap();
+                pty_process::passthrough::Passthrough::new()
+                    .run(&mut child, &mut pty)
+                    .unwrap();
+                i32::from(!resized.load(std::sync::atomic::Ordering::SeqCst))
+            },
+        )
+    }
+    .unwrap();
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "24 80\r\n");
+    assert_eq!(output.next().unwrap(), "\r\n");
+
+    pty.resize(pty_process::Size::new(30, 100)).unwrap();
+    assert_eq!(output.next().unwrap(), "30 100\r\n");
+    assert_eq!(output.next().unwrap(), "\r\n");
+    drop(output);
+
+    pty.write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_passthrough_keeps_signal_dispositions() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let (mut child, _pty) = unsafe {
+        pty_process::blocking::fork_with_pty(
+            pty_process::Size::new(24, 80),
+            || {
+                let (mut pty, pts) = pty_process::blocking::open().unwrap();
+                let mut child = pty_process::blocking::Command::new("true")
+                    .spawn(pts)
+                    .unwrap();
+                pty_process::passthrough::Passthrough::new()
+                    .run(&mut child, &mut pty)
+                    .unwrap();
+                // SIGTERM should still terminate the process afterwards
+                rustix::process::kill_process(
+                    rustix::process::getpid(),
+                    rustix::process::Signal::TERM,
+                )
+                .unwrap();
+                std::thread::sleep(std::time::Duration::from_secs(5));
+                0
+            },
+        )
+    }
+    .unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+}
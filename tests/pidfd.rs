@@ -0,0 +1,98 @@
+#![cfg(target_os = "linux")]
+
+#[test]
+fn test_pidfd() {
+    use std::io::Write as _;
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "read x; exit 2"])
+        .spawn(pts)
+        .unwrap();
+    let pidfd = child.pidfd().unwrap();
+    assert!(!pidfd.has_exited().unwrap());
+    assert!(
+        !pidfd
+            .wait_exited(Some(std::time::Duration::from_millis(50)))
+            .unwrap()
+    );
+
+    (&pty).write_all(b"\n").unwrap();
+    assert!(
+        pidfd
+            .wait_exited(Some(std::time::Duration::from_secs(5)))
+            .unwrap()
+    );
+    // the child has exited but hasn't been reaped yet
+    assert!(pidfd.has_exited().unwrap());
+    assert_eq!(child.wait().unwrap().code(), Some(2));
+
+    let (_pty, pts) = pty_process::blocking::open().unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "read x"])
+        .spawn(pts)
+        .unwrap();
+    child.signal(rustix::process::Signal::TERM).unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+    // signals can't reach a process which has been reaped
+    assert!(child.signal(rustix::process::Signal::TERM).is_err());
+
+    // the pidfd can't be opened after the child has been reaped, so this
+    // goes through the pid instead
+    let (_pty, pts) = pty_process::blocking::open().unwrap();
+    let mut child = pty_process::blocking::Command::new("true")
+        .spawn(pts)
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+    assert!(child.pidfd().is_none());
+    assert!(child.signal(rustix::process::Signal::TERM).is_err());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_pidfd_async() {
+    use std::os::unix::process::ExitStatusExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    let mut child = pty_process::Command::new("sh")
+        .args(["-c", "read x; exit 2"])
+        .spawn(pts)
+        .unwrap();
+    let pidfd = child.pidfd().unwrap();
+    assert!(
+        tokio::time::timeout(
+            std::time::Duration::from_millis(50),
+            pidfd.exited()
+        )
+        .await
+        .is_err()
+    );
+
+    pty.write_all(b"\n").await.unwrap();
+    tokio::time::timeout(std::time::Duration::from_secs(5), pidfd.exited())
+        .await
+        .unwrap()
+        .unwrap();
+    // waiting again returns immediately
+    pidfd.exited().await.unwrap();
+    assert_eq!(child.wait().await.unwrap().code(), Some(2));
+
+    let (_pty, pts) = pty_process::open().unwrap();
+    let mut child = pty_process::Command::new("sh")
+        .args(["-c", "read x"])
+        .spawn(pts)
+        .unwrap();
+    child.signal(rustix::process::Signal::TERM).unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGTERM));
+    assert!(child.signal(rustix::process::Signal::TERM).is_err());
+
+    let (_pty, pts) = pty_process::open().unwrap();
+    let mut child = pty_process::Command::new("true").spawn(pts).unwrap();
+    assert!(child.wait().await.unwrap().success());
+    assert!(child.pidfd().is_none());
+    assert!(child.signal(rustix::process::Signal::TERM).is_err());
+}
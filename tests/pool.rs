@@ -0,0 +1,146 @@
+#[test]
+fn test_pool_many() {
+    let mut pool = pty_process::blocking::PtyPool::new();
+    let results = std::sync::Arc::new(std::sync::Mutex::new(
+        std::collections::HashMap::new(),
+    ));
+    for n in 0..20 {
+        let (pty, pts) = pty_process::blocking::open().unwrap();
+        let child = pty_process::blocking::Command::new("sh")
+            .args(["-c", &format!("read x; echo job {n}; exit {n}")])
+            .spawn(pts)
+            .unwrap();
+        let results = std::sync::Arc::clone(&results);
+        let mut output = vec![];
+        let id = pool
+            .add(pty, child, move |_, event| match event {
+                pty_process::blocking::PoolEvent::Output(data) => {
+                    output.extend_from_slice(data);
+                }
+                pty_process::blocking::PoolEvent::Exit(status) => {
+                    results.lock().unwrap().insert(
+                        n,
+                        (std::mem::take(&mut output), status.code()),
+                    );
+                }
+                _ => unreachable!(),
+            })
+            .unwrap();
+        assert!(pool.write(id, b"\n"));
+    }
+    assert_eq!(pool.len(), 20);
+
+    nix::unistd::alarm::set(10);
+    pool.run().unwrap();
+    nix::unistd::alarm::cancel();
+
+    assert!(pool.is_empty());
+    let results = results.lock().unwrap();
+    assert_eq!(results.len(), 20);
+    for (n, (output, code)) in results.iter() {
+        let output = std::str::from_utf8(output).unwrap();
+        assert!(output.ends_with(&format!("job {n}\r\n")), "{output:?}");
+        assert_eq!(*code, Some(*n));
+    }
+}
+
+#[test]
+fn test_pool_respond() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let child = pty_process::blocking::Command::new("perl")
+        .args([
+            "-e",
+            "$| = 1; print 'name? '; $n = <STDIN>; chomp $n; print qq(hello $n\\n)",
+        ])
+        .spawn(pts)
+        .unwrap();
+
+    let mut pool = pty_process::blocking::PtyPool::new();
+    let result = std::sync::Arc::new(std::sync::Mutex::new(None));
+    let mut output = vec![];
+    let id = pool
+        .add(pty, child, {
+            let result = std::sync::Arc::clone(&result);
+            move |ctx, event| match event {
+                pty_process::blocking::PoolEvent::Output(data) => {
+                    output.extend_from_slice(data);
+                    if output.ends_with(b"name? ") {
+                        ctx.write(b"world\n");
+                    }
+                }
+                pty_process::blocking::PoolEvent::Exit(status) => {
+                    assert!(status.success());
+                    *result.lock().unwrap() =
+                        Some((std::mem::take(&mut output), *ctx.metrics()));
+                }
+                _ => unreachable!(),
+            }
+        })
+        .unwrap();
+    assert!(pool.metrics(id).is_some());
+
+    nix::unistd::alarm::set(5);
+    pool.run().unwrap();
+    nix::unistd::alarm::cancel();
+
+    assert!(pool.metrics(id).is_none());
+    let (output, metrics) = result.lock().unwrap().take().unwrap();
+    assert_eq!(output, b"name? world\r\nhello world\r\n");
+    assert_eq!(metrics.bytes_written, 6);
+    assert_eq!(metrics.bytes_read, u64::try_from(output.len()).unwrap());
+    assert!(metrics.reads > 0);
+    assert!(metrics.exited.is_some());
+}
+
+#[test]
+fn test_pool_remove() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "read x; exit 5"])
+        .spawn(pts)
+        .unwrap();
+
+    let mut pool = pty_process::blocking::PtyPool::new();
+    let id = pool.add(pty, child, |_, _| {}).unwrap();
+    assert_eq!(
+        pool.poll(Some(std::time::Duration::from_millis(50)))
+            .unwrap(),
+        0
+    );
+    assert_eq!(pool.ids().collect::<Vec<_>>(), vec![id]);
+
+    let (pty, mut child) = pool.remove(id).unwrap();
+    assert!(pool.is_empty());
+    let flags = nix::fcntl::OFlag::from_bits_truncate(
+        nix::fcntl::fcntl(&pty, nix::fcntl::FcntlArg::F_GETFL).unwrap(),
+    );
+    assert!(!flags.contains(nix::fcntl::OFlag::O_NONBLOCK));
+    assert!(!pool.write(id, b"\n"));
+    (&pty).write_all(b"\n").unwrap();
+    assert_eq!(child.wait().unwrap().code(), Some(5));
+}
+
+#[test]
+fn test_pool_fairness() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let child = pty_process::blocking::Command::new("perl")
+        .args(["-e", "$| = 1; print 'x' x 20000; <STDIN>"])
+        .spawn(pts)
+        .unwrap();
+
+    let mut pool = pty_process::blocking::PtyPool::new();
+    let id = pool.add(pty, child, |_, _| {}).unwrap();
+
+    // only one buffer is read from each pty per round, even though more
+    // output is available
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    nix::unistd::alarm::set(5);
+    while pool.metrics(id).unwrap().bytes_read < 20000 {
+        assert!(pool.poll(None).unwrap() <= 1);
+    }
+    assert!(pool.write(id, b"\n"));
+    pool.run().unwrap();
+    nix::unistd::alarm::cancel();
+}
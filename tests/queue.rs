@@ -0,0 +1,142 @@
+mod helpers;
+
+// the input written in these tests shouldn't also be echoed
+fn disable_echo(fd: impl std::os::fd::AsFd) {
+    let mut termios = nix::sys::termios::tcgetattr(&fd).unwrap();
+    termios
+        .local_flags
+        .remove(nix::sys::termios::LocalFlags::ECHO);
+    nix::sys::termios::tcsetattr(
+        &fd,
+        nix::sys::termios::SetArg::TCSANOW,
+        &termios,
+    )
+    .unwrap();
+}
+
+// data written to the pty takes a moment to reach the queue of the pts
+#[track_caller]
+fn wait_for(f: impl Fn() -> bool) {
+    for _ in 0..500 {
+        if f() {
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    panic!("timed out");
+}
+
+// the child may still read input which arrives before it has actually
+// stopped
+fn stop(pid: rustix::process::Pid) {
+    rustix::process::kill_process(pid, rustix::process::Signal::STOP)
+        .unwrap();
+    let (_, status) = rustix::process::waitpid(
+        Some(pid),
+        rustix::process::WaitOptions::UNTRACED,
+    )
+    .unwrap()
+    .unwrap();
+    assert!(status.stopped());
+}
+
+#[test]
+fn test_queue() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    disable_echo(&pty);
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(pts)
+        .unwrap();
+    let mut output = helpers::output(&pty);
+    let pid = rustix::process::Pid::from_child(&child);
+
+    // input which the child hasn't read yet
+    stop(pid);
+    (&pty).write_all(b"one\n").unwrap();
+    wait_for(|| pty.input_queue_len().unwrap() == 4);
+    // incomplete lines can't be read yet, so they aren't counted
+    (&pty).write_all(b"tw").unwrap();
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(pty.input_queue_len().unwrap(), 4);
+    pty.discard(pty_process::Queue::Input).unwrap();
+    assert_eq!(pty.input_queue_len().unwrap(), 0);
+
+    // output which hasn't been read from the pty yet
+    (&pty).write_all(b"two\n").unwrap();
+    child.signal(rustix::process::Signal::CONT).unwrap();
+    wait_for(|| pty.output_queue_len().unwrap() == 5);
+    pty.discard(pty_process::Queue::Output).unwrap();
+    assert_eq!(pty.output_queue_len().unwrap(), 0);
+
+    (&pty).write_all(b"three\n").unwrap();
+    assert_eq!(output.next().unwrap(), "three\r\n");
+
+    // the child reads its input, but can't write its output
+    pty.suspend_output().unwrap();
+    (&pty).write_all(b"four\n").unwrap();
+    wait_for(|| pty.input_queue_len().unwrap() == 0);
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    assert_eq!(pty.output_queue_len().unwrap(), 0);
+    pty.resume_output().unwrap();
+    assert_eq!(output.next().unwrap(), "four\r\n");
+
+    stop(pid);
+    (&pty).write_all(b"five\n").unwrap();
+    wait_for(|| pty.input_queue_len().unwrap() == 5);
+    let resume = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        rustix::process::kill_process(pid, rustix::process::Signal::CONT)
+            .unwrap();
+    });
+    pty.drain().unwrap();
+    assert_eq!(pty.input_queue_len().unwrap(), 0);
+    resume.join().unwrap();
+    assert_eq!(output.next().unwrap(), "five\r\n");
+
+    pty.send_break().unwrap();
+
+    (&pty).write_all(b"\x04").unwrap();
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_queue_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    disable_echo(&pty);
+    let mut child = pty_process::Command::new("cat").spawn(pts).unwrap();
+    let (pty_r, mut pty_w) = pty.into_split();
+    let mut output = helpers::output_async(pty_r);
+    let pid = rustix::process::Pid::from_raw(
+        i32::try_from(child.id().unwrap()).unwrap(),
+    )
+    .unwrap();
+
+    stop(pid);
+    pty_w.write_all(b"one\n").await.unwrap();
+    wait_for(|| pty_w.input_queue_len().unwrap() == 4);
+    pty_w.discard(pty_process::Queue::Input).unwrap();
+    pty_w.write_all(b"two\n").await.unwrap();
+    wait_for(|| pty_w.input_queue_len().unwrap() == 4);
+
+    let resume = tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        rustix::process::kill_process(pid, rustix::process::Signal::CONT)
+            .unwrap();
+    });
+    tokio::time::timeout(std::time::Duration::from_secs(5), pty_w.drain())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(pty_w.input_queue_len().unwrap(), 0);
+    resume.await.unwrap();
+    assert_eq!(output.next().await.unwrap(), "two\r\n");
+
+    pty_w.write_all(b"\x04").await.unwrap();
+    assert!(child.wait().await.unwrap().success());
+}
@@ -0,0 +1,69 @@
+mod helpers;
+
+#[test]
+fn test_read_timeout() {
+    use std::io::{Read as _, Write as _};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", "read x; echo \"got $x\"; read y"])
+        .spawn(pts)
+        .unwrap();
+
+    let mut buf = [0_u8; 1024];
+    assert_eq!(
+        pty.try_read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+    assert_eq!(
+        pty.read_timeout(&mut buf, std::time::Duration::from_millis(50))
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::TimedOut
+    );
+    assert!(
+        !pty.wait_readable(Some(std::time::Duration::from_millis(50)))
+            .unwrap()
+    );
+
+    (&pty).write_all(b"foo\n").unwrap();
+    assert!(
+        pty.wait_readable(Some(std::time::Duration::from_secs(5)))
+            .unwrap()
+    );
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "foo\r\n");
+    assert_eq!(output.next().unwrap(), "got foo\r\n");
+    assert_eq!(
+        pty.try_read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::WouldBlock
+    );
+
+    pty.set_read_timeout(Some(std::time::Duration::from_millis(50)))
+        .unwrap();
+    assert_eq!(
+        (&pty).read(&mut buf).unwrap_err().kind(),
+        std::io::ErrorKind::TimedOut
+    );
+    assert_eq!(
+        pty.set_read_timeout(Some(std::time::Duration::ZERO))
+            .unwrap_err()
+            .kind(),
+        std::io::ErrorKind::InvalidInput
+    );
+
+    (&pty).write_all(b"\n").unwrap();
+    assert!(child.wait().unwrap().success());
+
+    // once the pts is closed, reads fail immediately rather than timing out
+    pty.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let start = std::time::Instant::now();
+    let err = loop {
+        if let Err(e) = (&pty).read(&mut buf) {
+            break e;
+        }
+    };
+    assert_ne!(err.kind(), std::io::ErrorKind::TimedOut);
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+}
@@ -0,0 +1,324 @@
+fn entry(
+    millis: u64,
+    event: pty_process::transcript::Event,
+) -> pty_process::transcript::Entry {
+    pty_process::transcript::Entry {
+        elapsed: std::time::Duration::from_millis(millis),
+        event,
+    }
+}
+
+fn entries() -> Vec<pty_process::transcript::Entry> {
+    vec![
+        entry(0, pty_process::transcript::Event::Output(b"$ ".to_vec())),
+        entry(250, pty_process::transcript::Event::Input(b"ls\r".to_vec())),
+        entry(
+            250,
+            pty_process::transcript::Event::Output(
+                b"ls\r\nfoo\r\n$ ".to_vec(),
+            ),
+        ),
+        entry(
+            1500,
+            pty_process::transcript::Event::Resize { rows: 24, cols: 80 },
+        ),
+        entry(1750, pty_process::transcript::Event::RedactedInput(8)),
+        entry(
+            2000,
+            pty_process::transcript::Event::Output(b"\r\n".to_vec()),
+        ),
+    ]
+}
+
+#[test]
+fn test_ttyrec() {
+    let mut writer = pty_process::recording::Writer::new(
+        pty_process::recording::Format::Ttyrec,
+        vec![],
+    )
+    .start_time(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1000));
+    for entry in entries() {
+        writer.write_entry(&entry).unwrap();
+    }
+    let (data, timing) = writer.into_inner();
+    assert!(timing.is_none());
+    assert_eq!(&data[..12], &[232, 3, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(&data[12..14], b"$ ");
+    assert_eq!(&data[14..26], &[232, 3, 0, 0, 144, 208, 3, 0, 11, 0, 0, 0]);
+
+    let read: Vec<_> = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Ttyrec,
+        &data[..],
+    )
+    .collect::<std::io::Result<_>>()
+    .unwrap();
+    // only output is stored in ttyrec files
+    let expected: Vec<_> = entries()
+        .into_iter()
+        .filter(|entry| {
+            matches!(entry.event, pty_process::transcript::Event::Output(_))
+        })
+        .collect();
+    assert_eq!(read, expected);
+
+    let err = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Ttyrec,
+        &data[..20],
+    )
+    .nth(1)
+    .unwrap()
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    // lengths aren't trusted before the data has actually been read
+    let err = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Ttyrec,
+        &[232, 3, 0, 0, 0, 0, 0, 0, 255, 255, 255, 255, b'$'][..],
+    )
+    .next()
+    .unwrap()
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn test_script() {
+    let mut writer = pty_process::recording::Writer::new(
+        pty_process::recording::Format::Script,
+        vec![],
+    )
+    .timing(vec![])
+    .start_time(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1000));
+    for entry in entries() {
+        writer.write_entry(&entry).unwrap();
+    }
+    let (data, timing) = writer.into_inner();
+    let timing = timing.unwrap();
+    assert_eq!(
+        String::from_utf8(data.clone()).unwrap(),
+        "Script started on 1970-01-01 00:16:40+00:00\n\
+         $ ls\rls\r\nfoo\r\n$ \r\n"
+    );
+    assert_eq!(
+        String::from_utf8(timing.clone()).unwrap(),
+        "H 0.000000 START_TIME 1970-01-01 00:16:40+00:00\n\
+         O 0.000000 2\n\
+         I 0.250000 3\n\
+         O 0.000000 11\n\
+         S 1.250000 SIGWINCH ROWS=24 COLS=80\n\
+         O 0.500000 2\n"
+    );
+
+    let read: Vec<_> = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &data[..],
+    )
+    .timing(&timing[..])
+    .collect::<std::io::Result<_>>()
+    .unwrap();
+    // secrets are never stored
+    let expected: Vec<_> = entries()
+        .into_iter()
+        .filter(|entry| {
+            !matches!(
+                entry.event,
+                pty_process::transcript::Event::RedactedInput(_)
+            )
+        })
+        .collect();
+    assert_eq!(read, expected);
+
+    let read: Vec<_> = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &data[..],
+    )
+    .collect::<std::io::Result<_>>()
+    .unwrap();
+    assert_eq!(
+        read,
+        [entry(
+            0,
+            pty_process::transcript::Event::Output(
+                b"$ ls\rls\r\nfoo\r\n$ \r\n".to_vec()
+            )
+        )]
+    );
+
+    let mut writer = pty_process::recording::Writer::new(
+        pty_process::recording::Format::Script,
+        vec![],
+    )
+    .start_time(
+        std::time::UNIX_EPOCH + std::time::Duration::from_secs(1_709_210_096),
+    );
+    writer.write_entry(&entries()[0]).unwrap();
+    let (data, _) = writer.into_inner();
+    assert!(
+        data.starts_with(b"Script started on 2024-02-29 12:34:56+00:00\n")
+    );
+
+    let err = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &b"header\nfoo"[..],
+    )
+    .timing(&b"O 0.000000 18446744073709551615\n"[..])
+    .next()
+    .unwrap()
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+    let err = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &b"header\nab"[..],
+    )
+    .timing(&b"O 1e19 1\nO 1e19 1\n"[..])
+    .nth(1)
+    .unwrap()
+    .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn test_script_util_linux() {
+    // as written by script -B log -T timing -m advanced
+    let data = b"Script started on 2026-10-19 06:01:39+00:00 \
+                 [COMMAND=\"read x; echo got $x\"]\n\
+                 hello\nhello\r\ngot hello\r\n\n\
+                 Script done on 2026-10-19 06:01:41+00:00 \
+                 [COMMAND_EXIT_CODE=\"0\"]\n";
+    let timing = b"H 0.000000 START_TIME 2026-10-19 06:01:39+00:00\n\
+                   H 0.000000 SHELL /bin/bash\n\
+                   H 0.000000 COMMAND read x; echo got $x\n\
+                   I 0.000038 6\n\
+                   O 0.002544 18\n\
+                   S 0.500000 SIGWINCH ROWS=50 COLS=132\n\
+                   H 0.000000 DURATION 2.012834\n\
+                   H 0.000000 EXIT_CODE 0\n";
+    let read: Vec<_> = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &data[..],
+    )
+    .timing(&timing[..])
+    .collect::<std::io::Result<_>>()
+    .unwrap();
+    assert_eq!(
+        read,
+        [
+            pty_process::transcript::Entry {
+                elapsed: std::time::Duration::from_micros(38),
+                event: pty_process::transcript::Event::Input(
+                    b"hello\n".to_vec()
+                ),
+            },
+            pty_process::transcript::Entry {
+                elapsed: std::time::Duration::from_micros(2582),
+                event: pty_process::transcript::Event::Output(
+                    b"hello\r\ngot hello\r\n".to_vec()
+                ),
+            },
+            pty_process::transcript::Entry {
+                elapsed: std::time::Duration::from_micros(502_582),
+                event: pty_process::transcript::Event::Resize {
+                    rows: 50,
+                    cols: 132,
+                },
+            },
+        ]
+    );
+
+    // classic timing files only record output
+    let read: Vec<_> = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &b"header\nfoobar"[..],
+    )
+    .timing(&b"0.5 3\n0.25 3\n"[..])
+    .collect::<std::io::Result<_>>()
+    .unwrap();
+    assert_eq!(
+        read,
+        [
+            entry(
+                500,
+                pty_process::transcript::Event::Output(b"foo".to_vec())
+            ),
+            entry(
+                750,
+                pty_process::transcript::Event::Output(b"bar".to_vec())
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_recording_pty() {
+    use std::io::{Read as _, Write as _};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let mut child = pty_process::blocking::Command::new("cat")
+        .spawn(pts)
+        .unwrap();
+    let transcript = pty_process::transcript::Transcript::new();
+    let mut pty = transcript.wrap(pty);
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    pty.write_all(b"foo\n").unwrap();
+    let mut buf = [0; 16];
+    let mut output = vec![];
+    while !output.ends_with(b"foo\r\nfoo\r\n") {
+        let bytes = pty.read(&mut buf).unwrap();
+        output.extend_from_slice(&buf[..bytes]);
+    }
+    pty.write_all(b"\x04").unwrap();
+    assert!(child.wait().unwrap().success());
+
+    for format in [
+        pty_process::recording::Format::Ttyrec,
+        pty_process::recording::Format::Script,
+    ] {
+        let mut writer = pty_process::recording::Writer::new(format, vec![])
+            .timing(vec![]);
+        writer.write_transcript(&transcript).unwrap();
+        let (data, timing) = writer.into_inner();
+        let output: Vec<u8> =
+            pty_process::recording::Reader::new(format, &data[..])
+                .timing(&timing.unwrap()[..])
+                .map(Result::unwrap)
+                .filter(|entry| {
+                    entry.direction()
+                        == pty_process::transcript::Direction::Output
+                })
+                .flat_map(|entry| entry.data().to_vec())
+                .collect();
+        assert_eq!(output, b"foo\r\nfoo\r\n");
+    }
+
+    // resizes made through the wrapper are recorded too
+    let mut writer = pty_process::recording::Writer::new(
+        pty_process::recording::Format::Script,
+        vec![],
+    )
+    .timing(vec![]);
+    writer.write_transcript(&transcript).unwrap();
+    let (data, timing) = writer.into_inner();
+    let entries: Vec<_> = pty_process::recording::Reader::new(
+        pty_process::recording::Format::Script,
+        &data[..],
+    )
+    .timing(&timing.unwrap()[..])
+    .map(Result::unwrap)
+    .collect();
+    assert_eq!(
+        entries[0].event,
+        pty_process::transcript::Event::Resize { rows: 24, cols: 80 }
+    );
+
+    // an explicit start time isn't replaced by the transcript's
+    let mut writer = pty_process::recording::Writer::new(
+        pty_process::recording::Format::Ttyrec,
+        vec![],
+    )
+    .start_time(std::time::UNIX_EPOCH + std::time::Duration::from_secs(1000));
+    writer.write_transcript(&transcript).unwrap();
+    let (data, _) = writer.into_inner();
+    let sec = u32::from_le_bytes(data[..4].try_into().unwrap());
+    assert!((1000..1010).contains(&sec));
+}
@@ -0,0 +1,82 @@
+mod helpers;
+
+// sends each argument as a query, and then prints whatever it reads back
+// (with escapes made visible) until no more input arrives
+const SCRIPT: &str = "system 'stty raw -echo'; $| = 1; \
+    for my $q (@ARGV) { \
+        print $q; my $r = ''; \
+        while (1) { \
+            my $rin = ''; vec($rin, 0, 1) = 1; \
+            last unless select($rin, undef, undef, 0.2); \
+            sysread STDIN, my $c, 1; $r .= $c; \
+        } \
+        $r =~ s/\\e/^[/g; print qq($r\\r\\n); \
+    } \
+    system 'stty sane'; <STDIN>";
+
+#[test]
+fn test_responder_blocking() {
+    use std::io::{BufRead as _, Write as _};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-e", SCRIPT, "\x1b[5n", "\x1b[6n", "\x1b[c", "\x1b[>q"])
+        .spawn(pts)
+        .unwrap();
+
+    let pty = pty_process::filter::Filtered::new(pty)
+        .filter(pty_process::filter::Responder::new().cursor_position(4, 9));
+    let mut output =
+        std::io::BufReader::new(pty_process::strip::StripAnsi::new(pty));
+    let mut next = || {
+        let mut buf = vec![];
+        output.read_until(b'\n', &mut buf).unwrap();
+        String::from_utf8(buf).unwrap()
+    };
+    assert_eq!(next(), "^[[0n\r\n");
+    assert_eq!(next(), "^[[5;10R\r\n");
+    assert_eq!(next(), "^[[?62;22c\r\n");
+    assert_eq!(
+        next(),
+        format!("^[P>|pty-process({})^[\\\r\n", env!("CARGO_PKG_VERSION"))
+    );
+
+    output.get_mut().get_mut().write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_responder_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-e", SCRIPT, "\x1b[>c", "\x1b]11;?\x07", "\x1b]4;1;?\x07"])
+        .spawn(pts)
+        .unwrap();
+
+    let pty = pty_process::filter::Filtered::new(pty).filter(
+        pty_process::filter::Responder::new()
+            .secondary_device_attributes(b"\x1b[>41;100;0c")
+            .background(0x12, 0x34, 0xab),
+    );
+    let (pty_r, mut pty_w) = tokio::io::split(pty);
+    let mut output =
+        helpers::output_async(pty_process::strip::StripAnsi::new(pty_r));
+    assert_eq!(output.next().await.unwrap(), "^[[>41;100;0c\r\n");
+    assert_eq!(
+        output.next().await.unwrap(),
+        "^[]11;rgb:1212/3434/abab^[\\\r\n"
+    );
+    // unsupported queries aren't answered
+    assert_eq!(output.next().await.unwrap(), "\r\n");
+
+    pty_w.write_all(b"\n").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
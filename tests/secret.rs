@@ -0,0 +1,112 @@
+mod helpers;
+
+// prints the length of the password rather than the password itself, so
+// the tests can check that it never appears in the output
+const SCRIPT: &str = "stty -echo; printf 'Password: '; read p; stty echo; \
+                      echo; echo \"got ${#p}\"";
+
+#[derive(Default)]
+struct Recorder(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+
+impl pty_process::filter::Filter for Recorder {
+    fn input(
+        &mut self,
+        data: &mut Vec<u8>,
+        _: &mut pty_process::filter::Context<'_>,
+    ) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(String::from_utf8_lossy(data).into_owned());
+    }
+
+    fn redacted_input(&mut self, len: usize) {
+        self.0.lock().unwrap().push(format!("<{len} redacted>"));
+    }
+}
+
+#[test]
+fn test_secret() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    assert!(!pty.is_echo_disabled().unwrap());
+    assert!(
+        !pty.wait_for_password_prompt(Some(
+            std::time::Duration::from_millis(50)
+        ))
+        .unwrap()
+    );
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    assert!(
+        pty.wait_for_password_prompt(Some(std::time::Duration::from_secs(5)))
+            .unwrap()
+    );
+    assert!(pty.is_echo_disabled().unwrap());
+    let secret = pty_process::SecretBytes::from("hunter2\n".to_string());
+    assert_eq!(format!("{secret:?}"), "SecretBytes([REDACTED])");
+    pty.send_secret(&secret).unwrap();
+    drop(secret);
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "Password: \r\n");
+    assert_eq!(output.next().unwrap(), "got 7\r\n");
+    assert!(child.wait().unwrap().success());
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    let mut child = pty_process::blocking::Command::new("sh")
+        .args(["-c", &format!("read x; {SCRIPT}")])
+        .spawn(pts)
+        .unwrap();
+    let recorder = Recorder::default();
+    let recorded = std::sync::Arc::clone(&recorder.0);
+    let mut filtered =
+        pty_process::filter::Filtered::new(&pty).filter(recorder);
+    filtered.write_all(b"\n").unwrap();
+    assert!(
+        pty.wait_for_password_prompt(Some(std::time::Duration::from_secs(5)))
+            .unwrap()
+    );
+    filtered
+        .send_secret(&pty_process::SecretBytes::new(b"hunter2\n".to_vec()))
+        .unwrap();
+    assert_eq!(*recorded.lock().unwrap(), ["\n", "<8 redacted>"]);
+
+    let mut output = helpers::output(&pty);
+    assert_eq!(output.next().unwrap(), "\r\n");
+    assert_eq!(output.next().unwrap(), "Password: \r\n");
+    assert_eq!(output.next().unwrap(), "got 7\r\n");
+    assert!(child.wait().unwrap().success());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_secret_async() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    assert!(!pty.is_echo_disabled().unwrap());
+    let mut child = pty_process::Command::new("sh")
+        .args(["-c", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    assert!(
+        pty.wait_for_password_prompt(Some(std::time::Duration::from_secs(5)))
+            .await
+            .unwrap()
+    );
+    pty.send_secret(&pty_process::SecretBytes::from("hunter2\n".to_string()))
+        .await
+        .unwrap();
+
+    let (pty_r, _) = pty.split();
+    let mut output = helpers::output_async(pty_r);
+    assert_eq!(output.next().await.unwrap(), "Password: \r\n");
+    assert_eq!(output.next().await.unwrap(), "got 7\r\n");
+    assert!(child.wait().await.unwrap().success());
+}
@@ -0,0 +1,227 @@
+#![cfg(feature = "async")]
+
+// prints the size of the pty or the given line for each line of input
+const SCRIPT: &str = r#"
+while read line; do
+    case "$line" in
+        size) stty size ;;
+        exit) exit 3 ;;
+        *) echo "got $line" ;;
+    esac
+done
+"#;
+
+// the input lines shouldn't also be echoed
+fn disable_echo(fd: impl std::os::fd::AsFd) {
+    let mut termios = nix::sys::termios::tcgetattr(&fd).unwrap();
+    termios
+        .local_flags
+        .remove(nix::sys::termios::LocalFlags::ECHO);
+    nix::sys::termios::tcsetattr(
+        &fd,
+        nix::sys::termios::SetArg::TCSANOW,
+        &termios,
+    )
+    .unwrap();
+}
+
+// reads output until it ends with the expected string, returning
+// everything read
+async fn collect(
+    client: &mut pty_process::session::SessionClient,
+    expected: &str,
+) -> String {
+    let mut output = String::new();
+    while !output.ends_with(expected) {
+        let event = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.recv(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        match event {
+            Some(pty_process::session::SessionEvent::Output(data)) => {
+                output.push_str(std::str::from_utf8(&data).unwrap());
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+    }
+    output
+}
+
+#[tokio::test]
+async fn test_session() {
+    use std::os::unix::process::ExitStatusExt as _;
+
+    let socket = std::env::temp_dir()
+        .join(format!("pty-process-session-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&socket);
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    disable_echo(&pty);
+    let child = pty_process::Command::new("sh")
+        .args(["-c", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let server = tokio::spawn(
+        pty_process::session::SessionServer::new(child, pty)
+            .resize_policy(pty_process::session::ResizePolicy::Smallest)
+            .run(listener),
+    );
+
+    let mut first = pty_process::session::SessionClient::connect(&socket)
+        .await
+        .unwrap();
+    first.resize(pty_process::Size::new(30, 100)).await.unwrap();
+    first.send_input(b"one\n").await.unwrap();
+    assert_eq!(collect(&mut first, "got one\r\n").await, "got one\r\n");
+
+    // clients which attach later see the scrollback first
+    let mut second = pty_process::session::SessionClient::connect(&socket)
+        .await
+        .unwrap();
+    assert_eq!(collect(&mut second, "got one\r\n").await, "got one\r\n");
+
+    // messages from a single client are handled in order, so the resize
+    // happens before the input is written
+    second.resize(pty_process::Size::new(40, 60)).await.unwrap();
+    second.send_input(b"size\n").await.unwrap();
+    assert_eq!(collect(&mut second, "30 60\r\n").await, "30 60\r\n");
+    assert_eq!(collect(&mut first, "30 60\r\n").await, "30 60\r\n");
+
+    // once the second client detaches, only the first client's size is
+    // used
+    second.detach().await.unwrap();
+    let mut output = String::new();
+    for _ in 0..50 {
+        first.send_input(b"size\n").await.unwrap();
+        output = collect(&mut first, "\r\n").await;
+        if output == "30 100\r\n" {
+            break;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+    }
+    assert_eq!(output, "30 100\r\n");
+
+    first.send_input(b"exit\n").await.unwrap();
+    let event =
+        tokio::time::timeout(std::time::Duration::from_secs(5), first.recv())
+            .await
+            .unwrap()
+            .unwrap();
+    match event {
+        Some(pty_process::session::SessionEvent::Exit(status)) => {
+            assert_eq!(status.code(), Some(3));
+        }
+        event => panic!("unexpected event {event:?}"),
+    }
+    assert!(first.recv().await.unwrap().is_none());
+
+    let status = server.await.unwrap().unwrap();
+    assert_eq!(status.into_raw(), 3 << 8);
+    let _ = std::fs::remove_file(&socket);
+}
+
+#[tokio::test]
+async fn test_session_ends_with_child() {
+    let socket = std::env::temp_dir().join(format!(
+        "pty-process-session-exit-{}.sock",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&socket);
+
+    // the background process keeps the pty open after the child exits
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let child = pty_process::Command::new("sh")
+        .args(["-c", "trap '' HUP; read line; sleep 1000 & echo $!; exit 3"])
+        .spawn(pts)
+        .unwrap();
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let server = tokio::spawn(
+        pty_process::session::SessionServer::new(child, pty).run(listener),
+    );
+
+    let mut client = pty_process::session::SessionClient::connect(&socket)
+        .await
+        .unwrap();
+    client.send_input(b"\n").await.unwrap();
+    let mut output = vec![];
+    let status = loop {
+        let event = tokio::time::timeout(
+            std::time::Duration::from_secs(5),
+            client.recv(),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+        match event {
+            Some(pty_process::session::SessionEvent::Output(data)) => {
+                output.extend_from_slice(&data);
+            }
+            Some(pty_process::session::SessionEvent::Exit(status)) => {
+                break status;
+            }
+            event => panic!("unexpected event {event:?}"),
+        }
+    };
+    assert_eq!(status.code(), Some(3));
+    assert!(client.recv().await.unwrap().is_none());
+
+    let status =
+        tokio::time::timeout(std::time::Duration::from_secs(5), server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    assert_eq!(status.code(), Some(3));
+
+    let output = String::from_utf8(output).unwrap();
+    let pid = output.lines().last().unwrap().trim().parse().unwrap();
+    nix::sys::signal::kill(
+        nix::unistd::Pid::from_raw(pid),
+        nix::sys::signal::Signal::SIGKILL,
+    )
+    .unwrap();
+    let _ = std::fs::remove_file(&socket);
+}
+
+#[tokio::test]
+async fn test_session_stuck_client() {
+    use tokio::io::AsyncWriteExt as _;
+
+    let socket = std::env::temp_dir().join(format!(
+        "pty-process-session-stuck-{}.sock",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_file(&socket);
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let child = pty_process::Command::new("sh")
+        .args(["-c", "read line; head -c 4000000 /dev/zero; exit 3"])
+        .spawn(pts)
+        .unwrap();
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let server = tokio::spawn(
+        pty_process::session::SessionServer::new(child, pty).run(listener),
+    );
+
+    // a client which sends a line of input and then never reads, so the
+    // output fills up its socket
+    let mut client = tokio::net::UnixStream::connect(&socket).await.unwrap();
+    client.write_all(&[0, 0, 0, 0, 1, b'\n']).await.unwrap();
+
+    let status =
+        tokio::time::timeout(std::time::Duration::from_secs(10), server)
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+    assert_eq!(status.code(), Some(3));
+    drop(client);
+    let _ = std::fs::remove_file(&socket);
+}
@@ -0,0 +1,203 @@
+mod helpers;
+
+const SCRIPT: &str = "$| = 1; \
+    print qq(\\e]7;file://host/tmp/a%20b;c\\a\\e]133;A\\a\\$ \\e]133;B\\a); \
+    print qq(ls\\r\\n\\e]133;C\\e\\\\); sleep 0.1; \
+    print qq(out\\r\\n\\e]133;D;2\\a\\e]133;A\\a\\$ \\e]13); sleep 0.1; \
+    print qq(3;B\\e\\\\done\\n); <STDIN>";
+
+fn expected() -> Vec<pty_process::shell::Event> {
+    vec![
+        pty_process::shell::Event::Cwd("/tmp/a b;c".into()),
+        pty_process::shell::Event::PromptStart,
+        pty_process::shell::Event::PromptEnd,
+        pty_process::shell::Event::CommandStart,
+        pty_process::shell::Event::CommandEnd { exit: Some(2) },
+        pty_process::shell::Event::PromptStart,
+        pty_process::shell::Event::PromptEnd,
+    ]
+}
+
+#[test]
+fn test_shell_parser() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-e", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let mut parser = pty_process::shell::Parser::new();
+    let mut events = vec![];
+    for line in helpers::output(&pty) {
+        events.extend(parser.process(line.as_bytes()));
+        if line.ends_with("done\r\n") {
+            break;
+        }
+    }
+    assert_eq!(events, expected());
+
+    (&pty).write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_shell_parser_malformed_cwd() {
+    let mut parser = pty_process::shell::Parser::new();
+    // a cwd sequence with no url (or an empty one) is ignored
+    assert_eq!(
+        parser.process(b"\x1b]7\x07\x1b]7;\x07\x1b]133;A\x07"),
+        [pty_process::shell::Event::PromptStart]
+    );
+    assert_eq!(
+        parser.process(b"\x1b]7;file://host/100%25\x07"),
+        [pty_process::shell::Event::Cwd("/100%".into())]
+    );
+}
+
+#[test]
+fn test_shell_integration_bash() {
+    use std::io::{Read as _, Write as _};
+
+    let home = std::env::temp_dir()
+        .join(format!("pty-process-test-home-{}", std::process::id()));
+    // percent signs in the path have to survive being sent as a url
+    let dir = home.join("100%41");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::shell::Shell::Bash
+        .blocking_command()
+        .unwrap()
+        .env("HOME", &home)
+        .spawn(pts)
+        .unwrap();
+
+    (&pty)
+        .write_all(format!("cd '{}'; (exit 3)\n", dir.display()).as_bytes())
+        .unwrap();
+
+    let mut parser = pty_process::shell::Parser::new();
+    let mut events = vec![];
+    let mut buf = [0; 4096];
+    nix::unistd::alarm::set(5);
+    while !events.contains(&pty_process::shell::Event::CommandStart)
+        || events.last() != Some(&pty_process::shell::Event::PromptEnd)
+    {
+        let bytes = (&pty).read(&mut buf).unwrap();
+        events.extend(parser.process(&buf[..bytes]));
+    }
+    nix::unistd::alarm::cancel();
+
+    let command_start = events
+        .iter()
+        .position(|event| *event == pty_process::shell::Event::CommandStart)
+        .unwrap();
+    assert_eq!(
+        events[command_start..],
+        [
+            pty_process::shell::Event::CommandStart,
+            pty_process::shell::Event::CommandEnd { exit: Some(3) },
+            pty_process::shell::Event::Cwd(dir.clone()),
+            pty_process::shell::Event::PromptStart,
+            pty_process::shell::Event::PromptEnd,
+        ]
+    );
+
+    (&pty).write_all(b"exit 0\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+    std::fs::remove_dir_all(&home).unwrap();
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_shell_events_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-e", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let events = std::sync::Arc::new(std::sync::Mutex::new(vec![]));
+    let pty = pty_process::filter::Filtered::new(pty).filter(
+        pty_process::shell::events({
+            let events = std::sync::Arc::clone(&events);
+            move |event| events.lock().unwrap().push(event)
+        }),
+    );
+    let (pty_r, mut pty_w) = tokio::io::split(pty);
+    let mut output = helpers::output_async(pty_r);
+    while !output.next().await.unwrap().ends_with("done\r\n") {}
+    assert_eq!(*events.lock().unwrap(), expected());
+
+    pty_w.write_all(b"\n").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_shell_session() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("bash")
+        .args(["--norc", "--noprofile"])
+        .spawn(pts)
+        .unwrap();
+
+    let mut session = pty_process::shell::ShellSession::new(pty);
+    let output = session.run("echo foo; echo bar").unwrap();
+    assert_eq!(output.output, b"foo\r\nbar\r\n");
+    assert!(output.success());
+
+    let output = session.run("cd /; FOO=\"it's\"").unwrap();
+    assert_eq!(output.output, b"");
+    assert!(output.success());
+
+    let output = session.run("pwd; printf %s \"$FOO\"").unwrap();
+    assert_eq!(output.output, b"/\r\nit's");
+
+    let output = session.run("echo oops >&2; (exit 3)").unwrap();
+    assert_eq!(output.output, b"oops\r\n");
+    assert_eq!(output.status, 3);
+
+    session.get_mut().write_all(b"exit 0\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_shell_session_async() {
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("bash")
+        .args(["--norc", "--noprofile"])
+        .spawn(pts)
+        .unwrap();
+
+    let mut session = pty_process::shell::ShellSession::new(pty);
+    let output = session.run_async("export FOO=bar; false").await.unwrap();
+    assert_eq!(output.output, b"");
+    assert_eq!(output.status, 1);
+
+    let output = session.run_async("echo $FOO # comment").await.unwrap();
+    assert_eq!(output.output, b"bar\r\n");
+    assert!(output.success());
+
+    session.get_mut().write_all(b"exit 0\n").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
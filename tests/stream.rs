@@ -0,0 +1,141 @@
+mod helpers;
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_lines() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-E", "say for qw(foo bar baz); <STDIN>"])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, mut pty_w) = pty.split();
+    let lines: Vec<_> = pty_r
+        .lines()
+        .take(3)
+        .map(|line| line.unwrap())
+        .collect()
+        .await;
+    assert_eq!(lines, ["foo", "bar", "baz"]);
+
+    pty_w.write_all(b"\n").await.unwrap();
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_chunks() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-E", "$| = 1; print q(abcdefghij); <STDIN>"])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    let mut chunks = pty_r.into_chunks(4);
+    let mut output = vec![];
+    while output.len() < 10 {
+        let chunk = chunks.next().await.unwrap().unwrap();
+        assert!(chunk.len() <= 4);
+        output.extend_from_slice(&chunk);
+    }
+    assert_eq!(output, b"abcdefghij");
+
+    pty_w.write_all(b"\n").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_streams_end_when_child_exits() {
+    use futures::stream::StreamExt as _;
+
+    let (mut pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("printf")
+        .arg("foo\\nbar")
+        .spawn(pts)
+        .unwrap();
+    assert!(child.wait().await.unwrap().success());
+
+    // the partial last line is returned, rather than an error
+    let (pty_r, _) = pty.split();
+    let lines: Vec<_> =
+        pty_r.lines().map(|line| line.unwrap()).collect().await;
+    assert_eq!(lines, ["foo", "bar"]);
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("printf")
+        .arg("foo\\nbar")
+        .spawn(pts)
+        .unwrap();
+    assert!(child.wait().await.unwrap().success());
+
+    let (pty_r, _) = pty.into_split();
+    let chunks: Vec<_> = pty_r
+        .into_chunks(4)
+        .map(|chunk| chunk.unwrap())
+        .collect()
+        .await;
+    assert_eq!(chunks.concat(), b"foo\r\nbar");
+}
+
+#[cfg(feature = "codec")]
+#[tokio::test]
+async fn test_codec() {
+    use futures::{SinkExt as _, StreamExt as _};
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args([
+            "-e",
+            "system 'stty raw -echo'; $| = 1; \
+            print qq(\\e[?1h\\e[3); sleep 0.1; print qq(1mcaf\\xc3); \
+            sleep 0.1; print qq(\\xa9\\r\\n); \
+            sysread STDIN, my $buf, 3; \
+            print unpack('H*', $buf), qq(\\r\\n); \
+            system 'stty sane'",
+        ])
+        .spawn(pts)
+        .unwrap();
+
+    let mut framed = tokio_util::codec::Framed::new(
+        pty,
+        pty_process::codec::PtyCodec::new(),
+    );
+    let mut output = vec![];
+    while !output.ends_with(b"\r\n") {
+        let chunk = framed.next().await.unwrap().unwrap();
+        // chunks never end partway through an escape sequence or a
+        // multibyte character
+        assert!(!chunk.ends_with(b"\x1b[3"));
+        assert!(std::str::from_utf8(&chunk).is_ok());
+        output.extend_from_slice(&chunk);
+    }
+    assert_eq!(output, "\x1b[?1h\x1b[31mcaf\u{e9}\r\n".as_bytes());
+    assert!(framed.codec().modes().application_cursor());
+
+    framed
+        .send(pty_process::keys::KeyPress::from(
+            pty_process::keys::Key::Up,
+        ))
+        .await
+        .unwrap();
+    assert_eq!(framed.next().await.unwrap().unwrap(), &b"1b4f41\r\n"[..]);
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
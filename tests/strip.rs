@@ -0,0 +1,110 @@
+mod helpers;
+
+const SCRIPT: &str = "print qq(\\e]0;title\\a\\e[1;31mred\\e[m plain\\n), \
+    qq(progress 10%\\rprogress 100%\\n), \
+    qq(50%\\r\\e[Kdone\\e[?25h\\n), \
+    qq(ab\\bc\\n), \
+    qq(\\xc3\\xa9t\\xc3\\xa9\\rE\\n); \
+    <STDIN>";
+
+fn read_line(r: &mut impl std::io::BufRead) -> String {
+    let mut buf = vec![];
+    r.read_until(b'\n', &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+#[test]
+fn test_strip_blocking() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-E", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output =
+        std::io::BufReader::new(pty_process::strip::StripAnsi::new(&pty));
+    assert_eq!(read_line(&mut output), "red plain\r\n");
+    assert_eq!(read_line(&mut output), "progress 10%\rprogress 100%\r\n");
+    assert_eq!(read_line(&mut output), "50%\rdone\r\n");
+    assert_eq!(read_line(&mut output), "ab\x08c\r\n");
+    assert_eq!(read_line(&mut output), "\u{e9}t\u{e9}\rE\r\n");
+
+    (&pty).write_all(b"\n").unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_strip_overwrite_blocking() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-E", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = std::io::BufReader::new(
+        pty_process::strip::StripAnsi::new(&pty).overwrite(true),
+    );
+    assert_eq!(read_line(&mut output), "red plain\n");
+    assert_eq!(read_line(&mut output), "progress 100%\n");
+    assert_eq!(read_line(&mut output), "done\n");
+    assert_eq!(read_line(&mut output), "ac\n");
+    assert_eq!(read_line(&mut output), "Et\u{e9}\n");
+
+    (&pty).write_all(b"\n").unwrap();
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_strip_overwrite_huge_movement() {
+    use std::io::Read as _;
+
+    let mut output = pty_process::strip::StripAnsi::new(
+        &b"a\x1b[99999999999Cb\x1b[18446744073709551615Cc\x1b[99999999999Gd\n"
+            [..],
+    )
+    .overwrite(true);
+    let mut line = vec![];
+    output.read_to_end(&mut line).unwrap();
+    assert!(line.len() < 64 * 1024);
+    assert!(line.starts_with(b"a "));
+    assert!(line.ends_with(b" bcd\n"));
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_strip_async() {
+    use futures::stream::StreamExt as _;
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-E", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    let mut output = helpers::output_async(
+        pty_process::strip::StripAnsi::new(pty_r).overwrite(true),
+    );
+    assert_eq!(output.next().await.unwrap(), "red plain\n");
+    assert_eq!(output.next().await.unwrap(), "progress 100%\n");
+    assert_eq!(output.next().await.unwrap(), "done\n");
+    assert_eq!(output.next().await.unwrap(), "ac\n");
+    assert_eq!(output.next().await.unwrap(), "Et\u{e9}\n");
+
+    pty_w.write_all(b"\n").await.unwrap();
+
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
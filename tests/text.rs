@@ -0,0 +1,131 @@
+mod helpers;
+
+// prints multibyte characters split across separate writes, followed by
+// invalid utf8
+const SCRIPT: &str = "$| = 1; \
+    print qq(caf\\xc3); select undef, undef, undef, 0.1; \
+    print qq(\\xa9 \\xe2\\x82); select undef, undef, undef, 0.1; \
+    print qq(\\xac\\n); select undef, undef, undef, 0.1; \
+    print qq[a\\xffb\\xc3(\\n]; <STDIN>";
+
+#[test]
+fn test_utf8_decoder() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-e", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = pty_process::text::Utf8Decoder::new(&pty);
+    let mut line = String::new();
+    while !line.ends_with('\n') {
+        line.push_str(&output.read_chunk().unwrap().unwrap());
+    }
+    assert_eq!(line, "caf\u{e9} \u{20ac}\r\n");
+    assert_eq!(
+        output.read_line().unwrap().unwrap(),
+        "a\u{fffd}b\u{fffd}(\r\n"
+    );
+
+    (&pty).write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_utf8_decoder_invalid() {
+    use std::io::Write as _;
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .args(["-e", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let mut output = pty_process::text::Utf8Decoder::new(&pty)
+        .invalid(pty_process::text::InvalidUtf8::Error);
+    assert_eq!(
+        output.read_line().unwrap().unwrap(),
+        "caf\u{e9} \u{20ac}\r\n"
+    );
+    let mut errors = 0;
+    let line = loop {
+        match output.read_line() {
+            Ok(line) => break line.unwrap(),
+            Err(e) => {
+                assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+                errors += 1;
+            }
+        }
+    };
+    assert_eq!(line, "ab(\r\n");
+    assert_eq!(errors, 2);
+
+    (&pty).write_all(b"\n").unwrap();
+    let status = child.wait().unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[test]
+fn test_utf8_decoder_eof() {
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::blocking::Command::new("printf")
+        .arg("foo\\nbar\\303")
+        .spawn(pts)
+        .unwrap();
+    assert!(child.wait().unwrap().success());
+
+    // the partial last line is still returned once the child has exited
+    let mut output = pty_process::text::Utf8Decoder::new(&pty);
+    assert_eq!(output.read_line().unwrap().unwrap(), "foo\r\n");
+    assert_eq!(output.read_line().unwrap().unwrap(), "bar\u{fffd}");
+    assert!(output.read_line().unwrap().is_none());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_utf8_decoder_async() {
+    use tokio::io::AsyncWriteExt as _;
+
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .args(["-e", SCRIPT])
+        .spawn(pts)
+        .unwrap();
+
+    let (pty_r, mut pty_w) = pty.into_split();
+    let mut output = pty_process::text::Utf8Decoder::new(pty_r)
+        .invalid(pty_process::text::InvalidUtf8::Skip);
+    assert_eq!(
+        output.read_line_async().await.unwrap().unwrap(),
+        "caf\u{e9} \u{20ac}\r\n"
+    );
+    assert_eq!(output.read_chunk_async().await.unwrap().unwrap(), "ab(\r\n");
+
+    pty_w.write_all(b"\n").await.unwrap();
+    let status = child.wait().await.unwrap();
+    assert_eq!(status.code().unwrap(), 0);
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_utf8_decoder_eof_async() {
+    let (pty, pts) = pty_process::open().unwrap();
+    pty.resize(pty_process::Size::new(24, 80)).unwrap();
+    let mut child = pty_process::Command::new("printf")
+        .arg("foo\\nbar")
+        .spawn(pts)
+        .unwrap();
+    assert!(child.wait().await.unwrap().success());
+
+    let mut output = pty_process::text::Utf8Decoder::new(pty);
+    assert_eq!(output.read_line_async().await.unwrap().unwrap(), "foo\r\n");
+    assert_eq!(output.read_line_async().await.unwrap().unwrap(), "bar");
+    assert!(output.read_line_async().await.unwrap().is_none());
+}
@@ -0,0 +1,127 @@
+const SCRIPT: &str = "$| = 1; my $l = <STDIN>; print qq(got $l); \
+                      system 'stty -echo'; $l = <STDIN>; print qq(got $l)";
+
+#[test]
+fn test_transcript() {
+    use std::io::{BufRead as _, Write as _};
+
+    let (pty, pts) = pty_process::blocking::open().unwrap();
+    pty.set_read_timeout(Some(std::time::Duration::from_secs(5)))
+        .unwrap();
+    let mut child = pty_process::blocking::Command::new("perl")
+        .arg(format!("-E{SCRIPT}"))
+        .spawn(pts)
+        .unwrap();
+
+    let transcript = pty_process::transcript::Transcript::new();
+    let mut pty = std::io::BufReader::new(transcript.wrap(pty));
+    pty.get_mut().write_all(b"foo\n").unwrap();
+    let mut output = vec![];
+    while !output.ends_with(b"got foo\r\n") {
+        pty.read_until(b'\n', &mut output).unwrap();
+    }
+    assert!(
+        pty.get_mut()
+            .get_ref()
+            .wait_for_password_prompt(None)
+            .unwrap()
+    );
+    pty.get_mut()
+        .send_secret(&pty_process::SecretBytes::from("hunter2\n".to_string()))
+        .unwrap();
+    let mut line = vec![];
+    pty.read_until(b'\n', &mut line).unwrap();
+    assert_eq!(line, b"got hunter2\r\n");
+    assert!(child.wait().unwrap().success());
+
+    let entries = transcript.entries();
+    assert_eq!(
+        entries[0].event,
+        pty_process::transcript::Event::Input(b"foo\n".to_vec())
+    );
+    assert!(entries.iter().any(|entry| {
+        entry.event == pty_process::transcript::Event::RedactedInput(8)
+    }));
+    assert!(
+        entries
+            .windows(2)
+            .all(|entries| entries[0].elapsed <= entries[1].elapsed)
+    );
+    assert_eq!(
+        transcript.data(pty_process::transcript::Direction::Input),
+        b"foo\n"
+    );
+    assert_eq!(
+        transcript.data(pty_process::transcript::Direction::Output),
+        b"foo\r\ngot foo\r\ngot hunter2\r\n"
+    );
+    assert_eq!(
+        transcript.entries_between(..entries[1].elapsed),
+        &entries[..1]
+    );
+
+    let mut json = vec![];
+    transcript.write_json_lines(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+    assert_eq!(json.lines().count(), transcript.len());
+    let re = regex::Regex::new(
+        r#"^\{"time":\d+\.\d{6},"direction":"input","data":"foo\\n"\}$"#,
+    )
+    .unwrap();
+    assert!(re.is_match(json.lines().next().unwrap()));
+    assert!(json.contains(r#""direction":"input","redacted":8}"#));
+    assert!(!json.contains("hunter2\\n\""));
+
+    let mut log = vec![];
+    transcript.write_log(&mut log).unwrap();
+    let log = String::from_utf8(log).unwrap();
+    let re = regex::Regex::new(r"^\[ +\d+\.\d{6}\] > foo\\n$").unwrap();
+    assert!(re.is_match(log.lines().next().unwrap()));
+    assert!(log.contains("] > <8 bytes redacted>\n"));
+    assert!(log.contains("] < got foo\\r\\n\n"));
+
+    transcript.clear();
+    assert!(transcript.is_empty());
+}
+
+#[cfg(feature = "async")]
+#[tokio::test]
+async fn test_transcript_async() {
+    use tokio::io::{AsyncBufReadExt as _, AsyncWriteExt as _};
+
+    let (pty, pts) = pty_process::open().unwrap();
+    let mut child = pty_process::Command::new("perl")
+        .arg(
+            "-E$| = 1; system 'stty -echo'; say 'ready'; \
+             my $l = <STDIN>; print qq(got $l)",
+        )
+        .spawn(pts)
+        .unwrap();
+
+    let transcript = pty_process::transcript::Transcript::new();
+    let mut pty = tokio::io::BufReader::new(transcript.wrap(pty));
+    let mut line = vec![];
+    pty.read_until(b'\n', &mut line).await.unwrap();
+    assert_eq!(line, b"ready\r\n");
+    pty.get_mut().write_all(b"foo\n").await.unwrap();
+    line.clear();
+    pty.read_until(b'\n', &mut line).await.unwrap();
+    assert_eq!(line, b"got foo\r\n");
+    assert!(child.wait().await.unwrap().success());
+
+    // output can be split across several reads
+    let mut directions: Vec<_> = transcript
+        .entries()
+        .iter()
+        .map(pty_process::transcript::Entry::direction)
+        .collect();
+    directions.dedup();
+    assert_eq!(
+        directions,
+        [
+            pty_process::transcript::Direction::Output,
+            pty_process::transcript::Direction::Input,
+            pty_process::transcript::Direction::Output,
+        ]
+    );
+}